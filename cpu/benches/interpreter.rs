@@ -0,0 +1,78 @@
+// cpu/benches/interpreter.rs
+
+//! Velocidad del intérprete: instrucciones por segundo sobre un bus plano.
+//!
+//! `cargo bench -p cpu` guarda los resultados en `target/criterion`; la siguiente
+//! ejecución los compara con la anterior (así se mide cualquier cambio al decoder).
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use cpu::{Cpu, MemoryInterface};
+
+/// Bus mínimo: 64KB planos, sin registrar accesos (a diferencia del FlatBus de los tests).
+struct BenchBus {
+    ram: Vec<u8>,
+}
+
+impl MemoryInterface for BenchBus {
+    fn read(&mut self, addr: u16) -> u8 { self.ram[addr as usize] }
+    fn write(&mut self, addr: u16, val: u8) { self.ram[addr as usize] = val; }
+    fn peek(&self, addr: u16) -> u8 { self.ram[addr as usize] }
+    fn tick(&mut self) {}
+    fn pending_interrupts(&self) -> u8 { 0 }
+    fn clear_interrupt(&mut self, _mask: u8) {}
+}
+
+/// Bucle sintético con una mezcla típica: cargas, ALU, CB, CALL/RET, PUSH/POP y saltos.
+const PROGRAM: &[(u16, &[u8])] = &[
+    (0x0100, &[
+        0x21, 0x00, 0xC0, // ld hl, $C000
+        0x06, 0x40,       // ld b, $40
+        0x2A,             // .loop: ld a, [hl+]
+        0x80,             // add a, b
+        0xA9,             // xor a, c
+        0x4F,             // ld c, a
+        0xCB, 0x37,       // swap a
+        0xCB, 0x11,       // rl c
+        0xCB, 0x5E,       // bit 3, [hl]
+        0xCD, 0x20, 0x01, // call $0120
+        0x05,             // dec b
+        0x20, 0xF0,       // jr nz, .loop
+        0xC3, 0x00, 0x01, // jp $0100
+    ]),
+    (0x0120, &[
+        0xC5, // push bc
+        0x13, // inc de
+        0xC1, // pop bc
+        0xC9, // ret
+    ]),
+];
+
+const STEPS: u64 = 100_000;
+
+fn interpreter(c: &mut Criterion) {
+    let mut bus = BenchBus { ram: vec![0; 0x10000] };
+    for (addr, code) in PROGRAM {
+        let start = *addr as usize;
+        bus.ram[start..start + code.len()].copy_from_slice(code);
+    }
+    let mut cpu = Cpu::new();
+    cpu.regs.pc = 0x0100;
+    cpu.regs.sp = 0xDFFF;
+
+    let mut group = c.benchmark_group("interpreter");
+    group.throughput(Throughput::Elements(STEPS));
+    group.bench_function("step", |b| {
+        b.iter(|| {
+            let mut cycles = 0;
+            for _ in 0..STEPS {
+                cycles += cpu.step(&mut bus);
+            }
+            black_box(cycles)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, interpreter);
+criterion_main!(benches);
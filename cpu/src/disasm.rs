@@ -0,0 +1,209 @@
+// cpu/src/disasm.rs
+
+//! Desensamblador SM83 con sintaxis RGBDS (`ld a, [hl+]`, `ldh [$FF44], a`, `bit 7, h`).
+//!
+//! No depende del Bus: lee la memoria a través de un callback `FnMut(u16) -> u8`,
+//! así sirve igual para la ROM cruda, para el Bus en vivo o para tests.
+//! Es la base del depurador, del trace log y de cualquier herramienta.
+//!
+//! No decodifica por su cuenta: el texto sale de la misma tabla que ejecuta la CPU
+//! (`OPCODES`/`CB_OPCODES`), así que solo traduce cada `Op` a la sintaxis de RGBDS.
+
+use std::fmt;
+
+use crate::opcodes::{AluOp, Cond, Op, RotOp, CB_OPCODES, OPCODES, R16, R16Mem, R16Stk, R8};
+
+/// Nombres RGBDS de los operandos de la tabla ([HL] es memoria).
+fn r8(r: R8) -> &'static str {
+    match r {
+        R8::B => "b", R8::C => "c", R8::D => "d", R8::E => "e",
+        R8::H => "h", R8::L => "l", R8::HlInd => "[hl]", R8::A => "a",
+    }
+}
+
+fn r16(r: R16) -> &'static str {
+    match r { R16::BC => "bc", R16::DE => "de", R16::HL => "hl", R16::SP => "sp" }
+}
+
+fn r16_stk(r: R16Stk) -> &'static str {
+    match r { R16Stk::BC => "bc", R16Stk::DE => "de", R16Stk::HL => "hl", R16Stk::AF => "af" }
+}
+
+fn r16_mem(r: R16Mem) -> &'static str {
+    match r { R16Mem::BC => "[bc]", R16Mem::DE => "[de]", R16Mem::HLI => "[hl+]", R16Mem::HLD => "[hl-]" }
+}
+
+fn cond(c: Cond) -> &'static str {
+    match c { Cond::NZ => "nz", Cond::Z => "z", Cond::NC => "nc", Cond::C => "c", Cond::Always => "" }
+}
+
+/// Salto con destino: las versiones incondicionales no llevan condición (`jp $0150`).
+fn jump(mnemonic: &str, cc: Cond, dest: String) -> String {
+    match cc {
+        Cond::Always => format!("{} {}", mnemonic, dest),
+        _ => format!("{} {}, {}", mnemonic, cond(cc), dest),
+    }
+}
+
+fn alu(op: AluOp) -> &'static str {
+    match op {
+        AluOp::Add => "add", AluOp::Adc => "adc", AluOp::Sub => "sub", AluOp::Sbc => "sbc",
+        AluOp::And => "and", AluOp::Xor => "xor", AluOp::Or => "or", AluOp::Cp => "cp",
+    }
+}
+
+fn rot(op: RotOp) -> &'static str {
+    match op {
+        RotOp::Rlc => "rlc", RotOp::Rrc => "rrc", RotOp::Rl => "rl", RotOp::Rr => "rr",
+        RotOp::Sla => "sla", RotOp::Sra => "sra", RotOp::Swap => "swap", RotOp::Srl => "srl",
+    }
+}
+
+/// Una instrucción decodificada.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    /// Dirección del primer byte.
+    pub addr: u16,
+    /// Bytes crudos (1 a 3; 2 para los opcodes CB).
+    pub bytes: Vec<u8>,
+    /// Texto RGBDS, con el destino de salto ya resuelto (y simbólico si hay etiqueta).
+    pub text: String,
+    /// Destino absoluto de JP/JR/CALL/RST (None si es indirecto, como `jp hl`, o no salta).
+    pub target: Option<u16>,
+    /// Etiqueta de la propia dirección, si el callback de símbolos la conoce.
+    pub label: Option<String>,
+}
+
+impl Instruction {
+    /// Longitud en bytes.
+    pub fn len(&self) -> u16 {
+        self.bytes.len() as u16
+    }
+
+    /// Siempre tiene al menos el opcode; acompaña a `len` por convención de Rust.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// Formato de listado: `$0150: 3E 12     ld a, $12`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: Vec<String> = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        write!(f, "${:04X}: {:<9} {}", self.addr, bytes.join(" "), self.text)
+    }
+}
+
+/// Longitud en bytes de un opcode base (los CB siempre ocupan 2).
+/// Los opcodes ilegales ocupan 1 (se listan como `db`).
+pub fn instruction_length(opcode: u8) -> u16 {
+    OPCODES[opcode as usize].len as u16
+}
+
+/// Decodifica la instrucción en 'addr'.
+/// 'symbols' traduce una dirección a etiqueta (`Main.loop`); con `None` se usan direcciones.
+pub fn decode<R>(addr: u16, mut read: R, symbols: Option<&dyn Fn(u16) -> Option<String>>) -> Instruction
+where
+    R: FnMut(u16) -> u8,
+{
+    let opcode = read(addr);
+    let len = instruction_length(opcode);
+    let bytes: Vec<u8> = (0..len).map(|i| read(addr.wrapping_add(i))).collect();
+    let n8 = bytes.get(1).copied().unwrap_or(0);
+    let n16 = (bytes.get(2).copied().unwrap_or(0) as u16) << 8 | n8 as u16;
+    let next = addr.wrapping_add(len);
+
+    // Nombre simbólico de una dirección, o `$XXXX` si no hay etiqueta.
+    let name = |target: u16| -> String {
+        symbols.and_then(|lookup| lookup(target)).unwrap_or_else(|| format!("${:04X}", target))
+    };
+
+    let mut target = None;
+    let text = match OPCODES[opcode as usize].op {
+        Op::Nop => "nop".to_string(),
+        Op::Stop => "stop".to_string(),
+        Op::Halt => "halt".to_string(),
+        Op::Illegal => format!("db ${:02X}", opcode),
+        Op::Prefix => decode_cb(n8),
+
+        Op::LdR8R8(dst, src) => format!("ld {}, {}", r8(dst), r8(src)),
+        Op::LdR8N(dst) => format!("ld {}, ${:02X}", r8(dst), n8),
+        Op::LdR16N(dst) => format!("ld {}, ${:04X}", r16(dst), n16),
+        Op::LdMemA(mem) => format!("ld {}, a", r16_mem(mem)),
+        Op::LdAMem(mem) => format!("ld a, {}", r16_mem(mem)),
+        Op::LdNnSp => format!("ld [${:04X}], sp", n16),
+        Op::LdNnA => format!("ld [${:04X}], a", n16),
+        Op::LdANn => format!("ld a, [${:04X}]", n16),
+        Op::LdhNA => format!("ldh [${:04X}], a", 0xFF00 | n8 as u16),
+        Op::LdhAN => format!("ldh a, [${:04X}]", 0xFF00 | n8 as u16),
+        Op::LdhCA => "ldh [c], a".to_string(),
+        Op::LdhAC => "ldh a, [c]".to_string(),
+        Op::LdSpHl => "ld sp, hl".to_string(),
+        Op::LdHlSpE => format!("ld hl, sp{:+}", n8 as i8),
+        Op::Push(reg) => format!("push {}", r16_stk(reg)),
+        Op::Pop(reg) => format!("pop {}", r16_stk(reg)),
+
+        Op::IncR8(reg) => format!("inc {}", r8(reg)),
+        Op::DecR8(reg) => format!("dec {}", r8(reg)),
+        Op::IncR16(reg) => format!("inc {}", r16(reg)),
+        Op::DecR16(reg) => format!("dec {}", r16(reg)),
+        Op::AddHl(reg) => format!("add hl, {}", r16(reg)),
+        Op::AddSpE => format!("add sp, {}", n8 as i8),
+        Op::AluR8(op, reg) => format!("{} a, {}", alu(op), r8(reg)),
+        Op::AluN(op) => format!("{} a, ${:02X}", alu(op), n8),
+        // RLCA/RRCA/RLA/RRA: el nombre de la rotación con una 'a' pegada.
+        Op::RotA(op) => format!("{}a", rot(op)),
+        Op::Daa => "daa".to_string(),
+        Op::Cpl => "cpl".to_string(),
+        Op::Scf => "scf".to_string(),
+        Op::Ccf => "ccf".to_string(),
+        Op::Di => "di".to_string(),
+        Op::Ei => "ei".to_string(),
+
+        // JR: el destino es relativo a la instrucción siguiente.
+        Op::Jr(cc) => {
+            let dest = next.wrapping_add(n8 as i8 as u16);
+            target = Some(dest);
+            jump("jr", cc, name(dest))
+        }
+        Op::Jp(cc) => { target = Some(n16); jump("jp", cc, name(n16)) }
+        Op::Call(cc) => { target = Some(n16); jump("call", cc, name(n16)) }
+        Op::JpHl => "jp hl".to_string(),
+        Op::Ret(Cond::Always) => "ret".to_string(),
+        Op::Ret(cc) => format!("ret {}", cond(cc)),
+        Op::Reti => "reti".to_string(),
+        Op::Rst(vector) => { target = Some(vector); format!("rst ${:02X}", vector) }
+
+        // Las operaciones CB solo aparecen en CB_OPCODES.
+        Op::Rot(..) | Op::Bit(..) | Op::Res(..) | Op::Set(..) => unreachable!(),
+    };
+
+    Instruction { addr, bytes, text, target, label: symbols.and_then(|lookup| lookup(addr)) }
+}
+
+/// Texto de un opcode con prefijo CB.
+fn decode_cb(cb: u8) -> String {
+    match CB_OPCODES[cb as usize].op {
+        Op::Rot(op, reg) => format!("{} {}", rot(op), r8(reg)),
+        Op::Bit(bit, reg) => format!("bit {}, {}", bit, r8(reg)),
+        Op::Res(bit, reg) => format!("res {}, {}", bit, r8(reg)),
+        Op::Set(bit, reg) => format!("set {}, {}", bit, r8(reg)),
+        _ => unreachable!(),
+    }
+}
+
+/// Desensambla desde 'start' hasta 'end' (inclusive), instrucción por instrucción.
+/// Una instrucción que empieza antes de 'end' se lista completa aunque lo sobrepase.
+pub fn disassemble<R>(start: u16, end: u16, mut read: R, symbols: Option<&dyn Fn(u16) -> Option<String>>) -> Vec<Instruction>
+where
+    R: FnMut(u16) -> u8,
+{
+    let mut out = Vec::new();
+    let mut addr = start as u32;
+    while addr <= end as u32 {
+        let ins = decode(addr as u16, &mut read, symbols);
+        addr += ins.len() as u32;
+        out.push(ins);
+    }
+    out
+}
@@ -1,5 +1,66 @@
 // cpu/src/lib.rs
-use memory::Bus;
+
+// Submódulos del crate. 'pub mod' los expone como cpu::disasm.
+pub mod disasm;
+pub mod opcodes;
+pub mod profile;
+pub mod symbols;
+pub mod trace;
+
+use opcodes::{AluOp, Cond, Op, RotOp, CB_OPCODES, OPCODES, R16, R16Mem, R16Stk, R8};
+use profile::Profiler;
+use savestate::{Reader, Snapshot, StateError, Writer};
+use trace::Tracer;
+
+/// Interfaz de memoria que ve la CPU.
+/// La CPU no necesita saber si detrás hay un Game Boy completo (`memory::Bus`
+/// con GPU, Joypad y cartucho) o 64KB de RAM plana para tests.
+/// Por eso este crate no depende de `memory`: es `memory` quien implementa el trait.
+///
+/// Cada M-Cycle de una instrucción es exactamente una llamada a `read`, `write`
+/// o `tick`, en el orden en que ocurren en el hardware.
+///
+/// Concepto Rust vs Go:
+/// Igual que `Mbc`, es un trait (interface). Pero aquí `Cpu::step` es *genérico*
+/// (`<M: MemoryInterface>`) en lugar de usar `dyn`: el compilador genera una versión
+/// de la CPU por cada tipo de bus (monomorfización), sin coste de llamada dinámica.
+pub trait MemoryInterface {
+    /// Lectura de un byte. '&mut self' porque leer puede tener efectos (registros I/O).
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, val: u8);
+    /// Lectura de un byte de instrucción en PC. Es un `read` normal (mismo M-Cycle);
+    /// solo existe para que un bus con mapa de cobertura distinga código de datos.
+    fn fetch(&mut self, addr: u16, _kind: Fetch) -> u8 { self.read(addr) }
+    /// Lectura sin efectos ni consumo de ciclos, para herramientas (trace, depurador).
+    fn peek(&self, addr: u16) -> u8;
+    /// Un M-Cycle interno de la CPU (sin acceso al bus): aritmética de 16 bits,
+    /// cálculo de saltos, espera de HALT, etc.
+    fn tick(&mut self);
+
+    /// Interrupciones pendientes y habilitadas (IE & IF, bits 0-4).
+    fn pending_interrupts(&self) -> u8;
+    /// Baja el bit 'mask' de IF al despachar una interrupción.
+    fn clear_interrupt(&mut self, mask: u8);
+
+    /// ¿Alguna línea del Joypad (P10-P13) está en bajo? Es lo único que saca
+    /// a la CPU del modo STOP. Un bus sin Joypad nunca la despierta.
+    fn joypad_line_low(&self) -> bool { false }
+    /// La CPU entra en STOP: el bus resetea DIV (y en CGB haría el cambio de velocidad de KEY1).
+    fn stop(&mut self) {}
+
+    /// Banco de ROM mapeado en 0x4000-0x7FFF. Sin mapper, siempre el 1.
+    fn rom_bank(&self) -> u16 { 1 }
+    /// Banco de RAM del cartucho mapeado en 0xA000-0xBFFF. Sin mapper, el 0.
+    fn ram_bank(&self) -> u16 { 0 }
+}
+
+/// Qué byte de la instrucción se está leyendo en `MemoryInterface::fetch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fetch {
+    Opcode,
+    /// Operandos inmediatos y el segundo byte de los opcodes CB.
+    Operand,
+}
 
 /// Banderas del registro F (Flags).
 /// En Game Boy, el registro F contiene 4 bits de estado que las instrucciones consultan.
@@ -20,6 +81,10 @@ pub struct Registers {
     pub pc: u16,          // Program Counter
 }
 
+impl Default for Registers {
+    fn default() -> Self { Self::new() }
+}
+
 impl Registers {
     pub fn new() -> Self {
         // Valores iniciales (Post-Bootrom).
@@ -52,11 +117,41 @@ impl Registers {
     pub fn set_af(&mut self, val: u16) { self.a = (val >> 8) as u8; self.f = (val as u8) & 0xF0; }
 }
 
+/// Eventos que la CPU reporta al frontend (y al depurador).
+/// No interrumpen `step` (que sigue devolviendo ciclos): se consultan con `take_event`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CpuEvent {
+    /// Se ejecutó uno de los 11 opcodes que no existen en el SM83.
+    /// El hardware real se cuelga: casi siempre significa que la emulación se desvió.
+    IllegalOpcode { pc: u16, opcode: u8 },
+    /// Se despachó una interrupción: PC ya está en 'vector'
+    /// (0x0000 si el push sobre IE la canceló).
+    Interrupt { vector: u16 },
+}
+
 /// Estado global del CPU
 pub struct Cpu {
     pub regs: Registers,
     pub ime: bool,    // Interrupt Master Enable (Switch global de interrupciones)
     pub halted: bool, // Modo de bajo consumo (HALT instruction)
+    // EI no activa IME al instante: se activa después de la instrucción siguiente.
+    pub ei_pending: bool,
+    // "HALT bug": el próximo fetch de opcode no incrementa PC (el byte se lee dos veces).
+    pub halt_bug: bool,
+    // Modo STOP: CPU y LCD detenidos hasta que una línea del Joypad baje.
+    pub stopped: bool,
+    // CPU colgada por un opcode ilegal. Solo se sale con un reset.
+    pub locked: bool,
+    // Último evento sin consultar (Option: puede no haber ninguno).
+    event: Option<CpuEvent>,
+    // Trace log opcional (formato Gameboy Doctor). None = desactivado, sin coste.
+    pub tracer: Option<Tracer>,
+    // Profiler opcional (ciclos por función). None = desactivado, sin coste.
+    pub profiler: Option<Profiler>,
+}
+
+impl Default for Cpu {
+    fn default() -> Self { Self::new() }
 }
 
 impl Cpu {
@@ -65,18 +160,53 @@ impl Cpu {
             regs: Registers::new(),
             ime: false,
             halted: false,
+            ei_pending: false,
+            halt_bug: false,
+            stopped: false,
+            locked: false,
+            event: None,
+            tracer: None,
+            profiler: None,
         }
     }
 
+    /// Devuelve (y consume) el último evento de la CPU, si lo hubo.
+    /// 'take' deja 'None' en su lugar, como leer y vaciar un canal en Go.
+    pub fn take_event(&mut self) -> Option<CpuEvent> {
+        self.event.take()
+    }
+
     /// Ciclo principal: Fetch, Decode, Execute.
     /// Retorna el número de ciclos de máquina (M-Cycles) consumidos.
-    pub fn step(&mut self, bus: &mut Bus) -> u32 {
-        // 1. Verificar si estamos en modo HALT
+    pub fn step<M: MemoryInterface>(&mut self, bus: &mut M) -> u32 {
+        // 0. CPU colgada: el reloj sigue (GPU, Timer), pero no ejecuta nada
+        // ni atiende interrupciones.
+        if self.locked {
+            bus.tick();
+            if let Some(profiler) = self.profiler.as_mut() { profiler.idle(1); }
+            return 1;
+        }
+
+        // 1. Modo STOP: el reloj del sistema está parado (no hay tick: ni GPU ni Timer
+        // avanzan). Devolvemos 1 ciclo para que el frontend siga presentando frames.
+        if self.stopped {
+            if bus.joypad_line_low() {
+                self.stopped = false;
+            } else {
+                if let Some(profiler) = self.profiler.as_mut() { profiler.idle(1); }
+                return 1;
+            }
+        }
+
+        // Verificar si estamos en modo HALT
         if self.halted {
-            // Si hay interrupción pendiente, despertamos.
-            if self.ime && (bus.interrupt_flag & bus.interrupt_enable & 0x1F) != 0 {
+            // Si hay interrupción pendiente (IE & IF), despertamos.
+            // Da igual IME: con IME=0 la CPU sigue con la instrucción siguiente sin despachar.
+            if bus.pending_interrupts() != 0 {
                 self.halted = false;
             } else {
+                bus.tick();
+                if let Some(profiler) = self.profiler.as_mut() { profiler.idle(1); }
                 return 1; // CPU dormida, consume 1 ciclo sin hacer nada.
             }
         }
@@ -84,389 +214,209 @@ impl Cpu {
         // 2. Manejo de INTERRUPCIONES (Hardware Interrupts)
         if self.ime {
             // Un bit en 1 en ambos (Flag y Enable) dispara la interrupción.
-            let fired = bus.interrupt_flag & bus.interrupt_enable;
+            let fired = bus.pending_interrupts();
             
-            if (fired & 0x1F) != 0 {
+            if fired != 0 {
                 self.ime = false; // Deshabilitar interrupciones para evitar reentrancia infinita
                 self.halted = false;
 
-                // Push PC: Guardamos dirección de retorno en el stack
-                self.push(bus, self.regs.pc);
+                // Si EI;HALT disparó el HALT bug, la interrupción vuelve al propio HALT.
+                let mut ret_addr = self.regs.pc;
+                if self.halt_bug {
+                    self.halt_bug = false;
+                    ret_addr = ret_addr.wrapping_sub(1);
+                }
+
+                // 2 ciclos de espera antes de guardar PC.
+                bus.tick();
+                bus.tick();
+
+                // Push PC a mano: el vector se decide DESPUÉS de escribir el byte alto.
+                // Si SP apuntaba a 0x0000, ese byte pisa IE (0xFFFF) y puede cancelar
+                // la interrupción: entonces PC salta a 0x0000 (test ie_push de Mooneye).
+                self.regs.sp = self.regs.sp.wrapping_sub(1);
+                bus.write(self.regs.sp, (ret_addr >> 8) as u8);
+                let fired = bus.pending_interrupts();
+                self.regs.sp = self.regs.sp.wrapping_sub(1);
+                bus.write(self.regs.sp, ret_addr as u8);
 
                 // Priority Check hardcoded (hardware fixed priority)
-                if (fired & 0x01) != 0 {      // V-Blank
+                if fired == 0 {                 // Cancelada por el push
+                    self.regs.pc = 0x0000;
+                } else if (fired & 0x01) != 0 { // V-Blank
                     self.regs.pc = 0x0040;
-                    bus.interrupt_flag &= !0x01;
+                    bus.clear_interrupt(0x01);
                 } else if (fired & 0x02) != 0 { // LCD Stat
                     self.regs.pc = 0x0048;
-                    bus.interrupt_flag &= !0x02;
+                    bus.clear_interrupt(0x02);
                 } else if (fired & 0x04) != 0 { // Timer
                     self.regs.pc = 0x0050;
-                    bus.interrupt_flag &= !0x04;
+                    bus.clear_interrupt(0x04);
                 } else if (fired & 0x08) != 0 { // Serial
                     self.regs.pc = 0x0058;
-                    bus.interrupt_flag &= !0x08;
+                    bus.clear_interrupt(0x08);
                 } else if (fired & 0x10) != 0 { // Joypad
                     self.regs.pc = 0x0060;
-                    bus.interrupt_flag &= !0x10;
+                    bus.clear_interrupt(0x10);
+                }
+
+                self.event = Some(CpuEvent::Interrupt { vector: self.regs.pc });
+                bus.tick(); // Carga del vector en PC
+                if let Some(profiler) = self.profiler.as_mut() {
+                    profiler.interrupt(self.regs.pc, self.regs.sp.wrapping_add(2), 5);
                 }
-                
                 return 5; // ISR Dispatch toma 5 M-Cycles
             }
         }
 
+        // Trace: una línea por instrucción, con el estado ANTES de ejecutarla.
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(&self.regs, bus);
+        }
+
         // 3. FETCH: Leer opcode
-        let opcode = self.fetch(bus);
+        let sp = self.regs.sp; // Para el profiler: CALL/RET se detectan por el cambio de SP.
+        let opcode = self.fetch_as(bus, Fetch::Opcode);
+
+        // Si la instrucción anterior fue EI, IME se activa al terminar esta.
+        // (Si esta es DI, cancela el EI pendiente.)
+        let ei_armed = self.ei_pending;
+
+        // 4. DECODE & EXECUTE
+        let cycles = self.execute(opcode, bus);
+
+        if ei_armed && self.ei_pending {
+            self.ei_pending = false;
+            self.ime = true;
+        }
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.instruction(opcode, sp, &self.regs, cycles, bus);
+        }
+        cycles
+    }
 
-        // 4. DECODE & EXECUTE: El gran match de Rust
-        match opcode {
+    /// Ejecuta un opcode ya leído. Retorna los M-Cycles totales (incluido el fetch).
+    /// Operandos y ciclos salen de la tabla `OPCODES`; aquí solo se interpreta la operación.
+    fn execute<M: MemoryInterface>(&mut self, opcode: u8, bus: &mut M) -> u32 {
+        let info = &OPCODES[opcode as usize];
+        // Los saltos condicionales indican si se tomaron (cambia el número de ciclos).
+        let mut taken = false;
+
+        match info.op {
             // --- NOP & Control ---
-            0x00 => { 1 } // NOP
-            0x10 => { self.fetch(bus); 1 } // STOP (ignora siguiente byte)
-            0x76 => { self.halted = true; 1 } // HALT
-
-            // --- Cargas de 8 bits (Load) ---
-            0x06 => { self.regs.b = self.fetch(bus); 2 } // LD B, n
-            0x0E => { self.regs.c = self.fetch(bus); 2 }
-            0x16 => { self.regs.d = self.fetch(bus); 2 } // LD D, n
-            0x1E => { self.regs.e = self.fetch(bus); 2 }
-            0x26 => { self.regs.h = self.fetch(bus); 2 } // LD H, n
-            0x2E => { self.regs.l = self.fetch(bus); 2 }
-            
-            // LD (HL), n: Escribir en memoria apuntada por HL
-            0x36 => { let v = self.fetch(bus); bus.write(self.regs.get_hl(), v); 3 }
-            
-            // Stores y Loads indirectos
-            0x02 => { bus.write(self.regs.get_bc(), self.regs.a); 2 } // LD (BC), A
-            0x12 => { bus.write(self.regs.get_de(), self.regs.a); 2 } // LD (DE), A
-            0x0A => { self.regs.a = bus.read(self.regs.get_bc()); 2 } // LD A, (BC)
-            0x1A => { self.regs.a = bus.read(self.regs.get_de()); 2 } // LD A, (DE)
-
-            // LDI / LDD: Load and Increment/Decrement HL
-            0x22 => { bus.write(self.regs.get_hl(), self.regs.a); self.regs.set_hl(self.regs.get_hl().wrapping_add(1)); 2 }
-            0x2A => { self.regs.a = bus.read(self.regs.get_hl()); self.regs.set_hl(self.regs.get_hl().wrapping_add(1)); 2 }
-            0x32 => { bus.write(self.regs.get_hl(), self.regs.a); self.regs.set_hl(self.regs.get_hl().wrapping_sub(1)); 2 }
-            0x3A => { self.regs.a = bus.read(self.regs.get_hl()); self.regs.set_hl(self.regs.get_hl().wrapping_sub(1)); 2 }
-
-            0x3E => { self.regs.a = self.fetch(bus); 2 } // LD A, n
-
-            // LD r, r (Cargas registro a registro)
-            // Agrupamos el rango 0x40-0x7F y manejamos la excepción de HALT (0x76)
-            0x40..=0x7F => {
-                if opcode == 0x76 { self.halted = true; 1 }
-                else { self.execute_load_8bit(opcode, bus) }
-            }
+            Op::Nop => {}
+            Op::Stop => self.stop(bus),
+            Op::Halt => self.halt(bus),
+            Op::Illegal => self.lock_up(opcode),
+            Op::Prefix => return self.execute_cb(bus),
+
+            // --- Cargas de 8 bits ---
+            Op::LdR8R8(dst, src) => { let v = self.read_r8(bus, src); self.write_r8(bus, dst, v); }
+            Op::LdR8N(dst) => { let v = self.fetch(bus); self.write_r8(bus, dst, v); }
+            Op::LdMemA(mem) => { let addr = self.mem_addr(mem); bus.write(addr, self.regs.a); }
+            Op::LdAMem(mem) => { let addr = self.mem_addr(mem); self.regs.a = bus.read(addr); }
+            Op::LdNnA => { let addr = self.fetch_u16(bus); bus.write(addr, self.regs.a); }
+            Op::LdANn => { let addr = self.fetch_u16(bus); self.regs.a = bus.read(addr); }
+            // High RAM I/O: 0xFF00 + n / 0xFF00 + C
+            Op::LdhNA => { let off = self.fetch(bus) as u16; bus.write(0xFF00 + off, self.regs.a); }
+            Op::LdhAN => { let off = self.fetch(bus) as u16; self.regs.a = bus.read(0xFF00 + off); }
+            Op::LdhCA => bus.write(0xFF00 + (self.regs.c as u16), self.regs.a),
+            Op::LdhAC => self.regs.a = bus.read(0xFF00 + (self.regs.c as u16)),
 
             // --- Cargas de 16 bits ---
-            0x01 => { let v = self.fetch_u16(bus); self.regs.set_bc(v); 3 }
-            0x11 => { let v = self.fetch_u16(bus); self.regs.set_de(v); 3 }
-            0x21 => { let v = self.fetch_u16(bus); self.regs.set_hl(v); 3 }
-            0x31 => { self.regs.sp = self.fetch_u16(bus); 3 } // LD SP, nn
-            
-            // LD HL, SP+r8: Aritmética de punteros compleja
-            0xF8 => {
-                let offset = self.fetch(bus) as i8; // Cast a signed
-                let sp = self.regs.sp;
-                let res = sp.wrapping_add(offset as i16 as u16);
-                self.regs.set_hl(res);
-                self.regs.f = 0;
-                // Flags H y C funcionan raro con SP aritmetica (base 16 bits, flags 8 bits)
-                if (sp & 0xF) + (offset as u16 & 0xF) > 0xF { self.regs.f |= H_FLAG; }
-                if (sp & 0xFF) + (offset as u16 & 0xFF) > 0xFF { self.regs.f |= C_FLAG; }
-                3
-            }
-            
-            0xF9 => { self.regs.sp = self.regs.get_hl(); 2 } // LD SP, HL
-            
-            0x08 => { // LD (nn), SP
+            Op::LdR16N(rr) => { let v = self.fetch_u16(bus); self.write_r16(rr, v); }
+            Op::LdSpHl => { self.regs.sp = self.regs.get_hl(); bus.tick(); }
+            Op::LdNnSp => {
                 let addr = self.fetch_u16(bus);
                 let sp = self.regs.sp;
                 bus.write(addr, (sp & 0xFF) as u8);
                 bus.write(addr.wrapping_add(1), (sp >> 8) as u8);
-                5
             }
-            
-            // PUSH (Stack)
-            0xC5 => { self.push(bus, self.regs.get_bc()); 4 }
-            0xD5 => { self.push(bus, self.regs.get_de()); 4 }
-            0xE5 => { self.push(bus, self.regs.get_hl()); 4 }
-            0xF5 => { self.push(bus, self.regs.get_af()); 4 }
-            // POP
-            0xC1 => { let v = self.pop(bus); self.regs.set_bc(v); 3 }
-            0xD1 => { let v = self.pop(bus); self.regs.set_de(v); 3 }
-            0xE1 => { let v = self.pop(bus); self.regs.set_hl(v); 3 }
-            0xF1 => { let v = self.pop(bus); self.regs.set_af(v); 3 }
-
-            // --- ALU 8 bits (Aritmética) ---
-            // INC / DEC (Afectan Z, N, H. NO afectan C)
-            0x04 => { self.regs.b = self.inc(self.regs.b); 1 }
-            0x05 => { self.regs.b = self.dec(self.regs.b); 1 }
-            0x0C => { self.regs.c = self.inc(self.regs.c); 1 }
-            0x0D => { self.regs.c = self.dec(self.regs.c); 1 }
-            // ... (Repeticiones para D, E, H, L, A omitidas por brevedad, ver implementación completa)
-            0x14 => { self.regs.d = self.inc(self.regs.d); 1 }
-            0x15 => { self.regs.d = self.dec(self.regs.d); 1 }
-            0x1C => { self.regs.e = self.inc(self.regs.e); 1 }
-            0x1D => { self.regs.e = self.dec(self.regs.e); 1 }
-            0x24 => { self.regs.h = self.inc(self.regs.h); 1 }
-            0x25 => { self.regs.h = self.dec(self.regs.h); 1 }
-            0x2C => { self.regs.l = self.inc(self.regs.l); 1 }
-            0x2D => { self.regs.l = self.dec(self.regs.l); 1 }
-            0x3C => { self.regs.a = self.inc(self.regs.a); 1 }
-            0x3D => { self.regs.a = self.dec(self.regs.a); 1 }
-
-            0x34 => { // INC (HL)
-                let addr = self.regs.get_hl();
-                let v = bus.read(addr);
-                bus.write(addr, self.inc(v));
-                3
+            // LD HL, SP+e8: Aritmética de punteros
+            Op::LdHlSpE => {
+                let res = self.sp_plus_offset(bus);
+                self.regs.set_hl(res);
+                bus.tick(); // Suma de 16 bits
             }
-            0x35 => { // DEC (HL)
-                let addr = self.regs.get_hl();
-                let v = bus.read(addr);
-                bus.write(addr, self.dec(v));
-                3
+            Op::Push(rr) => { let v = self.read_r16_stk(rr); self.push(bus, v); }
+            Op::Pop(rr) => { let v = self.pop(bus); self.write_r16_stk(rr, v); }
+
+            // --- ALU 8 bits ---
+            // INC / DEC (Afectan Z, N, H. NO afectan C). Con [HL]: lectura + escritura.
+            Op::IncR8(r) => { let v = self.read_r8(bus, r); let res = self.inc(v); self.write_r8(bus, r, res); }
+            Op::DecR8(r) => { let v = self.read_r8(bus, r); let res = self.dec(v); self.write_r8(bus, r, res); }
+            Op::AluR8(alu, r) => { let v = self.read_r8(bus, r); self.alu(alu, v); }
+            Op::AluN(alu) => { let v = self.fetch(bus); self.alu(alu, v); }
+
+            // --- ALU 16 bits (Flags NO cambian en INC/DEC) ---
+            Op::AddHl(rr) => { self.add_hl(self.read_r16(rr)); bus.tick(); }
+            Op::IncR16(rr) => { self.write_r16(rr, self.read_r16(rr).wrapping_add(1)); bus.tick(); }
+            Op::DecR16(rr) => { self.write_r16(rr, self.read_r16(rr).wrapping_sub(1)); bus.tick(); }
+            Op::AddSpE => {
+                self.regs.sp = self.sp_plus_offset(bus);
+                bus.tick(); bus.tick(); // Suma de 16 bits en dos mitades
             }
 
-            // Operaciones ALU lógicas y aritméticas con acumulador (A)
-            // 0x80 - 0xBF
-            0x80..=0x87 => { self.add(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-            0x88..=0x8F => { self.adc(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-            0x90..=0x97 => { self.sub(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-            0x98..=0x9F => { self.sbc(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-            0xA0..=0xA7 => { self.and(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-            0xA8..=0xAF => { self.xor(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-            0xB0..=0xB7 => { self.or(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-            0xB8..=0xBF => { self.cp(self.get_reg_from_code(opcode & 0x07, bus)); 1 }
-
-            // Operaciones ALU Inmediatas (n)
-            0xC6 => { let v = self.fetch(bus); self.add(v); 2 }
-            0xCE => { let v = self.fetch(bus); self.adc(v); 2 }
-            0xD6 => { let v = self.fetch(bus); self.sub(v); 2 }
-            0xDE => { let v = self.fetch(bus); self.sbc(v); 2 }
-            0xE6 => { let v = self.fetch(bus); self.and(v); 2 }
-            0xEE => { let v = self.fetch(bus); self.xor(v); 2 }
-            0xF6 => { let v = self.fetch(bus); self.or(v); 2 }
-            0xFE => { let v = self.fetch(bus); self.cp(v); 2 }
-
-            // ALU 16 bits (ADD HL, rr)
-            0x09 => { self.add_hl(self.regs.get_bc()); 2 }
-            0x19 => { self.add_hl(self.regs.get_de()); 2 }
-            0x29 => { self.add_hl(self.regs.get_hl()); 2 }
-            0x39 => { self.add_hl(self.regs.sp); 2 }
-
-            // INC/DEC 16 bits (Note: Flags NO cambian)
-            0x03 => { self.regs.set_bc(self.regs.get_bc().wrapping_add(1)); 2 }
-            0x13 => { self.regs.set_de(self.regs.get_de().wrapping_add(1)); 2 }
-            0x23 => { self.regs.set_hl(self.regs.get_hl().wrapping_add(1)); 2 }
-            0x33 => { self.regs.sp = self.regs.sp.wrapping_add(1); 2 }
-            0x0B => { self.regs.set_bc(self.regs.get_bc().wrapping_sub(1)); 2 }
-            0x1B => { self.regs.set_de(self.regs.get_de().wrapping_sub(1)); 2 }
-            0x2B => { self.regs.set_hl(self.regs.get_hl().wrapping_sub(1)); 2 }
-            0x3B => { self.regs.sp = self.regs.sp.wrapping_sub(1); 2 }
-
-            // --- Saltos (Control Flow) ---
-            0xC3 => { self.regs.pc = self.fetch_u16(bus); 4 } // JP nn
-            0xE9 => { self.regs.pc = self.regs.get_hl(); 1 }  // JP (HL)
-            
-            // Saltos Relativos (JR)
-            0x18 => { self.jr(bus, true) }
-            0x20 => { self.jr(bus, !self.get_flag(Z_FLAG)) } // JR NZ, n
-            0x28 => { self.jr(bus, self.get_flag(Z_FLAG)) }  // JR Z, n
-            0x30 => { self.jr(bus, !self.get_flag(C_FLAG)) } // JR NC, n
-            0x38 => { self.jr(bus, self.get_flag(C_FLAG)) }  // JR C, n
-
-            // Saltos Absolutos (JP condicional)
-            0xC2 => { self.jp(bus, !self.get_flag(Z_FLAG)) }
-            0xCA => { self.jp(bus, self.get_flag(Z_FLAG)) }
-            0xD2 => { self.jp(bus, !self.get_flag(C_FLAG)) }
-            0xDA => { self.jp(bus, self.get_flag(C_FLAG)) }
-
-            // Calls
-            0xCD => { self.call(bus, true) } // CALL nn
-            0xC4 => { self.call(bus, !self.get_flag(Z_FLAG)) }
-            0xCC => { self.call(bus, self.get_flag(Z_FLAG)) }
-            0xD4 => { self.call(bus, !self.get_flag(C_FLAG)) }
-            0xDC => { self.call(bus, self.get_flag(C_FLAG)) }
-
-            // Returns
-            0xC9 => { self.ret(bus, true) } // RET
-            0xC0 => { self.ret(bus, !self.get_flag(Z_FLAG)) }
-            0xC8 => { self.ret(bus, self.get_flag(Z_FLAG)) }
-            0xD0 => { self.ret(bus, !self.get_flag(C_FLAG)) }
-            0xD8 => { self.ret(bus, self.get_flag(C_FLAG)) }
-            0xD9 => { self.regs.pc = self.pop(bus); self.ime = true; 4 } // RETI
-
-            // RST (Restart Vectors)
-            0xC7 => { self.rst(bus, 0x00); 4 }
-            0xCF => { self.rst(bus, 0x08); 4 }
-            0xD7 => { self.rst(bus, 0x10); 4 }
-            0xDF => { self.rst(bus, 0x18); 4 }
-            0xE7 => { self.rst(bus, 0x20); 4 }
-            0xEF => { self.rst(bus, 0x28); 4 }
-            0xF7 => { self.rst(bus, 0x30); 4 }
-            0xFF => { self.rst(bus, 0x38); 4 }
-
-            // --- Rotaciones de Acumulador (Legacy 8080) ---
-            0x07 => { // RLCA
-                let val = self.regs.a;
-                let carry = (val & 0x80) >> 7;
-                self.regs.a = (val << 1) | carry;
-                self.regs.f = 0; // Z es siempre 0 en RLCA hardware original
-                if carry != 0 { self.regs.f |= C_FLAG; }
-                1
-            }
-            0x0F => { // RRCA
-                let val = self.regs.a;
-                let carry = val & 0x01;
-                self.regs.a = (val >> 1) | (carry << 7);
-                self.regs.f = 0;
-                if carry != 0 { self.regs.f |= C_FLAG; }
-                1
-            }
-            0x17 => { // RLA (Rotate Left through Carry)
-                let val = self.regs.a;
-                let old_c = if self.get_flag(C_FLAG) { 1 } else { 0 };
-                let new_c = (val & 0x80) >> 7;
-                self.regs.a = (val << 1) | old_c;
-                self.regs.f = 0;
-                if new_c != 0 { self.regs.f |= C_FLAG; }
-                1
-            }
-            0x1F => { // RRA (Rotate Right through Carry)
-                let val = self.regs.a;
-                let old_c = if self.get_flag(C_FLAG) { 1 } else { 0 };
-                let new_c = val & 0x01;
-                self.regs.a = (val >> 1) | (old_c << 7);
-                self.regs.f = 0;
-                if new_c != 0 { self.regs.f |= C_FLAG; }
-                1
+            // --- Rotaciones de Acumulador (Legacy 8080): Z siempre 0 ---
+            Op::RotA(rot) => {
+                self.regs.a = self.rotate(rot, self.regs.a);
+                self.set_flag(Z_FLAG, false);
             }
 
             // --- Misc ---
-            0x27 => { self.daa(); 1 } // Decimal Adjust
-            0x2F => { self.regs.a = !self.regs.a; self.set_flag(N_FLAG, true); self.set_flag(H_FLAG, true); 1 } // CPL
-            0x37 => { self.set_flag(N_FLAG, false); self.set_flag(H_FLAG, false); self.set_flag(C_FLAG, true); 1 } // SCF
-            0x3F => { // CCF
-                self.set_flag(N_FLAG, false); 
-                self.set_flag(H_FLAG, false); 
-                let c = self.get_flag(C_FLAG); 
-                self.set_flag(C_FLAG, !c); 
-                1 
-            }
-            0xF3 => { self.ime = false; 1 } // DI: Disable Interrupts
-            0xFB => { self.ime = true; 1 }  // EI: Enable Interrupts (Delayed 1 instr en real hw, aquí directo)
-
-            // --- High RAM I/O ---
-            0xE0 => { let off = self.fetch(bus) as u16; bus.write(0xFF00 + off, self.regs.a); 3 } // LDH (n), A
-            0xF0 => { let off = self.fetch(bus) as u16; self.regs.a = bus.read(0xFF00 + off); 3 } // LDH A, (n)
-            0xE2 => { bus.write(0xFF00 + (self.regs.c as u16), self.regs.a); 2 } // LD (C), A
-            0xF2 => { self.regs.a = bus.read(0xFF00 + (self.regs.c as u16)); 2 } // LD A, (C)
-            0xEA => { let addr = self.fetch_u16(bus); bus.write(addr, self.regs.a); 4 } // LD (nn), A
-            0xFA => { let addr = self.fetch_u16(bus); self.regs.a = bus.read(addr); 4 } // LD A, (nn)
-            
-            0xE8 => { // ADD SP, e8
-                let offset = self.fetch(bus) as i8 as u16;
-                let sp = self.regs.sp;
-                let res = sp.wrapping_add(offset);
-                self.regs.f = 0;
-                if (sp & 0xF) + (offset & 0xF) > 0xF { self.regs.f |= H_FLAG; }
-                if (sp & 0xFF) + (offset & 0xFF) > 0xFF { self.regs.f |= C_FLAG; }
-                self.regs.sp = res;
-                4
+            Op::Daa => self.daa(), // Decimal Adjust
+            Op::Cpl => { self.regs.a = !self.regs.a; self.set_flag(N_FLAG, true); self.set_flag(H_FLAG, true); }
+            Op::Scf => { self.set_flag(N_FLAG, false); self.set_flag(H_FLAG, false); self.set_flag(C_FLAG, true); }
+            Op::Ccf => {
+                self.set_flag(N_FLAG, false);
+                self.set_flag(H_FLAG, false);
+                let c = self.get_flag(C_FLAG);
+                self.set_flag(C_FLAG, !c);
             }
+            Op::Di => { self.ime = false; self.ei_pending = false; } // DI: Disable Interrupts
+            Op::Ei => self.ei_pending = true, // EI: efectivo tras la siguiente instrucción
 
-            // --- PREFIX CB (Extensiones de Bitwise) ---
-            0xCB => { self.execute_cb(bus) }
-            
-            _ => { 
-                // Unhandled Opcode
-                // println!("Opcode Desconocido: {:#02X}", opcode);
-                1 
-            }
+            // --- Saltos (Control Flow) ---
+            Op::Jr(cc) => taken = self.jr(bus, self.condition(cc)),
+            Op::Jp(cc) => taken = self.jp(bus, self.condition(cc)),
+            Op::JpHl => self.regs.pc = self.regs.get_hl(),
+            Op::Call(cc) => taken = self.call(bus, self.condition(cc)),
+            Op::Ret(Cond::Always) => { self.regs.pc = self.pop(bus); bus.tick(); }
+            Op::Ret(cc) => taken = self.ret(bus, self.condition(cc)),
+            Op::Reti => { self.regs.pc = self.pop(bus); bus.tick(); self.ime = true; }
+            Op::Rst(vector) => self.rst(bus, vector),
+
+            // Las operaciones CB solo aparecen en CB_OPCODES.
+            Op::Rot(..) | Op::Bit(..) | Op::Res(..) | Op::Set(..) => unreachable!(),
         }
+
+        (if taken { info.cycles_taken } else { info.cycles }) as u32
     }
 
     /// Ejecuta instrucciones CB: Rotaciones extendidas, Shifts, Bits.
-    fn execute_cb(&mut self, bus: &mut Bus) -> u32 {
-        let opcode = self.fetch(bus);
-        let reg_idx = opcode & 0x07; // Últimos 3 bits dicen el registro
-        let mut val = self.get_reg_from_code(reg_idx, bus);
-        let cycles = if reg_idx == 6 { 4 } else { 2 }; // (HL) tarda más
-
-        match opcode {
-            // RLC r
-            0x00..=0x07 => { 
-                let carry = (val & 0x80) >> 7;
-                val = (val << 1) | carry;
-                self.regs.f = 0; 
-                if val == 0 { self.regs.f |= Z_FLAG; } 
-                if carry != 0 { self.regs.f |= C_FLAG; }
-            }
-            // RRC r
-            0x08..=0x0F => {
-                let carry = val & 0x01;
-                val = (val >> 1) | (carry << 7);
-                self.regs.f = 0; if val == 0 { self.regs.f |= Z_FLAG; } if carry != 0 { self.regs.f |= C_FLAG; }
-            }
-            // RL r
-            0x10..=0x17 => {
-                let old_c = if self.get_flag(C_FLAG) { 1 } else { 0 };
-                let new_c = (val & 0x80) >> 7;
-                val = (val << 1) | old_c;
-                self.regs.f = 0; if val == 0 { self.regs.f |= Z_FLAG; } if new_c != 0 { self.regs.f |= C_FLAG; }
-            }
-            // RR r
-            0x18..=0x1F => {
-                let old_c = if self.get_flag(C_FLAG) { 1 } else { 0 };
-                let new_c = val & 0x01;
-                val = (val >> 1) | (old_c << 7);
-                self.regs.f = 0; if val == 0 { self.regs.f |= Z_FLAG; } if new_c != 0 { self.regs.f |= C_FLAG; }
-            }
-            // SLA r (Shift Left Arithmetic)
-            0x20..=0x27 => {
-                let c = (val & 0x80) >> 7;
-                val <<= 1;
-                self.regs.f = 0; if val == 0 { self.regs.f |= Z_FLAG; } if c != 0 { self.regs.f |= C_FLAG; }
-            }
-            // SRA r (Shift Right Arithmetic - Keep sign)
-            0x28..=0x2F => {
-                let c = val & 0x01;
-                val = (val as i8 >> 1) as u8;
-                self.regs.f = 0; if val == 0 { self.regs.f |= Z_FLAG; } if c != 0 { self.regs.f |= C_FLAG; }
+    fn execute_cb<M: MemoryInterface>(&mut self, bus: &mut M) -> u32 {
+        let cb = self.fetch(bus);
+        let info = &CB_OPCODES[cb as usize];
+
+        match info.op {
+            Op::Rot(rot, r) => {
+                let v = self.read_r8(bus, r);
+                let res = self.rotate(rot, v);
+                self.write_r8(bus, r, res);
             }
-            // SWAP r
-            0x30..=0x37 => {
-                val = (val << 4) | (val >> 4);
-                self.regs.f = 0; if val == 0 { self.regs.f |= Z_FLAG; }
-            }
-            // SRL r (Shift Right Logical - Zero fill)
-            0x38..=0x3F => {
-                let c = val & 0x01;
-                val >>= 1;
-                self.regs.f = 0; if val == 0 { self.regs.f |= Z_FLAG; } if c != 0 { self.regs.f |= C_FLAG; }
-            }
-            // BIT b, r (Solo actualiza flags, no escribe val)
-            0x40..=0x7F => {
-                let bit = (opcode >> 3) & 0x07;
-                let zero = (val & (1 << bit)) == 0;
-                self.set_flag(Z_FLAG, zero);
+            // BIT b, r (Solo actualiza flags, no escribe)
+            Op::Bit(bit, r) => {
+                let v = self.read_r8(bus, r);
+                self.set_flag(Z_FLAG, (v & (1 << bit)) == 0);
                 self.set_flag(N_FLAG, false);
                 self.set_flag(H_FLAG, true);
-                return if reg_idx == 6 { 3 } else { 2 }; // Early return, no write back
-            }
-            // RES b, r (Reset bit)
-            0x80..=0xBF => {
-                val &= !(1 << ((opcode >> 3) & 0x07));
-            }
-            // SET b, r (Set bit)
-            0xC0..=0xFF => {
-                val |= 1 << ((opcode >> 3) & 0x07);
             }
+            Op::Res(bit, r) => { let v = self.read_r8(bus, r); self.write_r8(bus, r, v & !(1 << bit)); }
+            Op::Set(bit, r) => { let v = self.read_r8(bus, r); self.write_r8(bus, r, v | (1 << bit)); }
+            _ => unreachable!("CB_OPCODES solo contiene operaciones CB"),
         }
 
-        self.write_reg_cb(bus, reg_idx, val);
-        cycles
+        info.cycles as u32
     }
 
     // --- ALU / UTILIDADES --- en Rust usamos métodos privados (fn sin pub)
@@ -577,27 +527,79 @@ impl Cpu {
         self.set_flag(H_FLAG, false);
     }
 
+    fn alu(&mut self, op: AluOp, val: u8) {
+        match op {
+            AluOp::Add => self.add(val),
+            AluOp::Adc => self.adc(val),
+            AluOp::Sub => self.sub(val),
+            AluOp::Sbc => self.sbc(val),
+            AluOp::And => self.and(val),
+            AluOp::Xor => self.xor(val),
+            AluOp::Or => self.or(val),
+            AluOp::Cp => self.cp(val),
+        }
+    }
+
+    /// Rotaciones y shifts del prefijo CB. Z según el resultado, N=H=0, C el bit que sale.
+    fn rotate(&mut self, op: RotOp, val: u8) -> u8 {
+        let old_c = if self.get_flag(C_FLAG) { 1 } else { 0 };
+        let (res, carry) = match op {
+            RotOp::Rlc => (val.rotate_left(1), val >> 7),
+            RotOp::Rrc => (val.rotate_right(1), val & 0x01),
+            RotOp::Rl => ((val << 1) | old_c, val >> 7),          // A través del carry
+            RotOp::Rr => ((val >> 1) | (old_c << 7), val & 0x01),
+            RotOp::Sla => (val << 1, val >> 7),                   // Shift Left Arithmetic
+            RotOp::Sra => (((val as i8) >> 1) as u8, val & 0x01), // Mantiene el signo
+            RotOp::Swap => (val.rotate_left(4), 0),               // Intercambia nibbles
+            RotOp::Srl => (val >> 1, val & 0x01),                 // Rellena con ceros
+        };
+        self.regs.f = 0;
+        if res == 0 { self.regs.f |= Z_FLAG; }
+        if carry != 0 { self.regs.f |= C_FLAG; }
+        res
+    }
+
+    /// SP + e8 (ADD SP, e8 y LD HL, SP+e8). Lee el offset con signo.
+    /// Flags H y C funcionan raro con SP aritmética (base 16 bits, flags del byte bajo).
+    fn sp_plus_offset<M: MemoryInterface>(&mut self, bus: &mut M) -> u16 {
+        let offset = self.fetch(bus) as i8 as u16;
+        let sp = self.regs.sp;
+        self.regs.f = 0;
+        if (sp & 0xF) + (offset & 0xF) > 0xF { self.regs.f |= H_FLAG; }
+        if (sp & 0xFF) + (offset & 0xFF) > 0xFF { self.regs.f |= C_FLAG; }
+        sp.wrapping_add(offset)
+    }
+
     // --- MEMORY FETCH ---
 
-    fn fetch(&mut self, bus: &mut Bus) -> u8 {
-        let v = bus.read(self.regs.pc);
-        self.regs.pc = self.regs.pc.wrapping_add(1);
+    fn fetch<M: MemoryInterface>(&mut self, bus: &mut M) -> u8 {
+        self.fetch_as(bus, Fetch::Operand)
+    }
+
+    fn fetch_as<M: MemoryInterface>(&mut self, bus: &mut M, kind: Fetch) -> u8 {
+        let v = bus.fetch(self.regs.pc, kind);
+        if self.halt_bug {
+            self.halt_bug = false; // PC no avanza: este byte se volverá a leer
+        } else {
+            self.regs.pc = self.regs.pc.wrapping_add(1);
+        }
         v
     }
 
-    fn fetch_u16(&mut self, bus: &mut Bus) -> u16 {
+    fn fetch_u16<M: MemoryInterface>(&mut self, bus: &mut M) -> u16 {
         let l = self.fetch(bus) as u16;
         let h = self.fetch(bus) as u16;
         (h << 8) | l // Endianness: Little Endian (Low byte first)
     }
 
-    fn push(&mut self, bus: &mut Bus, val: u16) {
+    fn push<M: MemoryInterface>(&mut self, bus: &mut M, val: u16) {
         // Stack crece hacia abajo (direcciones menores)
+        bus.tick(); // Pre-decremento de SP
         self.regs.sp = self.regs.sp.wrapping_sub(1); bus.write(self.regs.sp, (val >> 8) as u8);
         self.regs.sp = self.regs.sp.wrapping_sub(1); bus.write(self.regs.sp, val as u8);
     }
 
-    fn pop(&mut self, bus: &mut Bus) -> u16 {
+    fn pop<M: MemoryInterface>(&mut self, bus: &mut M) -> u16 {
         // Stack decrece hacia arriba
         let l = bus.read(self.regs.sp) as u16; self.regs.sp = self.regs.sp.wrapping_add(1);
         let h = bus.read(self.regs.sp) as u16; self.regs.sp = self.regs.sp.wrapping_add(1);
@@ -606,36 +608,84 @@ impl Cpu {
 
     // --- CONTROL DE FLUJO ---
 
-    fn call(&mut self, bus: &mut Bus, cond: bool) -> u32 {
+    /// STOP: entra en el modo de mínimo consumo.
+    /// Su comportamiento depende de si hay un botón presionado y una interrupción pendiente:
+    /// - Botón presionado + interrupción: STOP ocupa 1 byte y no hace nada.
+    /// - Botón presionado, sin interrupción: ocupa 2 bytes y entra en HALT (no en STOP).
+    /// - Sin botón: entra en STOP y resetea DIV. Ocupa 2 bytes salvo que haya interrupción.
+    fn stop<M: MemoryInterface>(&mut self, bus: &mut M) {
+        let interrupt_pending = bus.pending_interrupts() != 0;
+        // El segundo byte (normalmente 0x00) se salta sin leerlo: el reloj ya se detiene.
+        if !interrupt_pending {
+            self.regs.pc = self.regs.pc.wrapping_add(1);
+        }
+
+        if bus.joypad_line_low() {
+            if !interrupt_pending {
+                self.halted = true;
+            }
+        } else {
+            bus.stop();
+            self.stopped = true;
+        }
+    }
+
+    /// Opcode ilegal: el SM83 real se cuelga para siempre.
+    fn lock_up(&mut self, opcode: u8) {
+        self.locked = true;
+        // El fetch ya avanzó PC: el opcode está en PC-1.
+        let pc = self.regs.pc.wrapping_sub(1);
+        self.event = Some(CpuEvent::IllegalOpcode { pc, opcode });
+    }
+
+    /// HALT: duerme la CPU hasta que haya una interrupción pendiente (IE & IF).
+    /// Con IME=0 y una interrupción ya pendiente, la CPU no duerme y sufre el
+    /// "HALT bug": el siguiente opcode se lee sin incrementar PC.
+    fn halt<M: MemoryInterface>(&mut self, bus: &mut M) {
+        if !self.ime && bus.pending_interrupts() != 0 {
+            self.halt_bug = true;
+        } else {
+            self.halted = true;
+        }
+    }
+
+    // Los saltos devuelven si se tomaron: los ciclos de cada caso están en OPCODES.
+
+    fn call<M: MemoryInterface>(&mut self, bus: &mut M, cond: bool) -> bool {
         let addr = self.fetch_u16(bus);
         if cond {
             self.push(bus, self.regs.pc);
             self.regs.pc = addr;
-            6
-        } else { 3 }
+        }
+        cond
     }
 
-    fn ret(&mut self, bus: &mut Bus, cond: bool) -> u32 {
+    /// RET condicional: un ciclo extra para evaluar la condición.
+    fn ret<M: MemoryInterface>(&mut self, bus: &mut M, cond: bool) -> bool {
+        bus.tick();
         if cond {
             self.regs.pc = self.pop(bus);
-            4 
-        } else { 2 }
+            bus.tick();
+        }
+        cond
     }
 
-    fn jp(&mut self, bus: &mut Bus, cond: bool) -> u32 {
+    fn jp<M: MemoryInterface>(&mut self, bus: &mut M, cond: bool) -> bool {
         let addr = self.fetch_u16(bus);
-        if cond { self.regs.pc = addr; 4 } else { 3 }
+        if cond { self.regs.pc = addr; bus.tick(); }
+        cond
     }
 
-    fn jr(&mut self, bus: &mut Bus, cond: bool) -> u32 {
+    fn jr<M: MemoryInterface>(&mut self, bus: &mut M, cond: bool) -> bool {
         let off = self.fetch(bus) as i8;
         if cond {
             self.regs.pc = (self.regs.pc as i32 + off as i32) as u16;
-            3
-        } else { 2 }
+            bus.tick();
+        }
+        cond
     }
 
-    fn rst(&mut self, bus: &mut Bus, addr: u16) {
+    fn rst<M: MemoryInterface>(&mut self, bus: &mut M, addr: u16) {
         self.push(bus, self.regs.pc);
         self.regs.pc = addr;
     }
@@ -645,40 +695,111 @@ impl Cpu {
     fn get_flag(&self, f: u8) -> bool { (self.regs.f & f) != 0 }
     fn set_flag(&mut self, f: u8, v: bool) { if v { self.regs.f |= f; } else { self.regs.f &= !f; } }
 
-    fn execute_load_8bit(&mut self, opcode: u8, bus: &mut Bus) -> u32 {
-        let src = opcode & 0x07;
-        let dst = (opcode >> 3) & 0x07;
-        let val = self.get_reg_from_code(src, bus);
-        match dst {
-            0 => self.regs.b = val, 1 => self.regs.c = val,
-            2 => self.regs.d = val, 3 => self.regs.e = val,
-            4 => self.regs.h = val, 5 => self.regs.l = val,
-            6 => bus.write(self.regs.get_hl(), val),
-            7 => self.regs.a = val,
-            _ => {}
+    fn condition(&self, cc: Cond) -> bool {
+        match cc {
+            Cond::NZ => !self.get_flag(Z_FLAG),
+            Cond::Z => self.get_flag(Z_FLAG),
+            Cond::NC => !self.get_flag(C_FLAG),
+            Cond::C => self.get_flag(C_FLAG),
+            Cond::Always => true,
+        }
+    }
+
+    /// Lee un operando de 8 bits. [HL] cuesta un acceso al bus.
+    fn read_r8<M: MemoryInterface>(&self, bus: &mut M, r: R8) -> u8 {
+        match r {
+            R8::B => self.regs.b, R8::C => self.regs.c,
+            R8::D => self.regs.d, R8::E => self.regs.e,
+            R8::H => self.regs.h, R8::L => self.regs.l,
+            R8::HlInd => bus.read(self.regs.get_hl()),
+            R8::A => self.regs.a,
+        }
+    }
+
+    fn write_r8<M: MemoryInterface>(&mut self, bus: &mut M, r: R8, val: u8) {
+        match r {
+            R8::B => self.regs.b = val, R8::C => self.regs.c = val,
+            R8::D => self.regs.d = val, R8::E => self.regs.e = val,
+            R8::H => self.regs.h = val, R8::L => self.regs.l = val,
+            R8::HlInd => bus.write(self.regs.get_hl(), val),
+            R8::A => self.regs.a = val,
         }
-        if src == 6 || dst == 6 { 2 } else { 1 }
     }
 
-    fn get_reg_from_code(&self, code: u8, bus: &Bus) -> u8 {
-        match code {
-            0 => self.regs.b, 1 => self.regs.c,
-            2 => self.regs.d, 3 => self.regs.e,
-            4 => self.regs.h, 5 => self.regs.l,
-            6 => bus.read(self.regs.get_hl()),
-            7 => self.regs.a,
-            _ => 0,
+    fn read_r16(&self, rr: R16) -> u16 {
+        match rr {
+            R16::BC => self.regs.get_bc(),
+            R16::DE => self.regs.get_de(),
+            R16::HL => self.regs.get_hl(),
+            R16::SP => self.regs.sp,
         }
     }
 
-    fn write_reg_cb(&mut self, bus: &mut Bus, idx: u8, val: u8) {
-        match idx {
-            0 => self.regs.b = val, 1 => self.regs.c = val,
-            2 => self.regs.d = val, 3 => self.regs.e = val,
-            4 => self.regs.h = val, 5 => self.regs.l = val,
-            6 => bus.write(self.regs.get_hl(), val),
-            7 => self.regs.a = val,
-            _ => {}
+    fn write_r16(&mut self, rr: R16, val: u16) {
+        match rr {
+            R16::BC => self.regs.set_bc(val),
+            R16::DE => self.regs.set_de(val),
+            R16::HL => self.regs.set_hl(val),
+            R16::SP => self.regs.sp = val,
         }
     }
-}
\ No newline at end of file
+
+    fn read_r16_stk(&self, rr: R16Stk) -> u16 {
+        match rr {
+            R16Stk::BC => self.regs.get_bc(),
+            R16Stk::DE => self.regs.get_de(),
+            R16Stk::HL => self.regs.get_hl(),
+            R16Stk::AF => self.regs.get_af(),
+        }
+    }
+
+    fn write_r16_stk(&mut self, rr: R16Stk, val: u16) {
+        match rr {
+            R16Stk::BC => self.regs.set_bc(val),
+            R16Stk::DE => self.regs.set_de(val),
+            R16Stk::HL => self.regs.set_hl(val),
+            R16Stk::AF => self.regs.set_af(val),
+        }
+    }
+
+    /// Dirección de LD [r16], A / LD A, [r16]. [HL+]/[HL-] actualizan HL después del acceso.
+    fn mem_addr(&mut self, mem: R16Mem) -> u16 {
+        match mem {
+            R16Mem::BC => self.regs.get_bc(),
+            R16Mem::DE => self.regs.get_de(),
+            R16Mem::HLI => { let hl = self.regs.get_hl(); self.regs.set_hl(hl.wrapping_add(1)); hl }
+            R16Mem::HLD => { let hl = self.regs.get_hl(); self.regs.set_hl(hl.wrapping_sub(1)); hl }
+        }
+    }
+}
+
+/// Save state: registros y estado de ejecución. El trace, el profiler y el evento
+/// pendiente son herramientas del frontend, no parte de la máquina.
+impl Snapshot for Cpu {
+    fn save_state(&self, w: &mut Writer) {
+        let r = &self.regs;
+        for v in [r.a, r.f, r.b, r.c, r.d, r.e, r.h, r.l] {
+            w.u8(v);
+        }
+        w.u16(r.sp);
+        w.u16(r.pc);
+        for flag in [self.ime, self.halted, self.ei_pending, self.halt_bug, self.stopped, self.locked] {
+            w.bool(flag);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut Reader) -> Result<(), StateError> {
+        let regs = &mut self.regs;
+        for v in [&mut regs.a, &mut regs.f, &mut regs.b, &mut regs.c, &mut regs.d, &mut regs.e, &mut regs.h, &mut regs.l] {
+            *v = r.u8()?;
+        }
+        regs.f &= 0xF0; // El nibble bajo de F no existe en el hardware.
+        regs.sp = r.u16()?;
+        regs.pc = r.u16()?;
+        for flag in [&mut self.ime, &mut self.halted, &mut self.ei_pending, &mut self.halt_bug, &mut self.stopped, &mut self.locked] {
+            *flag = r.bool()?;
+        }
+        self.event = None;
+        Ok(())
+    }
+}
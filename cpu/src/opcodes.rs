@@ -0,0 +1,287 @@
+// cpu/src/opcodes.rs
+
+//! Tabla de instrucciones del SM83: una entrada por opcode (y otra tabla para el prefijo CB)
+//! con la operación y sus operandos, la longitud, los M-Cycles y el efecto en los flags.
+//!
+//! Las tablas se generan en tiempo de compilación (`const fn`) a partir de la
+//! estructura octal del opcode (`xx yyy zzz`), en lugar de escribir 512 brazos a mano.
+//! `Cpu::execute` solo interpreta `Op`; los ciclos que devuelve salen de aquí.
+
+/// Operando de 8 bits según los 3 bits del opcode. `HlInd` es la memoria en [HL].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum R8 { B, C, D, E, H, L, HlInd, A }
+
+/// Pares de 16 bits para cargas y aritmética (grupo "SP").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum R16 { BC, DE, HL, SP }
+
+/// Pares de 16 bits para PUSH/POP (grupo "AF").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum R16Stk { BC, DE, HL, AF }
+
+/// Direcciones indirectas de LD [r16], A / LD A, [r16]. HLI/HLD incrementan/decrementan HL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum R16Mem { BC, DE, HLI, HLD }
+
+/// Condición de salto. `Always` para las versiones incondicionales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cond { NZ, Z, NC, C, Always }
+
+/// Operaciones ALU con el acumulador.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AluOp { Add, Adc, Sub, Sbc, And, Xor, Or, Cp }
+
+/// Rotaciones y shifts (prefijo CB 0x00-0x3F, y RLCA/RRCA/RLA/RRA sobre A).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotOp { Rlc, Rrc, Rl, Rr, Sla, Sra, Swap, Srl }
+
+/// Operación decodificada, con sus operandos ya resueltos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Nop,
+    Stop,
+    Halt,
+    Illegal,
+    /// 0xCB: la instrucción real está en `CB_OPCODES`.
+    Prefix,
+
+    // --- Cargas ---
+    LdR8R8(R8, R8),
+    LdR8N(R8),
+    LdR16N(R16),
+    LdMemA(R16Mem),
+    LdAMem(R16Mem),
+    LdNnSp,
+    LdNnA,
+    LdANn,
+    LdhNA,
+    LdhAN,
+    LdhCA,
+    LdhAC,
+    LdSpHl,
+    LdHlSpE,
+    Push(R16Stk),
+    Pop(R16Stk),
+
+    // --- Aritmética ---
+    IncR8(R8),
+    DecR8(R8),
+    IncR16(R16),
+    DecR16(R16),
+    AddHl(R16),
+    AddSpE,
+    AluR8(AluOp, R8),
+    AluN(AluOp),
+    /// RLCA/RRCA/RLA/RRA: como la versión CB sobre A, pero Z siempre queda a 0.
+    RotA(RotOp),
+    Daa,
+    Cpl,
+    Scf,
+    Ccf,
+    Di,
+    Ei,
+
+    // --- Control de flujo ---
+    Jr(Cond),
+    Jp(Cond),
+    JpHl,
+    Call(Cond),
+    Ret(Cond),
+    Reti,
+    Rst(u16),
+
+    // --- Prefijo CB ---
+    Rot(RotOp, R8),
+    Bit(u8, R8),
+    Res(u8, R8),
+    Set(u8, R8),
+}
+
+/// Entrada de la tabla.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpInfo {
+    pub op: Op,
+    /// Longitud en bytes (las CB ocupan 2, contando el prefijo).
+    pub len: u8,
+    /// M-Cycles totales, incluido el fetch. En los saltos condicionales: sin tomar.
+    pub cycles: u8,
+    /// M-Cycles si el salto se toma (igual a `cycles` en el resto).
+    pub cycles_taken: u8,
+    /// Efecto en Z N H C, al estilo de Pan Docs: la letra = se calcula,
+    /// '0'/'1' = se fuerza, '-' = no cambia. POP AF los carga todos del stack.
+    pub flags: &'static str,
+}
+
+const fn info(op: Op, len: u8, cycles: u8, flags: &'static str) -> OpInfo {
+    OpInfo { op, len, cycles, cycles_taken: cycles, flags }
+}
+
+const fn branch(op: Op, len: u8, cycles: u8, cycles_taken: u8) -> OpInfo {
+    OpInfo { op, len, cycles, cycles_taken, flags: "----" }
+}
+
+const fn r8(bits: u8) -> R8 {
+    match bits & 0x07 {
+        0 => R8::B, 1 => R8::C, 2 => R8::D, 3 => R8::E,
+        4 => R8::H, 5 => R8::L, 6 => R8::HlInd, _ => R8::A,
+    }
+}
+
+const fn r16(bits: u8) -> R16 {
+    match bits & 0x03 { 0 => R16::BC, 1 => R16::DE, 2 => R16::HL, _ => R16::SP }
+}
+
+const fn r16_stk(bits: u8) -> R16Stk {
+    match bits & 0x03 { 0 => R16Stk::BC, 1 => R16Stk::DE, 2 => R16Stk::HL, _ => R16Stk::AF }
+}
+
+const fn r16_mem(bits: u8) -> R16Mem {
+    match bits & 0x03 { 0 => R16Mem::BC, 1 => R16Mem::DE, 2 => R16Mem::HLI, _ => R16Mem::HLD }
+}
+
+const fn cond(bits: u8) -> Cond {
+    match bits & 0x03 { 0 => Cond::NZ, 1 => Cond::Z, 2 => Cond::NC, _ => Cond::C }
+}
+
+const fn alu(bits: u8) -> (AluOp, &'static str) {
+    match bits & 0x07 {
+        0 => (AluOp::Add, "Z0HC"), 1 => (AluOp::Adc, "Z0HC"),
+        2 => (AluOp::Sub, "Z1HC"), 3 => (AluOp::Sbc, "Z1HC"),
+        4 => (AluOp::And, "Z010"), 5 => (AluOp::Xor, "Z000"),
+        6 => (AluOp::Or, "Z000"), _ => (AluOp::Cp, "Z1HC"),
+    }
+}
+
+const fn rot(bits: u8) -> RotOp {
+    match bits & 0x07 {
+        0 => RotOp::Rlc, 1 => RotOp::Rrc, 2 => RotOp::Rl, 3 => RotOp::Rr,
+        4 => RotOp::Sla, 5 => RotOp::Sra, 6 => RotOp::Swap, _ => RotOp::Srl,
+    }
+}
+
+/// Un operando [HL] añade un acceso a memoria.
+const fn hl(bits: u8) -> u8 {
+    if bits & 0x07 == 6 { 1 } else { 0 }
+}
+
+/// Decodifica un opcode base.
+const fn decode(opcode: u8) -> OpInfo {
+    // Descomposición octal clásica del opcode: xx yyy zzz
+    let x = opcode >> 6;
+    let y = (opcode >> 3) & 0x07;
+    let z = opcode & 0x07;
+    let p = y >> 1; // Par de registros (bits 5-4)
+    let q = y & 1;  // Variante dentro del par (bit 3)
+
+    match opcode {
+        0x00 => info(Op::Nop, 1, 1, "----"),
+        // STOP se lista con 2 bytes; la CPU decide si salta el segundo (ver Cpu::stop).
+        0x10 => info(Op::Stop, 2, 1, "----"),
+        0x76 => info(Op::Halt, 1, 1, "----"),
+        0xCB => info(Op::Prefix, 2, 2, "----"),
+        0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB | 0xEC | 0xED | 0xF4 | 0xFC | 0xFD => {
+            info(Op::Illegal, 1, 1, "----")
+        }
+
+        0x08 => info(Op::LdNnSp, 3, 5, "----"),
+        0x07 => info(Op::RotA(RotOp::Rlc), 1, 1, "000C"),
+        0x0F => info(Op::RotA(RotOp::Rrc), 1, 1, "000C"),
+        0x17 => info(Op::RotA(RotOp::Rl), 1, 1, "000C"),
+        0x1F => info(Op::RotA(RotOp::Rr), 1, 1, "000C"),
+        0x27 => info(Op::Daa, 1, 1, "Z-0C"),
+        0x2F => info(Op::Cpl, 1, 1, "-11-"),
+        0x37 => info(Op::Scf, 1, 1, "-001"),
+        0x3F => info(Op::Ccf, 1, 1, "-00C"),
+        0x18 => branch(Op::Jr(Cond::Always), 2, 3, 3),
+        0x20 | 0x28 | 0x30 | 0x38 => branch(Op::Jr(cond(y - 4)), 2, 2, 3),
+
+        0xC3 => branch(Op::Jp(Cond::Always), 3, 4, 4),
+        0xCD => branch(Op::Call(Cond::Always), 3, 6, 6),
+        // RET incondicional no gasta el ciclo de evaluar la condición.
+        0xC9 => branch(Op::Ret(Cond::Always), 1, 4, 4),
+        0xD9 => info(Op::Reti, 1, 4, "----"),
+        0xE9 => info(Op::JpHl, 1, 1, "----"),
+        0xF9 => info(Op::LdSpHl, 1, 2, "----"),
+        0xE0 => info(Op::LdhNA, 2, 3, "----"),
+        0xF0 => info(Op::LdhAN, 2, 3, "----"),
+        0xE2 => info(Op::LdhCA, 1, 2, "----"),
+        0xF2 => info(Op::LdhAC, 1, 2, "----"),
+        0xEA => info(Op::LdNnA, 3, 4, "----"),
+        0xFA => info(Op::LdANn, 3, 4, "----"),
+        0xE8 => info(Op::AddSpE, 2, 4, "00HC"),
+        0xF8 => info(Op::LdHlSpE, 2, 3, "00HC"),
+        0xF3 => info(Op::Di, 1, 1, "----"),
+        0xFB => info(Op::Ei, 1, 1, "----"),
+
+        // Bloque 0 (0x00-0x3F): cargas y aritmética de 16 bits, INC/DEC, LD r,n
+        _ if x == 0 => match z {
+            1 if q == 0 => info(Op::LdR16N(r16(p)), 3, 3, "----"),
+            1 => info(Op::AddHl(r16(p)), 1, 2, "-0HC"),
+            2 if q == 0 => info(Op::LdMemA(r16_mem(p)), 1, 2, "----"),
+            2 => info(Op::LdAMem(r16_mem(p)), 1, 2, "----"),
+            3 if q == 0 => info(Op::IncR16(r16(p)), 1, 2, "----"),
+            3 => info(Op::DecR16(r16(p)), 1, 2, "----"),
+            // [HL]: lectura + escritura
+            4 => info(Op::IncR8(r8(y)), 1, 1 + 2 * hl(y), "Z0H-"),
+            5 => info(Op::DecR8(r8(y)), 1, 1 + 2 * hl(y), "Z1H-"),
+            _ => info(Op::LdR8N(r8(y)), 2, 2 + hl(y), "----"),
+        },
+        // Bloque 1 (0x40-0x7F): LD r, r (HALT ya está arriba)
+        _ if x == 1 => info(Op::LdR8R8(r8(y), r8(z)), 1, 1 + hl(y) + hl(z), "----"),
+        // Bloque 2 (0x80-0xBF): ALU A, r
+        _ if x == 2 => {
+            let (op, flags) = alu(y);
+            info(Op::AluR8(op, r8(z)), 1, 1 + hl(z), flags)
+        }
+        // Bloque 3 (0xC0-0xFF)
+        _ => match z {
+            0 => branch(Op::Ret(cond(y)), 1, 2, 5),
+            1 if p == 3 => info(Op::Pop(R16Stk::AF), 1, 3, "ZNHC"),
+            1 => info(Op::Pop(r16_stk(p)), 1, 3, "----"),
+            2 => branch(Op::Jp(cond(y)), 3, 3, 4),
+            4 => branch(Op::Call(cond(y)), 3, 3, 6),
+            5 => info(Op::Push(r16_stk(p)), 1, 4, "----"),
+            6 => {
+                let (op, flags) = alu(y);
+                info(Op::AluN(op), 2, 2, flags)
+            }
+            _ => info(Op::Rst(y as u16 * 8), 1, 4, "----"),
+        },
+    }
+}
+
+/// Decodifica el segundo byte de una instrucción con prefijo CB.
+const fn decode_cb(cb: u8) -> OpInfo {
+    let bit = (cb >> 3) & 0x07;
+    let reg = r8(cb);
+    let mem = cb & 0x07 == 6;
+    match cb >> 6 {
+        0 => {
+            let op = rot(bit);
+            let flags = if matches!(op, RotOp::Swap) { "Z000" } else { "Z00C" };
+            info(Op::Rot(op, reg), 2, if mem { 4 } else { 2 }, flags)
+        }
+        // BIT solo lee: con [HL] no hay escritura.
+        1 => info(Op::Bit(bit, reg), 2, if mem { 3 } else { 2 }, "Z01-"),
+        2 => info(Op::Res(bit, reg), 2, if mem { 4 } else { 2 }, "----"),
+        _ => info(Op::Set(bit, reg), 2, if mem { 4 } else { 2 }, "----"),
+    }
+}
+
+/// Tabla de opcodes base, indexada por el opcode.
+pub static OPCODES: [OpInfo; 256] = build(false);
+
+/// Tabla del prefijo CB, indexada por el byte que sigue a 0xCB.
+pub static CB_OPCODES: [OpInfo; 256] = build(true);
+
+/// Rellena una tabla en tiempo de compilación. En const fn no hay 'for' sobre rangos,
+/// así que usamos 'while' (como un for clásico de Go).
+const fn build(cb: bool) -> [OpInfo; 256] {
+    let mut table = [info(Op::Illegal, 1, 1, "----"); 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = if cb { decode_cb(i as u8) } else { decode(i as u8) };
+        i += 1;
+    }
+    table
+}
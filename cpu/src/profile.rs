@@ -0,0 +1,334 @@
+// cpu/src/profile.rs
+
+//! Profiler de ejecución: ¿en qué funciones se van los ciclos de cada frame?
+//!
+//! `Cpu::step` le avisa de cada instrucción. Con CALL/RST y la entrada a las
+//! interrupciones se apila una función; se desapila cuando SP vuelve a estar por
+//! encima de la dirección de retorno (RET, RETI, o código que "limpia" la pila a mano
+//! con POP/LD SP). Así no se desincroniza con trucos como `pop hl; jp hl`.
+//!
+//! Los ciclos (M-Cycles) se acumulan por pila completa, que es justo el formato
+//! "folded stacks" de flamegraph.pl / inferno:
+//!
+//! ```text
+//! Main;UpdateActors;DrawSprite 1234
+//! ```
+//!
+//! De ahí salen los ciclos exclusivos (la función está en la cima) y, con la hora de
+//! entrada de cada llamada, los inclusivos (la función y todo lo que llama).
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use crate::opcodes::{Op, OPCODES};
+use crate::symbols::SymbolTable;
+use crate::{MemoryInterface, Registers};
+
+/// Límite de profundidad: código que hace CALL y nunca vuelve (reinicios con `LD SP`
+/// dentro de una subrutina, por ejemplo) no debe hacer crecer la pila sin fin.
+const MAX_DEPTH: usize = 256;
+
+/// Entrada de una función: banco de ROM y dirección. Fuera de la ROM el banco es 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Location {
+    pub bank: u16,
+    pub addr: u16,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02X}:{:04X}", self.bank, self.addr)
+    }
+}
+
+/// Totales de una función. `None` como función es la raíz: el código que corre
+/// fuera de cualquier CALL (típicamente el bucle principal).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FunctionStats {
+    pub calls: u64,
+    /// Ciclos de la función y de todo lo que llama (la recursión no cuenta dos veces).
+    pub inclusive: u64,
+    /// Ciclos de la función estando en la cima de la pila.
+    pub exclusive: u64,
+}
+
+/// Reparto de un frame: ciclos exclusivos por función, de mayor a menor.
+#[derive(Debug, Clone)]
+pub struct FrameProfile {
+    pub number: u64,
+    pub cycles: u64,
+    pub functions: Vec<(Option<Location>, u64)>,
+}
+
+struct Call {
+    // SP antes de la llamada: cuando SP vuelve a ese valor (o más), la función terminó.
+    return_sp: u16,
+    entered_at: u64,
+}
+
+pub struct Profiler {
+    /// Símbolos para nombrar las funciones (`Main.loop` en vez de `00:0150`).
+    pub symbols: Option<Rc<SymbolTable>>,
+    /// Cuántos frames recientes se guardan para `frames()`.
+    pub frame_history: usize,
+    // Pila actual: 'path' son las funciones (clave del folded) y 'calls' su contabilidad.
+    path: Vec<Location>,
+    calls: Vec<Call>,
+    cycles: u64,
+    // Ciclos aún no asignados a 'path'. Se vuelcan solo cuando la pila cambia,
+    // para no buscar en el HashMap en cada instrucción.
+    pending: u64,
+    folded: HashMap<Vec<Location>, u64>,
+    call_counts: HashMap<Location, u64>,
+    inclusive: HashMap<Location, u64>,
+    frame: HashMap<Option<Location>, u64>,
+    frame_start: u64,
+    frame_number: u64,
+    frames: VecDeque<FrameProfile>,
+    // Destino del folded-stack al terminar (como la salida del Tracer).
+    output: Option<Box<dyn Write>>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self { Self::new() }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            symbols: None,
+            frame_history: 60,
+            path: Vec::new(),
+            calls: Vec::new(),
+            cycles: 0,
+            pending: 0,
+            folded: HashMap::new(),
+            call_counts: HashMap::new(),
+            inclusive: HashMap::new(),
+            frame: HashMap::new(),
+            frame_start: 0,
+            frame_number: 0,
+            frames: VecDeque::new(),
+            output: None,
+        }
+    }
+
+    /// Como `new`, pero `save` escribirá el folded-stack en 'output'.
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        Profiler { output: Some(output), ..Self::new() }
+    }
+
+    /// M-Cycles vistos desde que se creó el profiler.
+    pub fn total_cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Profundidad de la pila de llamadas actual.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    // ---------------------------------------------------------
+    // Hooks (los llama Cpu::step)
+    // ---------------------------------------------------------
+
+    /// Instrucción ejecutada: 'sp' es el de antes de ejecutarla, 'regs' los registros de después.
+    pub(crate) fn instruction<M: MemoryInterface>(&mut self, opcode: u8, sp: u16, regs: &Registers, cycles: u32, bus: &M) {
+        self.cycles += cycles as u64;
+        self.pending += cycles as u64;
+
+        // CALL tomado o RST: la dirección de retorno quedó en la pila (SP bajó 2).
+        // Un CALL condicional no tomado no toca SP.
+        if matches!(OPCODES[opcode as usize].op, Op::Call(_) | Op::Rst(_)) && regs.sp == sp.wrapping_sub(2) {
+            self.enter(regs.pc, sp, bus.rom_bank(), bus.ram_bank());
+        }
+        self.unwind(regs.sp);
+    }
+
+    /// Despacho de una interrupción: 'vector' es el nuevo PC, 'sp' el de antes del push.
+    pub(crate) fn interrupt(&mut self, vector: u16, sp: u16, cycles: u32) {
+        // El vector está en el banco 0: los bancos mapeados no importan.
+        self.enter(vector, sp, 0, 0);
+        self.cycles += cycles as u64;
+        self.pending += cycles as u64;
+    }
+
+    /// Ciclos sin ejecutar instrucciones (HALT, STOP, CPU colgada): cuentan para
+    /// la función que estaba corriendo, como en un profiler de CPU real.
+    pub(crate) fn idle(&mut self, cycles: u32) {
+        self.cycles += cycles as u64;
+        self.pending += cycles as u64;
+    }
+
+    fn enter(&mut self, addr: u16, sp: u16, rom_bank: u16, ram_bank: u16) {
+        self.flush();
+        if self.path.len() == MAX_DEPTH {
+            self.path.remove(0);
+            self.calls.remove(0);
+        }
+        let location = Location { bank: SymbolTable::bank_of(addr, rom_bank, ram_bank), addr };
+        *self.call_counts.entry(location).or_insert(0) += 1;
+        self.path.push(location);
+        self.calls.push(Call { return_sp: sp, entered_at: self.cycles });
+    }
+
+    /// Desapila las funciones cuya dirección de retorno ya salió de la pila.
+    fn unwind(&mut self, sp: u16) {
+        while let Some(call) = self.calls.last() && sp >= call.return_sp {
+            self.flush();
+            let call = self.calls.pop().unwrap();
+            let location = self.path.pop().unwrap();
+            // Recursión: solo cuenta la llamada más externa.
+            if !self.path.contains(&location) {
+                *self.inclusive.entry(location).or_insert(0) += self.cycles - call.entered_at;
+            }
+        }
+    }
+
+    /// Asigna los ciclos pendientes a la pila actual.
+    fn flush(&mut self) {
+        if self.pending == 0 {
+            return;
+        }
+        // get_mut con un slice: solo se reserva un Vec la primera vez que aparece esta pila.
+        match self.folded.get_mut(self.path.as_slice()) {
+            Some(total) => *total += self.pending,
+            None => { self.folded.insert(self.path.clone(), self.pending); }
+        }
+        *self.frame.entry(self.path.last().copied()).or_insert(0) += self.pending;
+        self.pending = 0;
+    }
+
+    // ---------------------------------------------------------
+    // Frames
+    // ---------------------------------------------------------
+
+    /// Cierra el frame actual. Lo llama el frontend en cada V-Blank.
+    pub fn end_frame(&mut self) {
+        self.flush();
+        let mut functions: Vec<_> = self.frame.drain().collect();
+        functions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        self.frames.push_back(FrameProfile {
+            number: self.frame_number,
+            cycles: self.cycles - self.frame_start,
+            functions,
+        });
+        while self.frames.len() > self.frame_history {
+            self.frames.pop_front();
+        }
+        self.frame_number += 1;
+        self.frame_start = self.cycles;
+    }
+
+    /// Frames recientes, del más antiguo al más nuevo.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameProfile> {
+        self.frames.iter()
+    }
+
+    // ---------------------------------------------------------
+    // Resultados
+    // ---------------------------------------------------------
+
+    /// Totales por función, de mayor a menor tiempo inclusivo.
+    pub fn stats(&mut self) -> Vec<(Option<Location>, FunctionStats)> {
+        self.flush();
+        let mut stats: HashMap<Option<Location>, FunctionStats> = HashMap::new();
+        for (path, &cycles) in &self.folded {
+            stats.entry(path.last().copied()).or_default().exclusive += cycles;
+        }
+        for (&location, &calls) in &self.call_counts {
+            stats.entry(Some(location)).or_default().calls = calls;
+        }
+        for (&location, &cycles) in &self.inclusive {
+            stats.entry(Some(location)).or_default().inclusive = cycles;
+        }
+        // Llamadas todavía abiertas: cuentan hasta ahora (una vez por función).
+        for (i, (location, call)) in self.path.iter().zip(&self.calls).enumerate() {
+            if !self.path[..i].contains(location) {
+                stats.entry(Some(*location)).or_default().inclusive += self.cycles - call.entered_at;
+            }
+        }
+        // La raíz incluye todo.
+        stats.entry(None).or_default().inclusive = self.cycles;
+
+        let mut stats: Vec<_> = stats.into_iter().collect();
+        stats.sort_by(|a, b| b.1.inclusive.cmp(&a.1.inclusive).then(a.0.cmp(&b.0)));
+        stats
+    }
+
+    /// Nombre de una función: su símbolo si lo hay, si no `banco:dirección`.
+    pub fn name(&self, location: Option<Location>) -> String {
+        let Some(location) = location else { return "(root)".to_string() };
+        self.symbols
+            .as_ref()
+            .and_then(|s| s.describe_in(location.bank, location.addr))
+            .unwrap_or_else(|| location.to_string())
+    }
+
+    /// Escribe las pilas en formato folded (`a;b;c ciclos`), listo para flamegraph.pl.
+    pub fn write_folded(&mut self, w: &mut dyn Write) -> io::Result<()> {
+        self.flush();
+        let mut lines: Vec<_> = self
+            .folded
+            .iter()
+            .map(|(path, cycles)| {
+                let names: Vec<_> = match path.is_empty() {
+                    true => vec![self.name(None)],
+                    false => path.iter().map(|&l| self.name(Some(l))).collect(),
+                };
+                (names.join(";"), *cycles)
+            })
+            .collect();
+        lines.sort();
+        for (stack, cycles) in lines {
+            writeln!(w, "{} {}", stack, cycles)?;
+        }
+        Ok(())
+    }
+
+    /// Tabla de las 'limit' funciones más caras.
+    pub fn write_report(&mut self, w: &mut dyn Write, limit: usize) -> io::Result<()> {
+        let total = self.cycles.max(1);
+        writeln!(w, "{:>8} {:>12} {:>6} {:>12} {:>6}  función", "llamadas", "inclusivo", "%", "exclusivo", "%")?;
+        for (location, s) in self.stats().into_iter().take(limit) {
+            writeln!(
+                w,
+                "{:>8} {:>12} {:>5.1}% {:>12} {:>5.1}%  {}",
+                s.calls,
+                s.inclusive,
+                s.inclusive as f64 * 100.0 / total as f64,
+                s.exclusive,
+                s.exclusive as f64 * 100.0 / total as f64,
+                self.name(location)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reparto de los últimos 'count' frames: las 'limit' funciones con más ciclos exclusivos.
+    pub fn write_frames(&self, w: &mut dyn Write, count: usize, limit: usize) -> io::Result<()> {
+        let skip = self.frames.len().saturating_sub(count);
+        for frame in self.frames.iter().skip(skip) {
+            writeln!(w, "frame {}: {} ciclos", frame.number, frame.cycles)?;
+            for &(location, cycles) in frame.functions.iter().take(limit) {
+                let percent = cycles as f64 * 100.0 / frame.cycles.max(1) as f64;
+                writeln!(w, "  {:>8} {:>5.1}%  {}", cycles, percent, self.name(location))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Escribe el folded-stack en la salida de `with_output` (una sola vez).
+    /// Igual que con el Tracer, hay que llamarlo antes de salir: winit no ejecuta los `Drop`.
+    pub fn save(&mut self) -> io::Result<()> {
+        match self.output.take() {
+            Some(mut out) => {
+                self.write_folded(&mut out)?;
+                out.flush()
+            }
+            None => Ok(()),
+        }
+    }
+}
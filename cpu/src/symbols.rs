@@ -0,0 +1,156 @@
+// cpu/src/symbols.rs
+
+//! Tabla de símbolos de RGBDS / no$gmb (archivos `.sym`):
+//!
+//! ```text
+//! ; comentario
+//! 00:0150 Main
+//! 01:4A20 Main.loop
+//! 00:C000 wBuffer
+//! ```
+//!
+//! Cada dirección se resuelve dentro de su zona del mapa de memoria (ROM 0, ROM X, VRAM,
+//! SRAM, WRAM, OAM, I/O, HRAM): una etiqueta de WRAM no nombra un registro I/O.
+//! Las zonas conmutables usan el banco: 0x0000-0x3FFF es siempre el banco 0,
+//! 0x4000-0x7FFF el banco de ROM mapeado por el MBC y 0xA000-0xBFFF su banco de RAM.
+//! El resto no se conmuta en DMG: el banco del .sym se guarda (`find` lo devuelve),
+//! pero no participa en la búsqueda.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// Zonas del mapa de memoria con etiquetas propias. Echo RAM (0xE000-0xFDFF) y
+/// 0xFEA0-0xFEFF no tienen símbolos.
+const REGIONS: [RangeInclusive<u16>; 9] = [
+    0x0000..=0x3FFF, // ROM banco 0
+    0x4000..=0x7FFF, // ROM conmutable
+    0x8000..=0x9FFF, // VRAM
+    0xA000..=0xBFFF, // SRAM (RAM del cartucho, conmutable)
+    0xC000..=0xDFFF, // WRAM
+    0xFE00..=0xFE9F, // OAM
+    0xFF00..=0xFF7F, // Registros I/O
+    0xFF80..=0xFFFE, // HRAM
+    0xFFFF..=0xFFFF, // IE
+];
+
+/// Zona que contiene 'addr' (None en las que no tienen símbolos).
+fn region(addr: u16) -> Option<&'static RangeInclusive<u16>> {
+    REGIONS.iter().find(|r| r.contains(&addr))
+}
+
+/// ¿Depende la zona de 'addr' del banco mapeado (ROM y SRAM)?
+fn is_banked(addr: u16) -> bool {
+    matches!(addr, 0x0000..=0x7FFF | 0xA000..=0xBFFF)
+}
+
+/// Símbolos indexados por (banco, dirección) en las zonas conmutables y por dirección
+/// en el resto. BTreeMap mantiene el orden, así "la etiqueta más cercana por debajo"
+/// es una búsqueda por rango.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    banked: BTreeMap<(u16, u16), String>,
+    fixed: BTreeMap<u16, (u16, String)>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interpreta el texto de un `.sym`. Las líneas que no tienen la forma
+    /// `banco:dirección nombre` (comentarios, secciones de no$gmb) se ignoran.
+    pub fn parse(text: &str) -> Self {
+        let mut table = SymbolTable::new();
+        for line in text.lines() {
+            // Todo lo que sigue a ';' es comentario.
+            let line = line.split(';').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let (Some(location), Some(name)) = (words.next(), words.next()) else { continue };
+            let Some((bank, addr)) = location.split_once(':') else { continue };
+            if let (Ok(bank), Ok(addr)) = (u16::from_str_radix(bank, 16), u16::from_str_radix(addr, 16)) {
+                table.insert(bank, addr, name);
+            }
+        }
+        table
+    }
+
+    /// Carga un archivo `.sym`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn insert(&mut self, bank: u16, addr: u16, name: &str) {
+        if is_banked(addr) {
+            self.banked.insert((bank, addr), name.to_string());
+        } else {
+            self.fixed.insert(addr, (bank, name.to_string()));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.banked.len() + self.fixed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Banco al que pertenece 'addr' con 'rom_bank' mapeado en 0x4000-0x7FFF
+    /// y 'ram_bank' en 0xA000-0xBFFF.
+    pub fn bank_of(addr: u16, rom_bank: u16, ram_bank: u16) -> u16 {
+        match addr {
+            0x4000..=0x7FFF => rom_bank,
+            0xA000..=0xBFFF => ram_bank,
+            _ => 0,
+        }
+    }
+
+    /// Etiqueta exacta de 'addr' (None si no empieza ahí ningún símbolo).
+    pub fn label(&self, addr: u16, rom_bank: u16, ram_bank: u16) -> Option<&str> {
+        if is_banked(addr) {
+            self.banked.get(&(Self::bank_of(addr, rom_bank, ram_bank), addr)).map(String::as_str)
+        } else {
+            self.fixed.get(&addr).map(|(_, name)| name.as_str())
+        }
+    }
+
+    /// Nombre legible de 'addr': la etiqueta más cercana por debajo y el desplazamiento
+    /// en hex (`Main.loop+3`). None si no hay ninguna etiqueta antes en esa zona/banco.
+    pub fn describe(&self, addr: u16, rom_bank: u16, ram_bank: u16) -> Option<String> {
+        self.describe_in(Self::bank_of(addr, rom_bank, ram_bank), addr)
+    }
+
+    /// Como `describe`, con el banco de 'addr' ya resuelto (ver `bank_of`).
+    pub fn describe_in(&self, bank: u16, addr: u16) -> Option<String> {
+        // La etiqueta tiene que estar en la misma zona que 'addr'.
+        let start = *region(addr)?.start();
+        let (base, name) = if is_banked(addr) {
+            let (&(_, base), name) = self.banked.range((bank, start)..=(bank, addr)).next_back()?;
+            (base, name)
+        } else {
+            let (&base, (_, name)) = self.fixed.range(start..=addr).next_back()?;
+            (base, name)
+        };
+        Some(match addr - base {
+            0 => name.clone(),
+            offset => format!("{}+{:X}", name, offset),
+        })
+    }
+
+    /// Dirección de un símbolo por nombre: (banco, dirección).
+    pub fn find(&self, name: &str) -> Option<(u16, u16)> {
+        self.banked
+            .iter()
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(&key, _)| key)
+            .or_else(|| self.fixed.iter().find(|(_, (_, n))| n.as_str() == name).map(|(&addr, &(bank, _))| (bank, addr)))
+    }
+
+    /// Resolvedor para `disasm::decode`: solo etiquetas exactas, con los bancos actuales.
+    pub fn resolver(&self, rom_bank: u16, ram_bank: u16) -> impl Fn(u16) -> Option<String> + '_ {
+        move |addr| self.label(addr, rom_bank, ram_bank).map(str::to_string)
+    }
+}
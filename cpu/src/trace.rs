@@ -0,0 +1,140 @@
+// cpu/src/trace.rs
+
+//! Trace log de instrucciones en el formato de Gameboy Doctor:
+//!
+//! `A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02`
+//!
+//! Una línea por instrucción, con el estado ANTES de ejecutarla. Así se puede
+//! comparar con `diff` contra el log de otro emulador y encontrar la primera
+//! instrucción donde divergen.
+//!
+//! Es opcional (`Cpu::tracer` es `None` por defecto) y se puede acotar por rango de PC
+//! o por banco de ROM, y/o guardar solo las últimas N líneas en un buffer circular
+//! para volcarlas cuando algo se rompe. Con una tabla de símbolos, cada línea termina
+//! en `; Main.loop+3` (deja de ser comparable con Gameboy Doctor, por eso es opcional).
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+use crate::symbols::SymbolTable;
+use crate::{MemoryInterface, Registers};
+
+/// Formatea una línea de Gameboy Doctor. 'bus' se consulta con `peek` (no consume ciclos).
+pub fn format_line<M: MemoryInterface>(regs: &Registers, bus: &M) -> String {
+    let pc = regs.pc;
+    let mem = |i: u16| bus.peek(pc.wrapping_add(i));
+    format!(
+        "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+        regs.a, regs.f, regs.b, regs.c, regs.d, regs.e, regs.h, regs.l,
+        regs.sp, pc, mem(0), mem(1), mem(2), mem(3)
+    )
+}
+
+/// Destino y filtros del trace.
+pub struct Tracer {
+    /// Solo se registran instrucciones con PC dentro de este rango.
+    pub pc_range: Option<RangeInclusive<u16>>,
+    /// Solo se registran instrucciones de este banco de ROM
+    /// (0x0000-0x3FFF cuenta como banco 0; fuera de la ROM no pasa el filtro).
+    pub bank: Option<u16>,
+    /// Símbolos para anotar cada línea. Rc: la misma tabla la comparte con el depurador.
+    pub symbols: Option<Rc<SymbolTable>>,
+    // Box<dyn Write>: cualquier destino (archivo, stdout, Vec<u8>...), como un io.Writer en Go.
+    output: Option<Box<dyn Write>>,
+    // Últimas N líneas (capacidad 0 = sin buffer circular).
+    ring: VecDeque<String>,
+    ring_capacity: usize,
+}
+
+impl Tracer {
+    /// Escribe todas las líneas en 'output' (conviene pasar un `BufWriter`).
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        Tracer { pc_range: None, bank: None, symbols: None, output: Some(output), ring: VecDeque::new(), ring_capacity: 0 }
+    }
+
+    /// No escribe nada: guarda solo las últimas 'capacity' líneas en memoria.
+    pub fn ring(capacity: usize) -> Self {
+        Tracer {
+            pc_range: None,
+            bank: None,
+            symbols: None,
+            output: None,
+            ring: VecDeque::with_capacity(capacity),
+            ring_capacity: capacity,
+        }
+    }
+
+    /// Activa (o redimensiona) el buffer circular además de la salida que ya hubiera.
+    pub fn set_ring_capacity(&mut self, capacity: usize) {
+        self.ring_capacity = capacity;
+        while self.ring.len() > capacity {
+            self.ring.pop_front();
+        }
+    }
+
+    /// ¿Pasa la instrucción en 'pc' los filtros?
+    fn accepts<M: MemoryInterface>(&self, pc: u16, bus: &M) -> bool {
+        if let Some(range) = &self.pc_range && !range.contains(&pc) {
+            return false;
+        }
+        if let Some(bank) = self.bank {
+            let current = match pc {
+                0x0000..=0x3FFF => 0,
+                0x4000..=0x7FFF => bus.rom_bank(),
+                _ => return false,
+            };
+            if current != bank {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Registra la instrucción que está a punto de ejecutarse. Lo llama `Cpu::step`.
+    pub fn record<M: MemoryInterface>(&mut self, regs: &Registers, bus: &M) {
+        if !self.accepts(regs.pc, bus) {
+            return;
+        }
+        let mut line = format_line(regs, bus);
+        if let Some(name) = self.symbols.as_ref().and_then(|s| s.describe(regs.pc, bus.rom_bank(), bus.ram_bank())) {
+            line.push_str(" ; ");
+            line.push_str(&name);
+        }
+        if let Some(out) = self.output.as_mut() {
+            // Un error de escritura (disco lleno...) desactiva la salida en vez de romper la emulación.
+            if writeln!(out, "{}", line).is_err() {
+                self.output = None;
+            }
+        }
+        if self.ring_capacity > 0 {
+            if self.ring.len() == self.ring_capacity {
+                self.ring.pop_front();
+            }
+            self.ring.push_back(line);
+        }
+    }
+
+    /// Líneas del buffer circular, de la más antigua a la más reciente.
+    pub fn recent(&self) -> impl Iterator<Item = &str> {
+        self.ring.iter().map(String::as_str)
+    }
+
+    /// Vuelca el buffer circular (típicamente a stderr tras un cuelgue).
+    pub fn dump_recent(&self, w: &mut dyn Write) -> io::Result<()> {
+        for line in &self.ring {
+            writeln!(w, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Vacía el buffer de la salida. Hay que llamarlo antes de salir:
+    /// el event loop de winit termina el proceso sin ejecutar los `Drop`.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.output.as_mut() {
+            Some(out) => out.flush(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// cpu/tests/common/mod.rs
+
+//! Utilidades compartidas por los tests de integración de la CPU.
+
+use cpu::MemoryInterface;
+
+/// Tipo de acceso registrado por el bus de prueba.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+    Internal,
+}
+
+/// Bus de prueba: 64KB de RAM plana, sin I/O ni mappers.
+/// Registra cada M-Cycle (lectura, escritura o interno) para compararlo
+/// con la lista de "cycles" del vector.
+pub struct FlatBus {
+    pub ram: Vec<u8>,
+    pub accesses: Vec<(u16, u8, Access)>,
+}
+
+impl FlatBus {
+    pub fn new() -> Self {
+        Self { ram: vec![0; 0x10000], accesses: Vec::new() }
+    }
+
+    /// Copia 'bytes' a partir de 'addr'.
+    #[allow(dead_code)] // No todos los tests de integración lo usan
+    pub fn load(&mut self, addr: u16, bytes: &[u8]) {
+        let start = addr as usize;
+        self.ram[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+}
+
+impl MemoryInterface for FlatBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        let val = self.ram[addr as usize];
+        self.accesses.push((addr, val, Access::Read));
+        val
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.ram[addr as usize] = val;
+        self.accesses.push((addr, val, Access::Write));
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.ram[addr as usize]
+    }
+
+    fn tick(&mut self) {
+        self.accesses.push((0, 0, Access::Internal));
+    }
+
+    // IE (0xFFFF) e IF (0xFF0F) son simples bytes de la RAM plana.
+    fn pending_interrupts(&self) -> u8 { self.ram[0xFFFF] & self.ram[0xFF0F] & 0x1F }
+    fn clear_interrupt(&mut self, mask: u8) { self.ram[0xFF0F] &= !mask; }
+}
@@ -0,0 +1,109 @@
+// cpu/tests/disasm.rs
+
+//! Desensamblador: sintaxis RGBDS, longitudes, destinos de salto y etiquetas.
+
+use cpu::Cpu;
+use cpu::disasm::{decode, disassemble, instruction_length};
+use cpu::opcodes::{Op, OPCODES};
+
+mod common;
+use common::FlatBus;
+
+/// Decodifica 'bytes' colocados en 'addr', sin símbolos.
+fn text_at(addr: u16, bytes: &[u8]) -> String {
+    let mut mem = vec![0u8; 0x10000];
+    mem[addr as usize..addr as usize + bytes.len()].copy_from_slice(bytes);
+    decode(addr, |a| mem[a as usize], None).text
+}
+
+#[test]
+fn base_and_cb_mnemonics_use_rgbds_syntax() {
+    assert_eq!(text_at(0x0100, &[0x00]), "nop");
+    assert_eq!(text_at(0x0100, &[0x3E, 0x12]), "ld a, $12");
+    assert_eq!(text_at(0x0100, &[0x2A]), "ld a, [hl+]");
+    assert_eq!(text_at(0x0100, &[0x32]), "ld [hl-], a");
+    assert_eq!(text_at(0x0100, &[0x36, 0x7F]), "ld [hl], $7F");
+    assert_eq!(text_at(0x0100, &[0x21, 0x34, 0x12]), "ld hl, $1234");
+    assert_eq!(text_at(0x0100, &[0x08, 0x00, 0xC0]), "ld [$C000], sp");
+    assert_eq!(text_at(0x0100, &[0xE0, 0x44]), "ldh [$FF44], a");
+    assert_eq!(text_at(0x0100, &[0xF2]), "ldh a, [c]");
+    assert_eq!(text_at(0x0100, &[0x96]), "sub a, [hl]");
+    assert_eq!(text_at(0x0100, &[0xFE, 0x90]), "cp a, $90");
+    assert_eq!(text_at(0x0100, &[0xF8, 0xFE]), "ld hl, sp-2");
+    assert_eq!(text_at(0x0100, &[0xE8, 0x05]), "add sp, 5");
+    assert_eq!(text_at(0x0100, &[0xF1]), "pop af");
+    assert_eq!(text_at(0x0100, &[0xFF]), "rst $38");
+    assert_eq!(text_at(0x0100, &[0xDD]), "db $DD");
+    assert_eq!(text_at(0x0100, &[0xCB, 0x37]), "swap a");
+    assert_eq!(text_at(0x0100, &[0xCB, 0x7C]), "bit 7, h");
+    assert_eq!(text_at(0x0100, &[0xCB, 0x86]), "res 0, [hl]");
+    assert_eq!(text_at(0x0100, &[0xCB, 0xFF]), "set 7, a");
+}
+
+#[test]
+fn jump_targets_are_resolved_and_symbolic() {
+    let mut mem = vec![0u8; 0x10000];
+    mem[0x0150..0x0152].copy_from_slice(&[0x20, 0xFC]); // JR NZ, -4
+    mem[0x0152..0x0155].copy_from_slice(&[0xCD, 0x00, 0x40]); // CALL $4000
+
+    let labels = |addr: u16| match addr {
+        0x014E => Some("Main.loop".to_string()),
+        0x0150 => Some("Main.wait".to_string()),
+        _ => None,
+    };
+    let listing = disassemble(0x0150, 0x0154, |a| mem[a as usize], Some(&labels));
+
+    assert_eq!(listing.len(), 2);
+    assert_eq!(listing[0].target, Some(0x014E));
+    assert_eq!(listing[0].text, "jr nz, Main.loop");
+    assert_eq!(listing[0].label.as_deref(), Some("Main.wait"));
+    assert_eq!(listing[1].target, Some(0x4000));
+    assert_eq!(listing[1].text, "call $4000");
+    assert_eq!(listing[1].to_string(), "$0152: CD 00 40  call $4000");
+}
+
+/// La longitud del desensamblador coincide con cuánto avanza PC la CPU
+/// en todos los opcodes que no saltan.
+#[test]
+fn lengths_match_cpu_execution() {
+    let jumps = [
+        0x18, 0x20, 0x28, 0x30, 0x38, 0xC2, 0xC3, 0xCA, 0xD2, 0xDA, 0xE9,
+        0xC4, 0xCC, 0xCD, 0xD4, 0xDC, 0xC0, 0xC8, 0xC9, 0xD0, 0xD8, 0xD9,
+        0xC7, 0xCF, 0xD7, 0xDF, 0xE7, 0xEF, 0xF7, 0xFF,
+        0x10, 0x76, // STOP/HALT: se saltan (STOP sin botón salta el segundo byte igualmente)
+    ];
+    for opcode in 0..=255u8 {
+        if jumps.contains(&opcode) { continue; }
+        let mut cpu = Cpu::new();
+        let mut bus = FlatBus::new();
+        bus.load(0x0100, &[opcode, 0x00, 0x00]);
+        cpu.regs.sp = 0xD000;
+        cpu.regs.set_hl(0xC000);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc - 0x0100, instruction_length(opcode), "opcode {:#04X}", opcode);
+    }
+}
+
+#[test]
+fn every_opcode_decodes() {
+    for opcode in 0..=255u8 {
+        for cb in 0..=255u8 {
+            let mem = [opcode, cb, 0x00];
+            let ins = decode(0, |a| mem.get(a as usize).copied().unwrap_or(0), None);
+            assert!(!ins.text.is_empty());
+            if opcode != 0xCB { break; }
+        }
+    }
+}
+
+/// Bytes y destino de salto salen de la misma tabla que ejecuta la CPU.
+#[test]
+fn bytes_and_targets_follow_the_opcode_table() {
+    for opcode in 0..=255u8 {
+        let ins = decode(0x0100, |a| if a == 0x0100 { opcode } else { 0x00 }, None);
+        let info = &OPCODES[opcode as usize];
+        assert_eq!(ins.len(), info.len as u16, "{:#04X}", opcode);
+        let jumps = matches!(info.op, Op::Jr(_) | Op::Jp(_) | Op::Call(_) | Op::Rst(_));
+        assert_eq!(ins.target.is_some(), jumps, "{:#04X}", opcode);
+    }
+}
@@ -0,0 +1,35 @@
+// cpu/tests/illegal_opcodes.rs
+
+//! Los 11 opcodes inexistentes cuelgan la CPU y se reportan como `CpuEvent`.
+
+use cpu::{Cpu, CpuEvent};
+
+mod common;
+use common::FlatBus;
+
+const ILLEGAL: [u8; 11] = [0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD];
+
+#[test]
+fn illegal_opcodes_lock_up_and_report_event() {
+    for opcode in ILLEGAL {
+        let mut cpu = Cpu::new();
+        let mut bus = FlatBus::new();
+        bus.load(0x0100, &[opcode, 0x3C]); // opcode ilegal ; INC A
+        cpu.step(&mut bus);
+
+        // Con la CPU colgada, ni siquiera una interrupción pendiente la despierta.
+        bus.ram[0xFFFF] = 0x01;
+        bus.ram[0xFF0F] = 0x01;
+        cpu.ime = true;
+
+        assert_eq!(cpu.take_event(), Some(CpuEvent::IllegalOpcode { pc: 0x0100, opcode }));
+        assert_eq!(cpu.take_event(), None);
+
+        let a = cpu.regs.a;
+        for _ in 0..10 {
+            assert_eq!(cpu.step(&mut bus), 1);
+        }
+        assert_eq!(cpu.regs.a, a, "{:#04X} no debe seguir ejecutando", opcode);
+        assert_eq!(cpu.regs.pc, 0x0101);
+    }
+}
@@ -0,0 +1,120 @@
+// cpu/tests/interrupts.rs
+
+//! Casos límite de interrupciones: retardo de EI, HALT bug, despertar de HALT con
+//! IME=0 e IE sobrescrito por el push del despacho.
+
+use cpu::Cpu;
+
+mod common;
+use common::FlatBus;
+
+const IE: usize = 0xFFFF;
+const IF: usize = 0xFF0F;
+
+/// CPU en 0x0100 con 'code' cargado y la interrupción V-Blank pendiente en IF.
+fn setup(code: &[u8]) -> (Cpu, FlatBus) {
+    let mut cpu = Cpu::new();
+    let mut bus = FlatBus::new();
+    bus.load(0x0100, code);
+    bus.ram[IF] = 0x01;
+    cpu.regs.sp = 0xD000;
+    (cpu, bus)
+}
+
+#[test]
+fn ei_takes_effect_after_the_next_instruction() {
+    let (mut cpu, mut bus) = setup(&[0xFB, 0x00, 0x00]); // EI ; NOP ; NOP
+    bus.ram[IE] = 0x01;
+
+    cpu.step(&mut bus); // EI
+    assert!(!cpu.ime);
+    cpu.step(&mut bus); // NOP: todavía no se despacha
+    assert_eq!(cpu.regs.pc, 0x0102);
+    assert!(cpu.ime);
+    assert_eq!(cpu.step(&mut bus), 5); // Despacho de V-Blank
+    assert_eq!(cpu.regs.pc, 0x0040);
+}
+
+#[test]
+fn di_right_after_ei_cancels_it() {
+    let (mut cpu, mut bus) = setup(&[0xFB, 0xF3, 0x00]); // EI ; DI ; NOP
+    bus.ram[IE] = 0x01;
+
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+    assert!(!cpu.ime);
+    assert_eq!(cpu.regs.pc, 0x0103);
+}
+
+#[test]
+fn halt_with_ime0_and_pending_interrupt_reads_next_byte_twice() {
+    let (mut cpu, mut bus) = setup(&[0x76, 0x3C, 0x00]); // HALT ; INC A ; NOP
+    bus.ram[IE] = 0x01;
+    cpu.regs.a = 0;
+
+    cpu.step(&mut bus); // HALT no duerme
+    assert!(!cpu.halted);
+    cpu.step(&mut bus); // INC A sin avanzar PC
+    assert_eq!(cpu.regs.pc, 0x0101);
+    cpu.step(&mut bus); // INC A otra vez
+    assert_eq!(cpu.regs.a, 2);
+    assert_eq!(cpu.regs.pc, 0x0102);
+}
+
+#[test]
+fn ei_halt_bug_returns_to_the_halt() {
+    let (mut cpu, mut bus) = setup(&[0xFB, 0x76, 0x00]); // EI ; HALT ; NOP
+    bus.ram[IE] = 0x01;
+
+    cpu.step(&mut bus); // EI
+    cpu.step(&mut bus); // HALT con IME todavía en 0: HALT bug
+    cpu.step(&mut bus); // Despacho
+    assert_eq!(cpu.regs.pc, 0x0040);
+    // Dirección de retorno: el propio HALT (0x0101)
+    assert_eq!(bus.ram[0xCFFE], 0x01);
+    assert_eq!(bus.ram[0xCFFF], 0x01);
+}
+
+#[test]
+fn halt_wakes_without_dispatch_when_ime_is_off() {
+    let (mut cpu, mut bus) = setup(&[0x76, 0x3C]); // HALT ; INC A
+    bus.ram[IF] = 0x00;
+    bus.ram[IE] = 0x01;
+    cpu.regs.a = 0;
+
+    cpu.step(&mut bus);
+    assert!(cpu.halted);
+    cpu.step(&mut bus); // Sigue dormida
+    assert!(cpu.halted);
+
+    bus.ram[IF] = 0x01;
+    cpu.step(&mut bus); // Despierta y ejecuta INC A, sin saltar al vector
+    assert!(!cpu.halted);
+    assert_eq!(cpu.regs.a, 1);
+    assert_eq!(cpu.regs.pc, 0x0102);
+}
+
+#[test]
+fn dispatch_is_cancelled_when_push_overwrites_ie() {
+    let (mut cpu, mut bus) = setup(&[0x00]);
+    bus.ram[IE] = 0x01;
+    cpu.ime = true;
+    cpu.regs.sp = 0x0000; // El byte alto de PC (0x01) se escribe en IE (0xFFFF)
+
+    assert_eq!(cpu.step(&mut bus), 5);
+    assert_eq!(bus.ram[IE], 0x01); // PC = 0x0100: el byte alto 0x01 deja V-Blank habilitada
+    assert_eq!(cpu.regs.pc, 0x0040);
+
+    // Ahora con PC = 0x0200: el push escribe 0x02 en IE y cancela V-Blank.
+    let (mut cpu, mut bus) = setup(&[0x00]);
+    bus.ram[IE] = 0x01;
+    cpu.ime = true;
+    cpu.regs.sp = 0x0000;
+    cpu.regs.pc = 0x0200;
+
+    cpu.step(&mut bus);
+    assert_eq!(bus.ram[IE], 0x02);
+    assert_eq!(cpu.regs.pc, 0x0000);
+    assert_eq!(bus.ram[IF], 0x01); // IF no se limpia
+}
@@ -0,0 +1,96 @@
+// cpu/tests/opcode_table.rs
+
+//! Coherencia entre la tabla de instrucciones (`cpu::opcodes`) y la ejecución real:
+//! ciclos devueltos = accesos al bus, longitud = avance de PC, flags según la columna "ZNHC".
+
+use cpu::Cpu;
+use cpu::opcodes::{CB_OPCODES, OPCODES, Op, OpInfo};
+
+mod common;
+use common::FlatBus;
+
+/// Generador pseudoaleatorio mínimo (xorshift) para variar registros y flags.
+struct Rng(u32);
+
+impl Rng {
+    fn next(&mut self) -> u8 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as u8
+    }
+}
+
+/// Ejecuta 'code' en 0x0100 con registros aleatorios y los flags dados.
+/// HL y SP apuntan a RAM para que [HL] y el stack no pisen el código.
+fn run(code: &[u8], f: u8, rng: &mut Rng) -> (Cpu, FlatBus, u32) {
+    let mut cpu = Cpu::new();
+    let mut bus = FlatBus::new();
+    bus.load(0x0100, code);
+    cpu.regs.a = rng.next();
+    cpu.regs.b = rng.next();
+    cpu.regs.c = rng.next();
+    cpu.regs.d = rng.next();
+    cpu.regs.e = rng.next();
+    cpu.regs.h = 0xC0;
+    cpu.regs.l = rng.next();
+    cpu.regs.f = f;
+    cpu.regs.sp = 0xD000;
+    cpu.regs.pc = 0x0100;
+    bus.ram[0xC000..0xC100].fill(rng.next());
+    let cycles = cpu.step(&mut bus);
+    (cpu, bus, cycles)
+}
+
+/// ¿La operación puede cambiar PC más allá de su longitud?
+fn jumps(op: Op) -> bool {
+    matches!(op, Op::Jr(_) | Op::Jp(_) | Op::JpHl | Op::Call(_) | Op::Ret(_) | Op::Reti | Op::Rst(_))
+}
+
+/// Todas las instrucciones (base y CB) junto con sus bytes.
+fn all_instructions() -> Vec<(Vec<u8>, &'static OpInfo)> {
+    let base = (0..=0xFFu8).filter(|&op| op != 0xCB).map(|op| (vec![op, 0x12, 0x34], &OPCODES[op as usize]));
+    let cb = (0..=0xFFu8).map(|cb| (vec![0xCB, cb], &CB_OPCODES[cb as usize]));
+    base.chain(cb).collect()
+}
+
+#[test]
+fn cycles_match_bus_accesses_and_table() {
+    let mut rng = Rng(0x1234_5678);
+    for (code, info) in all_instructions() {
+        // Con F=0x00 y F=0xF0 cada condición se cumple una vez y falla otra.
+        for f in [0x00, 0xF0] {
+            let (cpu, bus, cycles) = run(&code, f, &mut rng);
+            assert_eq!(cycles as usize, bus.accesses.len(), "{:02X?} {:?}: ciclos vs accesos", code, info.op);
+            assert!(
+                cycles == info.cycles as u32 || cycles == info.cycles_taken as u32,
+                "{:02X?} {:?}: {} ciclos, tabla {}/{}", code, info.op, cycles, info.cycles, info.cycles_taken
+            );
+            if !jumps(info.op) && info.op != Op::Illegal {
+                assert_eq!(cpu.regs.pc, 0x0100 + info.len as u16, "{:02X?} {:?}: longitud", code, info.op);
+            }
+        }
+    }
+}
+
+#[test]
+fn flags_follow_table_column() {
+    let mut rng = Rng(0x0BAD_CAFE);
+    for (code, info) in all_instructions() {
+        for _ in 0..8 {
+            let f = rng.next() & 0xF0;
+            let (cpu, _, _) = run(&code, f, &mut rng);
+            // Z N H C ocupan los bits 7..4 de F.
+            for (i, spec) in info.flags.chars().enumerate() {
+                let mask = 0x80 >> i;
+                let actual = cpu.regs.f & mask;
+                match spec {
+                    '-' => assert_eq!(actual, f & mask, "{:02X?} {:?}: flag {} debería quedar igual", code, info.op, i),
+                    '0' => assert_eq!(actual, 0, "{:02X?} {:?}: flag {} debería ser 0", code, info.op, i),
+                    '1' => assert_eq!(actual, mask, "{:02X?} {:?}: flag {} debería ser 1", code, info.op, i),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// cpu/tests/profile.rs
+
+//! Profiler: pila de llamadas por CALL/RET/interrupciones, ciclos inclusivos/exclusivos y folded stacks.
+
+use std::rc::Rc;
+
+use cpu::Cpu;
+use cpu::profile::{Location, Profiler};
+use cpu::symbols::SymbolTable;
+
+mod common;
+use common::FlatBus;
+
+/// Main llama a Func (que llama a Other) y luego a Other, y se queda en un bucle.
+fn setup() -> (Cpu, FlatBus) {
+    let mut bus = FlatBus::new();
+    bus.load(0x0100, &[0xCD, 0x00, 0x02, 0xCD, 0x10, 0x02, 0x18, 0xFE]); // call Func ; call Other ; jr @
+    bus.load(0x0200, &[0xCD, 0x10, 0x02, 0xC9]); // Func: call Other ; ret
+    bus.load(0x0210, &[0x00, 0xC9]); // Other: nop ; ret
+    let mut cpu = Cpu::new();
+    cpu.regs.pc = 0x0100;
+    cpu.regs.sp = 0xFFFE;
+    cpu.profiler = Some(Profiler::new());
+    (cpu, bus)
+}
+
+const FUNC: Location = Location { bank: 0, addr: 0x0200 };
+const OTHER: Location = Location { bank: 0, addr: 0x0210 };
+
+#[test]
+fn inclusive_and_exclusive_cycles() {
+    let (mut cpu, mut bus) = setup();
+    for _ in 0..9 {
+        cpu.step(&mut bus);
+    }
+    let profiler = cpu.profiler.as_mut().unwrap();
+    assert_eq!(profiler.depth(), 0);
+    assert_eq!(profiler.total_cycles(), 35);
+
+    let stats = profiler.stats();
+    let get = |l: Option<Location>| stats.iter().find(|(loc, _)| *loc == l).unwrap().1;
+    // CALL (6) + CALL (6) + JR (3) en la raíz.
+    assert_eq!(get(None).exclusive, 15);
+    assert_eq!(get(None).inclusive, 35);
+    // Func: su CALL (6) y su RET (4), más Other anidado (NOP 1 + RET 4).
+    assert_eq!((get(Some(FUNC)).calls, get(Some(FUNC)).exclusive, get(Some(FUNC)).inclusive), (1, 10, 15));
+    assert_eq!((get(Some(OTHER)).calls, get(Some(OTHER)).exclusive, get(Some(OTHER)).inclusive), (2, 10, 10));
+}
+
+#[test]
+fn folded_stacks_use_symbols() {
+    let (mut cpu, mut bus) = setup();
+    let profiler = cpu.profiler.as_mut().unwrap();
+    profiler.symbols = Some(Rc::new(SymbolTable::parse("00:0200 Func\n00:0210 Other\n")));
+    for _ in 0..9 {
+        cpu.step(&mut bus);
+    }
+    let mut out = Vec::new();
+    cpu.profiler.as_mut().unwrap().write_folded(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "(root) 15\nFunc 10\nFunc;Other 5\nOther 5\n");
+}
+
+#[test]
+fn interrupts_enter_and_reti_leaves() {
+    let (mut cpu, mut bus) = setup();
+    bus.load(0x0040, &[0xD9]); // reti
+    bus.ram[0xFFFF] = 0x01;
+    bus.ram[0xFF0F] = 0x01;
+    cpu.ime = true;
+
+    cpu.step(&mut bus); // despacho (5)
+    assert_eq!(cpu.profiler.as_ref().unwrap().depth(), 1);
+    cpu.step(&mut bus); // reti (4)
+    let profiler = cpu.profiler.as_mut().unwrap();
+    assert_eq!(profiler.depth(), 0);
+    profiler.end_frame();
+
+    let frame = profiler.frames().last().unwrap();
+    assert_eq!(frame.cycles, 9);
+    assert_eq!(frame.functions, vec![(Some(Location { bank: 0, addr: 0x0040 }), 9)]);
+}
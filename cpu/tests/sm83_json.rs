@@ -0,0 +1,202 @@
+// cpu/tests/sm83_json.rs
+
+//! Conformidad instrucción por instrucción con los vectores JSON públicos de SM83
+//! (formato "SingleStepTests": estado inicial, estado final y ciclos de bus).
+//!
+//! Los vectores no se incluyen en el repositorio (son ~500 archivos de 1000 casos).
+//! Se buscan en `cpu/tests/fixtures/sm83/` (o en la carpeta de `SM83_TESTS`),
+//! un archivo por opcode: `00.json` ... `ff.json` y `cb 00.json` ... `cb ff.json`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cpu::Cpu;
+use serde_json::Value;
+
+mod common;
+use common::{Access, FlatBus};
+
+fn field(state: &Value, name: &str) -> u16 {
+    state[name].as_u64().unwrap_or_else(|| panic!("campo '{}' ausente", name)) as u16
+}
+
+/// Carga el estado "initial" de un vector en la CPU y el bus.
+fn load_state(state: &Value, cpu: &mut Cpu, bus: &mut FlatBus) {
+    let r = &mut cpu.regs;
+    r.a = field(state, "a") as u8;
+    r.f = field(state, "f") as u8;
+    r.b = field(state, "b") as u8;
+    r.c = field(state, "c") as u8;
+    r.d = field(state, "d") as u8;
+    r.e = field(state, "e") as u8;
+    r.h = field(state, "h") as u8;
+    r.l = field(state, "l") as u8;
+    r.sp = field(state, "sp");
+    r.pc = field(state, "pc");
+    cpu.ime = state["ime"].as_u64() == Some(1);
+    cpu.ei_pending = state["ei"].as_u64() == Some(1);
+    if let Some(ie) = state["ie"].as_u64() {
+        bus.ram[0xFFFF] = ie as u8;
+    }
+    for pair in state["ram"].as_array().into_iter().flatten() {
+        bus.ram[pair[0].as_u64().unwrap() as usize] = pair[1].as_u64().unwrap() as u8;
+    }
+}
+
+/// Compara el estado final. Devuelve la lista de diferencias (vacía = OK).
+fn compare_state(state: &Value, cpu: &Cpu, bus: &FlatBus) -> Vec<String> {
+    let r = &cpu.regs;
+    let mut diffs = Vec::new();
+    let regs: [(&str, u16); 10] = [
+        ("a", r.a as u16), ("f", r.f as u16), ("b", r.b as u16), ("c", r.c as u16),
+        ("d", r.d as u16), ("e", r.e as u16), ("h", r.h as u16), ("l", r.l as u16),
+        ("sp", r.sp), ("pc", r.pc),
+    ];
+    for (name, actual) in regs {
+        let expected = field(state, name);
+        if actual != expected {
+            diffs.push(format!("{}={:#06X} (esperado {:#06X})", name, actual, expected));
+        }
+    }
+    if let Some(ime) = state["ime"].as_u64() && cpu.ime != (ime == 1) {
+        diffs.push(format!("ime={} (esperado {})", cpu.ime as u8, ime));
+    }
+    if let Some(ei) = state["ei"].as_u64() && cpu.ei_pending != (ei == 1) {
+        diffs.push(format!("ei={} (esperado {})", cpu.ei_pending as u8, ei));
+    }
+    for pair in state["ram"].as_array().into_iter().flatten() {
+        let addr = pair[0].as_u64().unwrap() as usize;
+        let expected = pair[1].as_u64().unwrap() as u8;
+        if bus.ram[addr] != expected {
+            diffs.push(format!("[{:#06X}]={:#04X} (esperado {:#04X})", addr, bus.ram[addr], expected));
+        }
+    }
+    diffs
+}
+
+/// Ejecuta un caso. Devuelve `Err` con la descripción si no coincide.
+fn run_case(case: &Value) -> Result<(), String> {
+    let mut cpu = Cpu::new();
+    let mut bus = FlatBus::new();
+    load_state(&case["initial"], &mut cpu, &mut bus);
+
+    let m_cycles = cpu.step(&mut bus);
+
+    let mut diffs = compare_state(&case["final"], &cpu, &bus);
+
+    // "cycles": una entrada por M-Cycle. Los ciclos internos son 'null'
+    // o no indican lectura ni escritura en su tercera columna.
+    let cycles = case["cycles"].as_array().cloned().unwrap_or_default();
+    if m_cycles as usize != cycles.len() {
+        diffs.push(format!("{} M-Cycles (esperado {})", m_cycles, cycles.len()));
+    }
+    let expected_accesses: Vec<(u16, u8, Access)> = cycles
+        .iter()
+        .map(|c| {
+            let kind = c.get(2).and_then(Value::as_str).unwrap_or("---");
+            let access = if kind.contains('r') { Access::Read } else if kind.contains('w') { Access::Write } else { Access::Internal };
+            if access == Access::Internal {
+                (0, 0, access)
+            } else {
+                (c[0].as_u64().unwrap_or(0) as u16, c[1].as_u64().unwrap_or(0) as u8, access)
+            }
+        })
+        .collect();
+    if bus.accesses != expected_accesses {
+        diffs.push(format!("accesos de bus {:X?} (esperado {:X?})", bus.accesses, expected_accesses));
+    }
+
+    if diffs.is_empty() { Ok(()) } else { Err(diffs.join(", ")) }
+}
+
+fn fixtures_dir() -> PathBuf {
+    match env::var("SM83_TESTS") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sm83"),
+    }
+}
+
+#[test]
+fn sm83_single_step_vectors() {
+    let dir = fixtures_dir();
+    let Ok(entries) = fs::read_dir(&dir) else {
+        println!("Sin vectores SM83 en {} (omitido)", dir.display());
+        return;
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut failed_opcodes = Vec::new();
+    let mut total = 0;
+
+    for path in &files {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let text = fs::read_to_string(path).unwrap();
+        let cases: Vec<Value> = serde_json::from_str(&text).unwrap();
+
+        let mut failures = 0;
+        let mut first_error = None;
+        for case in &cases {
+            total += 1;
+            if let Err(e) = run_case(case) {
+                failures += 1;
+                first_error.get_or_insert_with(|| format!("{}: {}", case["name"], e));
+            }
+        }
+        if failures > 0 {
+            println!("[{}] {}/{} fallos. Primero: {}", name, failures, cases.len(), first_error.unwrap());
+            failed_opcodes.push(name);
+        }
+    }
+
+    println!("{} archivos, {} casos, {} opcodes con fallos", files.len(), total, failed_opcodes.len());
+    assert!(failed_opcodes.is_empty(), "Opcodes que no cumplen: {:?}", failed_opcodes);
+}
+
+/// Autoverificación del arnés con vectores escritos a mano (siempre se ejecutan).
+#[test]
+fn harness_runs_handwritten_vectors() {
+    let vectors = r#"[
+        {
+            "name": "00 nop",
+            "initial": {"pc": 256, "sp": 65534, "a": 1, "b": 2, "c": 3, "d": 4, "e": 5, "f": 176, "h": 6, "l": 7, "ime": 0, "ie": 0,
+                        "ram": [[256, 0]]},
+            "final":   {"pc": 257, "sp": 65534, "a": 1, "b": 2, "c": 3, "d": 4, "e": 5, "f": 176, "h": 6, "l": 7, "ime": 0, "ie": 0,
+                        "ram": [[256, 0]]},
+            "cycles": [[256, 0, "r-m"]]
+        },
+        {
+            "name": "c5 push bc",
+            "initial": {"pc": 512, "sp": 53248, "a": 0, "b": 18, "c": 52, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+                        "ram": [[512, 197]]},
+            "final":   {"pc": 513, "sp": 53246, "a": 0, "b": 18, "c": 52, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+                        "ram": [[512, 197], [53247, 18], [53246, 52]]},
+            "cycles": [[512, 197, "r-m"], null, [53247, 18, "-wm"], [53246, 52, "-wm"]]
+        },
+        {
+            "name": "cb 37 swap a",
+            "initial": {"pc": 1024, "sp": 65534, "a": 240, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+                        "ram": [[1024, 203], [1025, 55]]},
+            "final":   {"pc": 1026, "sp": 65534, "a": 15, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+                        "ram": [[1024, 203], [1025, 55]]},
+            "cycles": [[1024, 203, "r-m"], [1025, 55, "r-m"]]
+        },
+        {
+            "name": "c0 ret nz (tomado)",
+            "initial": {"pc": 768, "sp": 53248, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+                        "ram": [[768, 192], [53248, 52], [53249, 18]]},
+            "final":   {"pc": 4660, "sp": 53250, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+                        "ram": [[768, 192], [53248, 52], [53249, 18]]},
+            "cycles": [[768, 192, "r-m"], null, [53248, 52, "r-m"], [53249, 18, "r-m"], null]
+        }
+    ]"#;
+    let cases: Vec<Value> = serde_json::from_str(vectors).unwrap();
+    for case in &cases {
+        assert_eq!(run_case(case), Ok(()), "{}", case["name"]);
+    }
+}
@@ -0,0 +1,68 @@
+// cpu/tests/symbols.rs
+
+//! Tabla de símbolos `.sym`: parseo, búsqueda por banco y desplazamientos.
+
+use cpu::symbols::SymbolTable;
+
+const SYM: &str = "\
+; File generated by rgblink
+00:0150 Main
+00:0158 Main.loop
+01:4000 BankOne
+02:4000 BankTwo
+02:4010 BankTwo.inner ; comentario al final
+00:C000 wBuffer
+00:A000 sSave
+01:A000 sSaveBankOne
+00:FF80 hCounter
+esto no es un símbolo
+";
+
+#[test]
+fn parses_and_skips_comments_and_garbage() {
+    let table = SymbolTable::parse(SYM);
+    assert_eq!(table.len(), 9);
+    assert_eq!(table.find("BankTwo.inner"), Some((2, 0x4010)));
+    assert_eq!(table.find("hCounter"), Some((0, 0xFF80)));
+    assert_eq!(table.find("sSaveBankOne"), Some((1, 0xA000)));
+    assert_eq!(table.find("nada"), None);
+}
+
+#[test]
+fn describe_uses_nearest_label_and_offset() {
+    let table = SymbolTable::parse(SYM);
+    assert_eq!(table.describe(0x0158, 1, 0).as_deref(), Some("Main.loop"));
+    assert_eq!(table.describe(0x015B, 1, 0).as_deref(), Some("Main.loop+3"));
+    assert_eq!(table.describe(0x0100, 1, 0), None);
+    assert_eq!(table.describe(0xC012, 1, 0).as_deref(), Some("wBuffer+12"));
+}
+
+#[test]
+fn switchable_bank_depends_on_mapped_bank() {
+    let table = SymbolTable::parse(SYM);
+    assert_eq!(table.describe(0x4005, 1, 0).as_deref(), Some("BankOne+5"));
+    assert_eq!(table.describe(0x4015, 2, 0).as_deref(), Some("BankTwo.inner+5"));
+    assert_eq!(table.describe(0x4000, 3, 0), None);
+    // Las etiquetas del banco 0 no se arrastran a 0x4000-0x7FFF.
+    assert_eq!(table.label(0x4000, 0, 0), None);
+    assert_eq!(table.label(0x0150, 7, 0), Some("Main"));
+}
+
+#[test]
+fn ram_lookups_stay_inside_their_region_and_bank() {
+    let table = SymbolTable::parse(SYM);
+    // Ni WRAM nombra registros I/O, ni SRAM nombra WRAM.
+    assert_eq!(table.describe(0xFF40, 1, 0), None);
+    assert_eq!(table.describe(0xE000, 1, 0), None);
+    assert_eq!(table.describe(0xC123, 1, 0).as_deref(), Some("wBuffer+123"));
+    assert_eq!(table.describe(0xFF85, 1, 0).as_deref(), Some("hCounter+5"));
+
+    let sram = SymbolTable::parse("00:A000 sOnly\n");
+    assert_eq!(sram.describe(0xC123, 1, 0), None);
+
+    // La SRAM depende del banco de RAM mapeado.
+    assert_eq!(table.describe(0xA010, 1, 0).as_deref(), Some("sSave+10"));
+    assert_eq!(table.describe(0xA010, 1, 1).as_deref(), Some("sSaveBankOne+10"));
+    assert_eq!(table.describe(0xA010, 1, 2), None);
+    assert_eq!(table.label(0xA000, 1, 1), Some("sSaveBankOne"));
+}
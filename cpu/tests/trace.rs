@@ -0,0 +1,79 @@
+// cpu/tests/trace.rs
+
+//! Trace log en formato Gameboy Doctor: formato exacto de la línea, filtros y buffer circular.
+
+use cpu::Cpu;
+use cpu::trace::{format_line, Tracer};
+
+mod common;
+use common::FlatBus;
+
+/// Estado de arranque post-boot de DMG, con 'code' en 0x0100.
+fn setup(code: &[u8]) -> (Cpu, FlatBus) {
+    let mut cpu = Cpu::new();
+    let mut bus = FlatBus::new();
+    bus.load(0x0100, code);
+    cpu.regs.a = 0x01;
+    cpu.regs.f = 0xB0;
+    cpu.regs.c = 0x13;
+    cpu.regs.e = 0xD8;
+    cpu.regs.h = 0x01;
+    cpu.regs.l = 0x4D;
+    cpu.regs.sp = 0xFFFE;
+    cpu.regs.pc = 0x0100;
+    (cpu, bus)
+}
+
+#[test]
+fn line_matches_gameboy_doctor_format() {
+    let (cpu, bus) = setup(&[0x00, 0xC3, 0x13, 0x02]);
+    assert_eq!(
+        format_line(&cpu.regs, &bus),
+        "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02"
+    );
+    // 'peek' no cuenta como acceso de bus.
+    assert!(bus.accesses.is_empty());
+}
+
+#[test]
+fn ring_buffer_keeps_last_entries_before_each_instruction() {
+    let (mut cpu, mut bus) = setup(&[0x00, 0x3C, 0x3C, 0x3C]); // NOP ; INC A x3
+    cpu.tracer = Some(Tracer::ring(2));
+    for _ in 0..4 {
+        cpu.step(&mut bus);
+    }
+    let lines: Vec<&str> = cpu.tracer.as_ref().unwrap().recent().collect();
+    assert_eq!(lines.len(), 2);
+    // Estado ANTES de ejecutar: el tercer INC A todavía ve A=3.
+    assert!(lines[0].starts_with("A:02 ") && lines[0].contains("PC:0102"), "{}", lines[0]);
+    assert!(lines[1].starts_with("A:03 ") && lines[1].contains("PC:0103"), "{}", lines[1]);
+}
+
+#[test]
+fn filters_by_pc_range_and_bank() {
+    // JP $4000 ; en 0x4000: NOP ; NOP
+    let (mut cpu, mut bus) = setup(&[0xC3, 0x00, 0x40]);
+    bus.load(0x4000, &[0x00, 0x00]);
+
+    let mut tracer = Tracer::ring(16);
+    tracer.pc_range = Some(0x4001..=0x7FFF);
+    cpu.tracer = Some(tracer);
+    for _ in 0..3 {
+        cpu.step(&mut bus);
+    }
+    let lines: Vec<&str> = cpu.tracer.as_ref().unwrap().recent().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("PC:4001"));
+
+    // Sin mapper, 0x4000-0x7FFF es el banco 1; la ROM fija (0x0000-0x3FFF) es el 0.
+    let (mut cpu, mut bus) = setup(&[0xC3, 0x00, 0x40]);
+    bus.load(0x4000, &[0x00]);
+    let mut tracer = Tracer::ring(16);
+    tracer.bank = Some(0);
+    cpu.tracer = Some(tracer);
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+    let lines: Vec<&str> = cpu.tracer.as_ref().unwrap().recent().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("PC:0100"));
+}
@@ -0,0 +1,416 @@
+// debugger/src/gdb.rs
+
+//! Servidor GDB Remote Serial Protocol (RSP) sobre TCP local.
+//!
+//! Permite depurar la ROM desde cualquier IDE/cliente que hable el protocolo de GDB.
+//! Los breakpoints y watchpoints son los del `Debugger`; la memoria se lee y escribe
+//! a través del Bus (sin avanzar el reloj).
+//!
+//! Paquetes: `$<datos>#<checksum>`, donde checksum es la suma módulo 256 de los datos
+//! en hex. Cada paquete se confirma con '+' (salvo tras `QStartNoAckMode`).
+//! Los acks del cliente a nuestras respuestas no se esperan: los descarta el lector de paquetes.
+//!
+//! Registros (16 bits, little-endian, en este orden): AF, BC, DE, HL, SP, PC.
+
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use cpu::Cpu;
+use memory::Bus;
+
+use crate::{Access, Debugger, StopReason, Watchpoint};
+
+/// Tamaño máximo de paquete que anunciamos en qSupported (en bytes).
+/// Ni aceptamos paquetes más grandes ni respondemos con más datos.
+const PACKET_SIZE: usize = 0x4000;
+
+/// M-Cycles que se ejecutan entre comprobaciones de Ctrl-C durante `continue` (~1 frame).
+const CHUNK_M_CYCLES: u64 = 17556;
+
+/// Descripción de los registros para el cliente (qXfer:features:read:target.xml).
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.gnu.gdb.sm83.core">
+    <reg name="af" bitsize="16" type="int"/>
+    <reg name="bc" bitsize="16" type="int"/>
+    <reg name="de" bitsize="16" type="int"/>
+    <reg name="hl" bitsize="16" type="int"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>"#;
+
+/// Escucha en 'addr' (ej: "127.0.0.1:2345") y atiende una conexión hasta que el cliente se desconecta.
+pub fn serve(addr: &str, debugger: &mut Debugger, cpu: &mut Cpu, bus: &mut Bus) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("Esperando a GDB en {}", listener.local_addr()?);
+    let (stream, peer) = listener.accept()?;
+    println!("GDB conectado desde {}", peer);
+    Session::new(stream).run(debugger, cpu, bus)
+}
+
+/// Resultado de procesar un paquete.
+enum Reply {
+    Packet(String),
+    /// El cliente se desconecta (D / k): respondemos y cerramos.
+    Close(String),
+}
+
+/// Una conexión con un cliente GDB.
+pub struct Session {
+    stream: TcpStream,
+    no_ack: bool,
+    // Bytes leídos mientras la CPU corría (buscando Ctrl-C) que no eran 0x03:
+    // pertenecen al siguiente paquete y el lector los consume antes que el socket.
+    pending: VecDeque<u8>,
+}
+
+impl Session {
+    pub fn new(stream: TcpStream) -> Self {
+        // Paquetes pequeños de ida y vuelta: sin Nagle cada uno espera ~40ms al ACK de TCP.
+        let _ = stream.set_nodelay(true);
+        Session { stream, no_ack: false, pending: VecDeque::new() }
+    }
+
+    /// Atiende paquetes hasta que el cliente cierra la conexión o se desconecta.
+    pub fn run(&mut self, debugger: &mut Debugger, cpu: &mut Cpu, bus: &mut Bus) -> io::Result<()> {
+        while let Some(packet) = self.read_packet()? {
+            match self.handle(&packet, debugger, cpu, bus)? {
+                Reply::Packet(data) => self.send(&data)?,
+                Reply::Close(data) => {
+                    self.send(&data)?;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lee el siguiente paquete. None = conexión cerrada.
+    /// Un Ctrl-C (0x03) fuera de un paquete se entrega como "\x03".
+    /// Los paquetes con checksum incorrecto o más largos que PACKET_SIZE se rechazan
+    /// con '-' y se espera el siguiente (en un bucle: un cliente ruidoso no crece el stack).
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // Buscar el inicio '$' (ignorando acks '+'/'-').
+            loop {
+                let Some(byte) = self.read_byte()? else { return Ok(None) };
+                match byte {
+                    b'$' => break,
+                    0x03 => return Ok(Some("\x03".to_string())),
+                    _ => {}
+                }
+            }
+
+            let mut data = Vec::new();
+            let mut too_long = false;
+            loop {
+                let Some(byte) = self.read_byte()? else { return Ok(None) };
+                if byte == b'#' { break; }
+                // Pasado el límite se sigue leyendo hasta '#', pero sin guardar nada.
+                if data.len() < PACKET_SIZE { data.push(byte); } else { too_long = true; }
+            }
+            let mut checksum = [0u8; 2];
+            for digit in &mut checksum {
+                let Some(byte) = self.read_byte()? else { return Ok(None) };
+                *digit = byte;
+            }
+
+            let expected = std::str::from_utf8(&checksum).ok().and_then(|s| u8::from_str_radix(s, 16).ok());
+            let actual = data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+            let valid = !too_long && expected == Some(actual);
+            if !self.no_ack {
+                // '-' pide al cliente que reenvíe el paquete.
+                let ack: &[u8] = if valid { b"+" } else { b"-" };
+                self.stream.write_all(ack)?;
+            }
+            if valid {
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    /// Siguiente byte del cliente: primero los que guardó `interrupted`. None = conexión cerrada.
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        if let Some(byte) = self.pending.pop_front() {
+            return Ok(Some(byte));
+        }
+        let mut byte = [0u8; 1];
+        match self.stream.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// Envía una respuesta. El '+' del cliente no se espera aquí: puede llegar con la CPU
+    /// ya corriendo, y `read_packet` lo salta igual (un '-' pediría reenviar; no lo reintentamos).
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        write!(self.stream, "${}#{:02x}", data, checksum)?;
+        self.stream.flush()
+    }
+
+    fn handle(&mut self, packet: &str, debugger: &mut Debugger, cpu: &mut Cpu, bus: &mut Bus) -> io::Result<Reply> {
+        // El primer carácter es el comando; el resto, sus argumentos.
+        let (cmd, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match cmd {
+            "\x03" => "S02".to_string(), // Ctrl-C con la CPU ya parada
+            "?" => "S05".to_string(),
+            "g" => read_registers(cpu),
+            "G" => write_registers(cpu, args),
+            "p" => read_register(cpu, args),
+            "P" => write_register(cpu, args),
+            "m" => read_memory(bus, args),
+            "M" => write_memory(bus, args),
+            "s" => {
+                set_pc(cpu, args);
+                let stop = debugger.step(cpu, bus).1.unwrap_or(StopReason::Step);
+                stop_reply(stop)
+            }
+            "c" => {
+                set_pc(cpu, args);
+                self.resume(debugger, cpu, bus)?
+            }
+            "Z" => insert_point(debugger, args),
+            "z" => remove_point(debugger, args),
+            "H" => "OK".to_string(), // Un solo hilo: cualquier selección vale
+            "T" => "OK".to_string(),
+            "D" => return Ok(Reply::Close("OK".to_string())),
+            "k" => return Ok(Reply::Close(String::new())),
+            "q" | "Q" => self.query(packet),
+            // Paquete no soportado: la respuesta vacía se lo indica al cliente.
+            _ => String::new(),
+        };
+        Ok(Reply::Packet(reply))
+    }
+
+    /// Paquetes de consulta (qXxx / QXxx).
+    fn query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!("PacketSize={:x};qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+", PACKET_SIZE);
+        }
+        if packet == "QStartNoAckMode" {
+            // El '+' de este paquete ya se envió; a partir de la respuesta, sin acks.
+            self.no_ack = true;
+            return "OK".to_string();
+        }
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return xfer(TARGET_XML, range);
+        }
+        match packet {
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// `continue`: ejecuta por tramos, mirando entre tramos si llegó un Ctrl-C.
+    fn resume(&mut self, debugger: &mut Debugger, cpu: &mut Cpu, bus: &mut Bus) -> io::Result<String> {
+        let mut first = true;
+        loop {
+            // 'run' no mira los breakpoints de su primera instrucción (para poder continuar
+            // desde uno): entre tramos hay que comprobarlos aquí.
+            if !first && let Some(stop) = debugger.check_before(cpu, bus) {
+                return Ok(stop_reply(stop));
+            }
+            first = false;
+            if let Some(stop) = debugger.run(cpu, bus, Some(CHUNK_M_CYCLES)) {
+                return Ok(stop_reply(stop));
+            }
+            if self.interrupted()? {
+                return Ok("S02".to_string()); // SIGINT
+            }
+        }
+    }
+
+    /// ¿Envió el cliente un Ctrl-C (0x03)? No bloquea.
+    /// Cualquier otro byte (un ack, el inicio de un paquete) se guarda para `read_packet`.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0u8; 1];
+        let result = match self.stream.read(&mut byte) {
+            Ok(1) if byte[0] == 0x03 => Ok(true),
+            Ok(1) => {
+                self.pending.push_back(byte[0]);
+                Ok(false)
+            }
+            Ok(_) => Err(io::Error::new(ErrorKind::ConnectionAborted, "GDB cerró la conexión")),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+}
+
+// --- PAQUETES ---
+
+/// Respuesta de parada: T05 con el motivo (breakpoint, watchpoint) o la señal que corresponda.
+fn stop_reply(stop: StopReason) -> String {
+    match stop {
+        StopReason::Breakpoint(_) => "T05swbreak:;".to_string(),
+        StopReason::Watchpoint { addr, access: Access::Write, .. } => format!("T05watch:{:x};", addr),
+        StopReason::Watchpoint { addr, access: Access::Read, .. } => format!("T05rwatch:{:x};", addr),
+        StopReason::Locked { .. } => "S04".to_string(), // SIGILL: opcode ilegal
+        StopReason::UserBreak => "S02".to_string(), // SIGINT
+        StopReason::Step | StopReason::Opcode { .. } | StopReason::Interrupt { .. } => "S05".to_string(),
+    }
+}
+
+fn registers(cpu: &Cpu) -> [u16; 6] {
+    let r = &cpu.regs;
+    [r.get_af(), r.get_bc(), r.get_de(), r.get_hl(), r.sp, r.pc]
+}
+
+fn set_register(cpu: &mut Cpu, index: usize, val: u16) -> bool {
+    let r = &mut cpu.regs;
+    match index {
+        0 => r.set_af(val),
+        1 => r.set_bc(val),
+        2 => r.set_de(val),
+        3 => r.set_hl(val),
+        4 => r.sp = val,
+        5 => r.pc = val,
+        _ => return false,
+    }
+    true
+}
+
+/// 16 bits en hex little-endian: 0x1234 -> "3412".
+fn le_hex(val: u16) -> String {
+    format!("{:02x}{:02x}", val as u8, (val >> 8) as u8)
+}
+
+fn parse_le_hex(text: &str) -> Option<u16> {
+    let bytes = hex_bytes(text)?;
+    match bytes.as_slice() {
+        [lo, hi] => Some(u16::from_le_bytes([*lo, *hi])),
+        _ => None,
+    }
+}
+
+fn hex_bytes(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn read_registers(cpu: &Cpu) -> String {
+    registers(cpu).iter().map(|&v| le_hex(v)).collect()
+}
+
+fn write_registers(cpu: &mut Cpu, args: &str) -> String {
+    for i in 0..6 {
+        match args.get(i * 4..i * 4 + 4).and_then(parse_le_hex) {
+            Some(val) => { set_register(cpu, i, val); }
+            None => return "E01".to_string(),
+        }
+    }
+    "OK".to_string()
+}
+
+fn read_register(cpu: &Cpu, args: &str) -> String {
+    match usize::from_str_radix(args, 16).ok().and_then(|i| registers(cpu).get(i).copied()) {
+        Some(val) => le_hex(val),
+        None => "E01".to_string(),
+    }
+}
+
+fn write_register(cpu: &mut Cpu, args: &str) -> String {
+    let parsed = args.split_once('=').and_then(|(index, val)| {
+        Some((usize::from_str_radix(index, 16).ok()?, parse_le_hex(val)?))
+    });
+    match parsed {
+        Some((index, val)) if set_register(cpu, index, val) => "OK".to_string(),
+        _ => "E01".to_string(),
+    }
+}
+
+/// "addr,len" en hex.
+fn parse_addr_len(args: &str) -> Option<(u16, usize)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((u16::from_str_radix(addr, 16).ok()?, usize::from_str_radix(len, 16).ok()?))
+}
+
+/// `m addr,len`: lectura sin efectos (`Bus::peek` no avanza el reloj ni toca el mapper).
+/// La respuesta (2 caracteres por byte) no puede pasar de PACKET_SIZE.
+fn read_memory(bus: &Bus, args: &str) -> String {
+    match parse_addr_len(args) {
+        Some((addr, len)) if len <= PACKET_SIZE / 2 => (0..len).map(|i| format!("{:02x}", bus.peek(addr.wrapping_add(i as u16)))).collect(),
+        _ => "E01".to_string(),
+    }
+}
+
+/// `M addr,len:datos`. Escribir en la zona de ROM llega al MBC (cambio de banco), como en el hardware.
+fn write_memory(bus: &mut Bus, args: &str) -> String {
+    let parsed = args.split_once(':').and_then(|(head, data)| Some((parse_addr_len(head)?, hex_bytes(data)?)));
+    match parsed {
+        Some(((addr, len), data)) if data.len() == len => {
+            for (i, byte) in data.into_iter().enumerate() {
+                bus.write(addr.wrapping_add(i as u16), byte);
+            }
+            "OK".to_string()
+        }
+        _ => "E01".to_string(),
+    }
+}
+
+/// `c [addr]` / `s [addr]`: continuar desde otra dirección.
+fn set_pc(cpu: &mut Cpu, args: &str) {
+    if let Ok(addr) = u16::from_str_radix(args, 16) {
+        cpu.regs.pc = addr;
+    }
+}
+
+/// "tipo,addr,kind": 0/1 = breakpoint, 2 = escritura, 3 = lectura, 4 = acceso.
+fn parse_point(args: &str) -> Option<(u8, u16, u16)> {
+    let mut parts = args.split(',');
+    let kind = parts.next()?.parse().ok()?;
+    let addr = u16::from_str_radix(parts.next()?, 16).ok()?;
+    let len = u16::from_str_radix(parts.next()?, 16).ok()?;
+    Some((kind, addr, len))
+}
+
+fn watchpoint(kind: u8, addr: u16, len: u16) -> Watchpoint {
+    let end = addr.saturating_add(len.max(1) - 1);
+    Watchpoint { range: addr..=end, read: kind != 2, write: kind != 3 }
+}
+
+fn insert_point(debugger: &mut Debugger, args: &str) -> String {
+    match parse_point(args) {
+        Some((0 | 1, addr, _)) => { debugger.breakpoints.insert(addr); }
+        Some((kind @ 2..=4, addr, len)) => debugger.watchpoints.push(watchpoint(kind, addr, len)),
+        _ => return String::new(),
+    }
+    "OK".to_string()
+}
+
+fn remove_point(debugger: &mut Debugger, args: &str) -> String {
+    match parse_point(args) {
+        Some((0 | 1, addr, _)) => { debugger.breakpoints.remove(&addr); }
+        Some((kind @ 2..=4, addr, len)) => {
+            let target = watchpoint(kind, addr, len);
+            debugger.watchpoints.retain(|w| *w != target);
+        }
+        _ => return String::new(),
+    }
+    "OK".to_string()
+}
+
+/// `qXfer:...:offset,length`: trozo de un documento ('m' = hay más, 'l' = último).
+fn xfer(document: &str, range: &str) -> String {
+    let Some((offset, len)) = range.split_once(',').and_then(|(o, l)| {
+        Some((usize::from_str_radix(o, 16).ok()?, usize::from_str_radix(l, 16).ok()?))
+    }) else {
+        return "E01".to_string();
+    };
+    let start = offset.min(document.len());
+    let end = start.saturating_add(len).min(document.len());
+    let marker = if end < document.len() { 'm' } else { 'l' };
+    format!("{}{}", marker, &document[start..end])
+}
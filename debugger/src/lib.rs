@@ -0,0 +1,274 @@
+// debugger/src/lib.rs
+
+//! Depurador: puntos de parada alrededor de `Cpu::step` y de los accesos al Bus.
+//!
+//! - Breakpoints por PC y por opcode (se comprueban ANTES de ejecutar la instrucción).
+//! - Watchpoints de lectura/escritura sobre rangos de direcciones (se detectan DURANTE
+//!   la instrucción, envolviendo el Bus; la instrucción termina y luego se para).
+//! - Parada al despachar una interrupción (`CpuEvent::Interrupt`).
+//! - Parada pedida desde fuera (Ctrl-C), mirada entre tramos de ejecución.
+//!
+//! El núcleo no hace I/O: el REPL de terminal (módulo `repl`) y el servidor
+//! GDB (módulo `gdb`) son solo interfaces sobre él.
+
+pub mod gdb;
+pub mod repl;
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use cpu::opcodes::{Op, OPCODES};
+use cpu::symbols::SymbolTable;
+use cpu::{Cpu, CpuEvent, Fetch, MemoryInterface};
+use memory::Bus;
+
+/// Cada cuántos M-Cycles mira `run` si le pidieron parar (un frame).
+const CHECK_M_CYCLES: u64 = 17556;
+
+/// Tipo de acceso que vigila un watchpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Vigila un rango de direcciones. 'read'/'write' indican qué accesos lo disparan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    pub read: bool,
+    pub write: bool,
+}
+
+impl Watchpoint {
+    fn matches(&self, addr: u16, access: Access) -> bool {
+        let kind = match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+        };
+        kind && self.range.contains(&addr)
+    }
+}
+
+/// Por qué se detuvo la ejecución.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    /// Se completó el paso pedido (step / step over).
+    Step,
+    /// PC llegó a un breakpoint (la instrucción todavía no se ejecutó).
+    Breakpoint(u16),
+    /// La siguiente instrucción tiene un opcode vigilado.
+    Opcode { pc: u16, opcode: u8 },
+    /// La instrucción anterior accedió a una dirección vigilada.
+    Watchpoint { addr: u16, value: u8, access: Access },
+    /// Se despachó una interrupción; PC está en el vector.
+    Interrupt { vector: u16 },
+    /// Opcode ilegal: la CPU quedó colgada y no hay nada más que ejecutar.
+    Locked { pc: u16, opcode: u8 },
+    /// El usuario pidió parar (Ctrl-C) mientras se ejecutaba.
+    UserBreak,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StopReason::Step => write!(f, "paso completado"),
+            StopReason::Breakpoint(pc) => write!(f, "breakpoint en ${:04X}", pc),
+            StopReason::Opcode { pc, opcode } => write!(f, "opcode ${:02X} en ${:04X}", opcode, pc),
+            StopReason::Watchpoint { addr, value, access } => {
+                let kind = if access == Access::Read { "lectura" } else { "escritura" };
+                write!(f, "watchpoint: {} de ${:02X} en ${:04X}", kind, value, addr)
+            }
+            StopReason::Interrupt { vector } => write!(f, "interrupción despachada a ${:04X}", vector),
+            StopReason::Locked { pc, opcode } => write!(f, "CPU colgada: opcode ilegal ${:02X} en ${:04X}", opcode, pc),
+            StopReason::UserBreak => write!(f, "interrumpido por el usuario"),
+        }
+    }
+}
+
+/// Puntos de parada y estado de la sesión de depuración.
+#[derive(Default)]
+pub struct Debugger {
+    pub breakpoints: BTreeSet<u16>,
+    pub opcode_breaks: BTreeSet<u8>,
+    pub watchpoints: Vec<Watchpoint>,
+    pub break_on_interrupt: bool,
+    /// Símbolos del `.sym` de la ROM, para mostrar `Main.loop+3` y poner breakpoints por nombre.
+    pub symbols: Option<Rc<SymbolTable>>,
+    /// Pedido de parada desde fuera: lo pone a `true` el manejador de Ctrl-C, que corre
+    /// en otro hilo. Por eso es un Arc<AtomicBool> y no un bool (como un atomic.Bool en Go).
+    pub interrupt: Arc<AtomicBool>,
+    // Último opcode ilegal visto: se sigue reportando mientras la CPU esté colgada.
+    locked_at: Option<(u16, u8)>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nombre simbólico de 'addr' con el banco de ROM mapeado ahora mismo.
+    pub fn describe(&self, addr: u16, bus: &Bus) -> Option<String> {
+        self.symbols.as_ref()?.describe(addr, bus.rom_bank(), bus.ram_bank())
+    }
+
+    /// Ejecuta exactamente un `Cpu::step`.
+    /// Retorna los M-Cycles y, si hay que parar, el motivo.
+    pub fn step(&mut self, cpu: &mut Cpu, bus: &mut Bus) -> (u32, Option<StopReason>) {
+        // Sin watchpoints no hace falta envolver el Bus: coste cero.
+        let (cycles, mut stop) = if self.watchpoints.is_empty() {
+            (cpu.step(bus), None)
+        } else {
+            let mut watched = WatchBus { bus, watchpoints: &self.watchpoints, hit: None };
+            let cycles = cpu.step(&mut watched);
+            (cycles, watched.hit)
+        };
+
+        match cpu.take_event() {
+            Some(CpuEvent::IllegalOpcode { pc, opcode }) => {
+                self.locked_at = Some((pc, opcode));
+                stop = Some(StopReason::Locked { pc, opcode });
+            }
+            Some(CpuEvent::Interrupt { vector }) if self.break_on_interrupt => {
+                stop = stop.or(Some(StopReason::Interrupt { vector }));
+            }
+            _ => {}
+        }
+        // Sin ventana nadie consume 'frame_ready': los frames del profiler se cierran aquí.
+        if bus.frame_ready {
+            bus.frame_ready = false;
+            if let Some(profiler) = cpu.profiler.as_mut() {
+                profiler.end_frame();
+            }
+        }
+        (cycles, stop)
+    }
+
+    /// Comprueba los puntos de parada que se evalúan ANTES de ejecutar.
+    fn check_before(&self, cpu: &Cpu, bus: &Bus) -> Option<StopReason> {
+        if cpu.locked {
+            let (pc, opcode) = self.locked_at.unwrap_or((cpu.regs.pc, 0));
+            return Some(StopReason::Locked { pc, opcode });
+        }
+        // Dormida, la CPU no va a leer ningún opcode todavía.
+        if cpu.halted || cpu.stopped {
+            return None;
+        }
+        let pc = cpu.regs.pc;
+        if self.breakpoints.contains(&pc) {
+            return Some(StopReason::Breakpoint(pc));
+        }
+        let opcode = bus.peek(pc);
+        if self.opcode_breaks.contains(&opcode) {
+            return Some(StopReason::Opcode { pc, opcode });
+        }
+        None
+    }
+
+    /// Ejecuta hasta el siguiente punto de parada.
+    /// La primera instrucción no comprueba breakpoints: así se puede continuar desde uno.
+    /// 'max_cycles' limita la ejecución en M-Cycles (None = sin límite); al agotarse retorna None.
+    /// Aun sin límite, cada frame se mira `interrupt`: si está activo, para con `UserBreak`.
+    pub fn run(&mut self, cpu: &mut Cpu, bus: &mut Bus, max_cycles: Option<u64>) -> Option<StopReason> {
+        self.run_until(cpu, bus, max_cycles, None)
+    }
+
+    /// Step over: si la instrucción es CALL o RST, ejecuta la subrutina completa
+    /// y para en la instrucción siguiente. Si no, es un paso normal.
+    /// Si la subrutina no vuelve nunca, solo la para otro punto de parada o `interrupt`.
+    pub fn step_over(&mut self, cpu: &mut Cpu, bus: &mut Bus) -> Option<StopReason> {
+        let pc = cpu.regs.pc;
+        let info = &OPCODES[bus.peek(pc) as usize];
+        match info.op {
+            Op::Call(_) | Op::Rst(_) if !cpu.halted => {
+                // También SP: en una subrutina recursiva, la misma dirección de retorno
+                // aparece antes en niveles más profundos del stack.
+                let ret_addr = pc.wrapping_add(info.len as u16);
+                self.run_until(cpu, bus, None, Some((ret_addr, cpu.regs.sp)))
+            }
+            _ => Some(self.step(cpu, bus).1.unwrap_or(StopReason::Step)),
+        }
+    }
+
+    fn run_until(&mut self, cpu: &mut Cpu, bus: &mut Bus, max_cycles: Option<u64>, target: Option<(u16, u16)>) -> Option<StopReason> {
+        let mut cycles = 0u64;
+        let mut next_check = CHECK_M_CYCLES;
+        let mut first = true;
+        loop {
+            if cycles >= next_check {
+                next_check += CHECK_M_CYCLES;
+                if self.interrupt.swap(false, Ordering::Relaxed) {
+                    return Some(StopReason::UserBreak);
+                }
+            }
+            if !first {
+                if let Some((addr, sp)) = target && cpu.regs.pc == addr && cpu.regs.sp >= sp && !cpu.halted {
+                    return Some(StopReason::Step);
+                }
+                if let Some(reason) = self.check_before(cpu, bus) {
+                    return Some(reason);
+                }
+            }
+            if max_cycles.is_some_and(|max| cycles >= max) {
+                return None;
+            }
+            let (spent, stop) = self.step(cpu, bus);
+            if stop.is_some() {
+                return stop;
+            }
+            cycles += spent as u64;
+            first = false;
+        }
+    }
+}
+
+/// Bus envuelto: delega todo en el Bus real y anota el primer acceso vigilado.
+/// Implementa el mismo trait que el Bus, así la CPU no nota la diferencia
+/// (como envolver un io.Reader en Go).
+struct WatchBus<'a> {
+    bus: &'a mut Bus,
+    watchpoints: &'a [Watchpoint],
+    hit: Option<StopReason>,
+}
+
+impl WatchBus<'_> {
+    fn check(&mut self, addr: u16, value: u8, access: Access) {
+        if self.hit.is_none() && self.watchpoints.iter().any(|w| w.matches(addr, access)) {
+            self.hit = Some(StopReason::Watchpoint { addr, value, access });
+        }
+    }
+}
+
+// Llamamos a los métodos del trait con la sintaxis completa (MemoryInterface::read):
+// Bus también tiene un 'read' propio que NO avanza el reloj.
+impl MemoryInterface for WatchBus<'_> {
+    fn read(&mut self, addr: u16) -> u8 {
+        let value = MemoryInterface::read(self.bus, addr);
+        self.check(addr, value, Access::Read);
+        value
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        MemoryInterface::write(self.bus, addr, val);
+        self.check(addr, val, Access::Write);
+    }
+
+    fn fetch(&mut self, addr: u16, kind: Fetch) -> u8 {
+        let value = MemoryInterface::fetch(self.bus, addr, kind);
+        self.check(addr, value, Access::Read);
+        value
+    }
+
+    fn peek(&self, addr: u16) -> u8 { MemoryInterface::peek(self.bus, addr) }
+    fn tick(&mut self) { MemoryInterface::tick(self.bus) }
+    fn pending_interrupts(&self) -> u8 { self.bus.pending_interrupts() }
+    fn clear_interrupt(&mut self, mask: u8) { self.bus.clear_interrupt(mask) }
+    fn joypad_line_low(&self) -> bool { self.bus.joypad_line_low() }
+    fn stop(&mut self) { MemoryInterface::stop(self.bus) }
+    fn rom_bank(&self) -> u16 { self.bus.rom_bank() }
+    fn ram_bank(&self) -> u16 { self.bus.ram_bank() }
+}
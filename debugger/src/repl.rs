@@ -0,0 +1,311 @@
+// debugger/src/repl.rs
+
+//! Interfaz de línea de comandos del depurador (Read-Eval-Print Loop).
+//!
+//! Es genérica sobre la entrada y la salida (`BufRead`/`Write`, como io.Reader/io.Writer
+//! en Go): en el ejecutable son stdin/stdout, en los tests un texto fijo y un Vec<u8>.
+
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::Ordering;
+
+use cpu::disasm::{decode, Instruction};
+use cpu::profile::Profiler;
+use cpu::trace::format_line;
+use cpu::{Cpu, MemoryInterface};
+use memory::Bus;
+
+use crate::{Debugger, StopReason, Watchpoint};
+
+/// M-Cycles de un frame (70224 T-Cycles / 4).
+const M_CYCLES_PER_FRAME: u64 = 17556;
+
+const HELP: &str = "\
+Comandos (direcciones y opcodes en hexadecimal, con o sin '$'; con .sym, también etiquetas;
+las cantidades n, en decimal):
+  s [n]              ejecutar n instrucciones (1 por defecto)
+  n                  step over: ejecutar CALL/RST completo (Ctrl-C para parar)
+  c                  continuar hasta el siguiente punto de parada (Ctrl-C para parar)
+  f [n]              ejecutar n frames (1 por defecto) salvo que algo pare antes
+  b <addr>           breakpoint en PC
+  bo <opcode>        parar antes de ejecutar ese opcode
+  bi                 parar al despachar interrupciones (activar/desactivar)
+  w  <ini>[-<fin>]   watchpoint de lectura y escritura
+  wr <ini>[-<fin>]   watchpoint de lectura
+  ww <ini>[-<fin>]   watchpoint de escritura
+  d                  borrar todos los puntos de parada
+  i                  listar puntos de parada
+  r                  registros
+  io                 IE/IF, GPU, timer y bancos del cartucho
+  x <addr> [n]       volcar n bytes de memoria (64 por defecto)
+  l [addr] [n]       desensamblar n instrucciones (8 por defecto) desde addr (PC)
+  prof [n]           activar el profiler; ya activo, las n funciones más caras (10) y el último frame
+  q                  salir";
+
+/// Bucle principal: lee comandos hasta `q` o fin de la entrada.
+pub fn run<R: BufRead, W: Write>(debugger: &mut Debugger, cpu: &mut Cpu, bus: &mut Bus, input: R, out: &mut W) -> io::Result<()> {
+    writeln!(out, "Depurador listo. 'h' para ayuda.")?;
+    print_location(debugger, cpu, bus, out)?;
+    prompt(out)?;
+
+    for line in input.lines() {
+        let line = line?;
+        // split_whitespace: como strings.Fields en Go.
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&cmd, args)) = words.split_first() else {
+            prompt(out)?;
+            continue;
+        };
+
+        if cmd == "q" {
+            break;
+        }
+        if let Err(msg) = execute(debugger, cpu, bus, cmd, args, out) {
+            writeln!(out, "Error: {}", msg)?;
+        }
+        prompt(out)?;
+    }
+    Ok(())
+}
+
+fn prompt<W: Write>(out: &mut W) -> io::Result<()> {
+    write!(out, "(gb) ")?;
+    out.flush()
+}
+
+/// Ejecuta un comando. Los errores de uso se devuelven como texto (Err(String)).
+fn execute<W: Write>(debugger: &mut Debugger, cpu: &mut Cpu, bus: &mut Bus, cmd: &str, args: &[&str], out: &mut W) -> Result<(), String> {
+    // Los errores de escritura en 'out' no son de uso: los convertimos a texto igual.
+    let io = |e: io::Error| e.to_string();
+    // Un Ctrl-C pulsado en el prompt no debe cortar el próximo 'c'.
+    debugger.interrupt.store(false, Ordering::Relaxed);
+
+    match cmd {
+        "h" | "help" => writeln!(out, "{}", HELP).map_err(io)?,
+        "s" => {
+            let n = optional(args.first(), 1, parse_dec)?;
+            let mut stop = None;
+            for _ in 0..n {
+                stop = debugger.step(cpu, bus).1;
+                if stop.is_some() { break; }
+            }
+            report(debugger, stop.unwrap_or(StopReason::Step), cpu, bus, out).map_err(io)?;
+        }
+        "n" => {
+            let stop = debugger.step_over(cpu, bus).unwrap_or(StopReason::Step);
+            report(debugger, stop, cpu, bus, out).map_err(io)?;
+        }
+        "c" => {
+            let stop = debugger.run(cpu, bus, None).unwrap_or(StopReason::Step);
+            report(debugger, stop, cpu, bus, out).map_err(io)?;
+        }
+        "f" => {
+            let frames = optional(args.first(), 1, parse_dec)?;
+            let budget = frames.checked_mul(M_CYCLES_PER_FRAME).ok_or("demasiados frames".to_string())?;
+            match debugger.run(cpu, bus, Some(budget)) {
+                Some(stop) => report(debugger, stop, cpu, bus, out).map_err(io)?,
+                None => {
+                    writeln!(out, "{} frame(s) ejecutados", frames).map_err(io)?;
+                    print_location(debugger, cpu, bus, out).map_err(io)?;
+                }
+            }
+        }
+        "b" => {
+            let addr = address(debugger, required(args.first())?)?;
+            debugger.breakpoints.insert(addr);
+        }
+        "bo" => {
+            let opcode = parse_hex(required(args.first())?)?;
+            let opcode = u8::try_from(opcode).map_err(|_| "el opcode es de 8 bits".to_string())?;
+            debugger.opcode_breaks.insert(opcode);
+        }
+        "bi" => {
+            debugger.break_on_interrupt = !debugger.break_on_interrupt;
+            let state = if debugger.break_on_interrupt { "activada" } else { "desactivada" };
+            writeln!(out, "Parada en interrupciones {}", state).map_err(io)?;
+        }
+        "w" | "wr" | "ww" => {
+            let (start, end) = parse_range(required(args.first())?)?;
+            debugger.watchpoints.push(Watchpoint { range: start..=end, read: cmd != "ww", write: cmd != "wr" });
+        }
+        "d" => {
+            debugger.breakpoints.clear();
+            debugger.opcode_breaks.clear();
+            debugger.watchpoints.clear();
+            debugger.break_on_interrupt = false;
+        }
+        "i" => list(debugger, out).map_err(io)?,
+        "r" => print_registers(cpu, bus, out).map_err(io)?,
+        "io" => print_io(cpu, bus, out).map_err(io)?,
+        "x" => {
+            let addr = address(debugger, required(args.first())?)?;
+            let len = optional(args.get(1), 64, parse_dec)?;
+            dump(bus, addr, len, out).map_err(io)?;
+        }
+        "l" => {
+            let addr = match args.first() {
+                Some(text) => address(debugger, text)?,
+                None => cpu.regs.pc,
+            };
+            let count = optional(args.get(1), 8, parse_dec)?;
+            let mut pc = addr;
+            for _ in 0..count {
+                let ins = disassemble_at(debugger, bus, pc);
+                if let Some(label) = &ins.label {
+                    writeln!(out, "{}:", label).map_err(io)?;
+                }
+                let marker = if pc == cpu.regs.pc { "=>" } else { "  " };
+                writeln!(out, "{} {}", marker, ins).map_err(io)?;
+                pc = pc.wrapping_add(ins.len());
+            }
+        }
+        "prof" => match cpu.profiler.as_mut() {
+            Some(profiler) => {
+                let limit = optional(args.first(), 10, parse_dec)? as usize;
+                profiler.write_report(out, limit).map_err(io)?;
+                profiler.write_frames(out, 1, limit).map_err(io)?;
+            }
+            None => {
+                let mut profiler = Profiler::new();
+                profiler.symbols = debugger.symbols.clone();
+                cpu.profiler = Some(profiler);
+                writeln!(out, "profiler activado").map_err(io)?;
+            }
+        },
+        other => return Err(format!("comando desconocido '{}' ('h' para ayuda)", other)),
+    }
+    Ok(())
+}
+
+/// Muestra el motivo de la parada y la siguiente instrucción.
+fn report<W: Write>(debugger: &Debugger, stop: StopReason, cpu: &Cpu, bus: &Bus, out: &mut W) -> io::Result<()> {
+    if stop != StopReason::Step {
+        writeln!(out, "Parada: {}", stop)?;
+    }
+    print_location(debugger, cpu, bus, out)
+}
+
+/// Siguiente instrucción, con su posición simbólica si hay .sym: `=> $4A23: ... (Main.loop+3)`.
+fn print_location<W: Write>(debugger: &Debugger, cpu: &Cpu, bus: &Bus, out: &mut W) -> io::Result<()> {
+    let ins = disassemble_at(debugger, bus, cpu.regs.pc);
+    match debugger.describe(cpu.regs.pc, bus) {
+        Some(name) => writeln!(out, "=> {}    ({})", ins, name),
+        None => writeln!(out, "=> {}", ins),
+    }
+}
+
+/// Desensambla con las etiquetas del banco de ROM mapeado ahora.
+fn disassemble_at(debugger: &Debugger, bus: &Bus, addr: u16) -> Instruction {
+    match &debugger.symbols {
+        Some(symbols) => decode(addr, |a| bus.peek(a), Some(&symbols.resolver(bus.rom_bank(), bus.ram_bank()))),
+        None => decode(addr, |a| bus.peek(a), None),
+    }
+}
+
+fn print_registers<W: Write>(cpu: &Cpu, bus: &Bus, out: &mut W) -> io::Result<()> {
+    // Misma línea que el trace log: fácil de comparar con otros emuladores.
+    writeln!(out, "{}", format_line(&cpu.regs, bus))?;
+    let f = cpu.regs.f;
+    let flag = |mask: u8, name: char| if f & mask != 0 { name } else { '-' };
+    writeln!(
+        out,
+        "Flags: {}{}{}{}  IME={} halted={} stopped={} locked={}",
+        flag(0x80, 'Z'), flag(0x40, 'N'), flag(0x20, 'H'), flag(0x10, 'C'),
+        cpu.ime as u8, cpu.halted, cpu.stopped, cpu.locked
+    )
+}
+
+fn print_io<W: Write>(cpu: &Cpu, bus: &Bus, out: &mut W) -> io::Result<()> {
+    writeln!(out, "IE=${:02X} IF=${:02X} IME={}", bus.interrupt_enable, bus.read(0xFF0F), cpu.ime as u8)?;
+    let gpu = &bus.gpu;
+    writeln!(
+        out,
+        "LCDC=${:02X} STAT=${:02X} (modo {}) LY={} LYC={} SCY={} SCX={} WY={} WX={}",
+        gpu.lcdc, gpu.stat, gpu.stat & 0x03, gpu.ly, gpu.lyc, gpu.scy, gpu.scx, gpu.wy, gpu.wx
+    )?;
+    writeln!(out, "BGP=${:02X} OBP0=${:02X} OBP1=${:02X}", gpu.bgp, gpu.obp0, gpu.obp1)?;
+    writeln!(
+        out,
+        "DIV=${:02X} (interno ${:04X}) TIMA=${:02X} TMA=${:02X} TAC=${:02X}",
+        bus.read(0xFF04), bus.div, bus.tima, bus.tma, bus.tac
+    )?;
+    let cart = &bus.cartridge;
+    writeln!(
+        out,
+        "{}: ROM0=${:02X} ROM=${:02X} RAM=${:X} ({})",
+        cart.name(), cart.rom_bank0(), cart.rom_bank(), cart.ram_bank(),
+        if cart.ram_enabled() { "habilitada" } else { "bloqueada" }
+    )
+}
+
+fn list<W: Write>(debugger: &Debugger, out: &mut W) -> io::Result<()> {
+    for addr in &debugger.breakpoints {
+        writeln!(out, "breakpoint  ${:04X}", addr)?;
+    }
+    for opcode in &debugger.opcode_breaks {
+        writeln!(out, "opcode      ${:02X}", opcode)?;
+    }
+    for w in &debugger.watchpoints {
+        let kind = match (w.read, w.write) { (true, true) => "rw", (true, false) => "r", _ => "w" };
+        writeln!(out, "watchpoint  ${:04X}-${:04X} ({})", w.range.start(), w.range.end(), kind)?;
+    }
+    if debugger.break_on_interrupt {
+        writeln!(out, "interrupciones")?;
+    }
+    Ok(())
+}
+
+/// Volcado hexadecimal de 16 bytes por línea. Usa `Bus::peek` (sin avanzar el reloj ni tocar el mapper).
+fn dump<W: Write>(bus: &Bus, addr: u16, len: u64, out: &mut W) -> io::Result<()> {
+    for row in (0..len).step_by(16) {
+        let base = addr.wrapping_add(row as u16);
+        let bytes: Vec<String> = (0..16.min(len - row))
+            .map(|i| format!("{:02X}", bus.peek(base.wrapping_add(i as u16))))
+            .collect();
+        writeln!(out, "${:04X}: {}", base, bytes.join(" "))?;
+    }
+    Ok(())
+}
+
+// --- PARSEO DE ARGUMENTOS ---
+
+/// Dirección en hex o nombre de un símbolo del .sym (el banco se ignora: los breakpoints son por PC).
+fn address(debugger: &Debugger, text: &str) -> Result<u16, String> {
+    if let Some((_, addr)) = debugger.symbols.as_ref().and_then(|s| s.find(text)) {
+        return Ok(addr);
+    }
+    parse_hex(text)
+}
+
+fn required<'a>(arg: Option<&&'a str>) -> Result<&'a str, String> {
+    arg.copied().ok_or_else(|| "falta un argumento ('h' para ayuda)".to_string())
+}
+
+/// Argumento opcional con valor por defecto.
+fn optional<T>(arg: Option<&&str>, default: T, parse: fn(&str) -> Result<T, String>) -> Result<T, String> {
+    match arg {
+        Some(text) => parse(text),
+        None => Ok(default),
+    }
+}
+
+/// Dirección hexadecimal: `C000`, `$C000` o `0xC000`.
+fn parse_hex(text: &str) -> Result<u16, String> {
+    let digits = text.trim_start_matches('$').trim_start_matches("0x");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("'{}' no es una dirección hexadecimal", text))
+}
+
+fn parse_dec(text: &str) -> Result<u64, String> {
+    text.parse().map_err(|_| format!("'{}' no es un número", text))
+}
+
+/// Rango `ini-fin` o una sola dirección.
+fn parse_range(text: &str) -> Result<(u16, u16), String> {
+    match text.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (parse_hex(start)?, parse_hex(end)?);
+            if start > end { return Err(format!("rango vacío: {}", text)); }
+            Ok((start, end))
+        }
+        None => parse_hex(text).map(|addr| (addr, addr)),
+    }
+}
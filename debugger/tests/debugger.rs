@@ -0,0 +1,134 @@
+// debugger/tests/debugger.rs
+
+//! Puntos de parada, step over y una sesión del REPL con entrada fija.
+
+use std::io::Cursor;
+
+use cpu::Cpu;
+use debugger::{repl, Access, Debugger, StopReason, Watchpoint};
+use mbc::RomOnly;
+use memory::Bus;
+
+/// CPU en 0x0100 y un Bus con una ROM ONLY de 32KB que tiene 'code' en 0x0100.
+fn setup(code: &[u8]) -> (Cpu, Bus) {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0100 + code.len()].copy_from_slice(code);
+    let mut cpu = Cpu::new();
+    cpu.regs.pc = 0x0100;
+    cpu.regs.sp = 0xDFF0;
+    (cpu, Bus::new(Box::new(RomOnly { rom })))
+}
+
+/// ld a, $42 ; ld [$C000], a ; call $0110 ; inc b ; jr -2 ... $0110: inc c ; ret
+const PROGRAM: &[u8] = &[
+    0x3E, 0x42,       // 0100: ld a, $42
+    0xEA, 0x00, 0xC0, // 0102: ld [$C000], a
+    0xCD, 0x10, 0x01, // 0105: call $0110
+    0x04,             // 0108: inc b
+    0x18, 0xFD,       // 0109: jr $0108
+    0, 0, 0, 0, 0,
+    0x0C,             // 0110: inc c
+    0xC9,             // 0111: ret
+];
+
+#[test]
+fn breakpoint_stops_before_the_instruction_and_can_continue() {
+    let (mut cpu, mut bus) = setup(PROGRAM);
+    let mut dbg = Debugger::new();
+    dbg.breakpoints.insert(0x0108);
+
+    assert_eq!(dbg.run(&mut cpu, &mut bus, None), Some(StopReason::Breakpoint(0x0108)));
+    assert_eq!(cpu.regs.pc, 0x0108);
+    // Continuar desde el breakpoint: ejecuta 'inc b', salta y vuelve a parar.
+    let b = cpu.regs.b;
+    assert_eq!(dbg.run(&mut cpu, &mut bus, None), Some(StopReason::Breakpoint(0x0108)));
+    assert_eq!(cpu.regs.b, b.wrapping_add(1));
+}
+
+#[test]
+fn write_watchpoint_reports_address_and_value() {
+    let (mut cpu, mut bus) = setup(PROGRAM);
+    let mut dbg = Debugger::new();
+    dbg.watchpoints.push(Watchpoint { range: 0xC000..=0xC0FF, read: false, write: true });
+
+    let stop = dbg.run(&mut cpu, &mut bus, None);
+    assert_eq!(stop, Some(StopReason::Watchpoint { addr: 0xC000, value: 0x42, access: Access::Write }));
+    assert_eq!(cpu.regs.pc, 0x0105); // La instrucción que escribió ya terminó
+}
+
+#[test]
+fn opcode_break_and_budget() {
+    let (mut cpu, mut bus) = setup(PROGRAM);
+    let mut dbg = Debugger::new();
+    dbg.opcode_breaks.insert(0xC9); // ret
+    assert_eq!(dbg.run(&mut cpu, &mut bus, None), Some(StopReason::Opcode { pc: 0x0111, opcode: 0xC9 }));
+
+    // Sin puntos de parada, el presupuesto de ciclos termina la ejecución.
+    dbg.opcode_breaks.clear();
+    assert_eq!(dbg.run(&mut cpu, &mut bus, Some(1000)), None);
+}
+
+#[test]
+fn step_over_runs_the_whole_call() {
+    let (mut cpu, mut bus) = setup(PROGRAM);
+    let mut dbg = Debugger::new();
+    dbg.step(&mut cpu, &mut bus);
+    dbg.step(&mut cpu, &mut bus);
+    assert_eq!(cpu.regs.pc, 0x0105);
+
+    let c = cpu.regs.c;
+    assert_eq!(dbg.step_over(&mut cpu, &mut bus), Some(StopReason::Step));
+    assert_eq!(cpu.regs.pc, 0x0108);
+    assert_eq!(cpu.regs.c, c.wrapping_add(1));
+}
+
+#[test]
+fn interrupt_dispatch_stops_at_the_vector() {
+    let (mut cpu, mut bus) = setup(&[0xFB, 0x00, 0x00, 0x00]); // ei ; nop...
+    bus.interrupt_enable = 0x04;
+    bus.interrupt_flag = 0x04; // Timer pendiente
+    let mut dbg = Debugger::new();
+    dbg.break_on_interrupt = true;
+
+    assert_eq!(dbg.run(&mut cpu, &mut bus, Some(100)), Some(StopReason::Interrupt { vector: 0x0050 }));
+    assert_eq!(cpu.regs.pc, 0x0050);
+}
+
+#[test]
+fn repl_session() {
+    let (mut cpu, mut bus) = setup(PROGRAM);
+    let mut dbg = Debugger::new();
+    let script = "b 108\nc\nr\nx $C000 17\nn\ni\nfoo\nf 99999999999999999\nq\ns\n";
+    let mut out = Vec::new();
+    repl::run(&mut dbg, &mut cpu, &mut bus, Cursor::new(script), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    assert!(text.contains("Parada: breakpoint en $0108"), "{}", text);
+    assert!(text.contains("A:42 "), "{}", text);
+    assert!(text.contains("$C000: 42 00 00 00"), "{}", text);
+    // La cantidad es decimal: 17 bytes, el último solo en la segunda fila.
+    assert!(text.contains("$C010: 00\n"), "{}", text);
+    assert!(text.contains("breakpoint  $0108"), "{}", text);
+    assert!(text.contains("comando desconocido 'foo'"), "{}", text);
+    assert!(text.contains("demasiados frames"), "{}", text);
+    // 'n' ejecutó 'inc b'; el 's' después de 'q' no se ejecuta.
+    assert_eq!(cpu.regs.pc, 0x0109);
+}
+
+#[test]
+fn interrupt_flag_stops_endless_run_and_step_over() {
+    use std::sync::atomic::Ordering;
+
+    // Sin puntos de parada, 'jr -2' no termina nunca: solo lo para el pedido de parada.
+    let (mut cpu, mut bus) = setup(&[0x18, 0xFE]);
+    let mut dbg = Debugger::new();
+    dbg.interrupt.store(true, Ordering::Relaxed);
+    assert_eq!(dbg.run(&mut cpu, &mut bus, None), Some(StopReason::UserBreak));
+    assert!(!dbg.interrupt.load(Ordering::Relaxed));
+
+    // Un CALL a una subrutina que no vuelve.
+    let (mut cpu, mut bus) = setup(&[0xCD, 0x10, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x18, 0xFE]);
+    dbg.interrupt.store(true, Ordering::Relaxed);
+    assert_eq!(dbg.step_over(&mut cpu, &mut bus), Some(StopReason::UserBreak));
+    assert_eq!(cpu.regs.pc, 0x0110);
+}
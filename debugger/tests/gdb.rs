@@ -0,0 +1,176 @@
+// debugger/tests/gdb.rs
+
+//! Sesión GDB RSP real sobre TCP local: el cliente corre en otro hilo.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use cpu::Cpu;
+use debugger::gdb::Session;
+use debugger::Debugger;
+use mbc::RomOnly;
+use memory::Bus;
+
+/// Envía un paquete y espera su '+'.
+fn send_packet(stream: &mut TcpStream, data: &str) {
+    let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+    write!(stream, "${}#{:02x}", data, checksum).unwrap();
+
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte).unwrap();
+    assert_eq!(byte[0], b'+', "el stub no confirmó '{}'", data);
+}
+
+/// Lee una respuesta ($...#xx) y la confirma.
+fn read_reply(stream: &mut TcpStream) -> String {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte).unwrap();
+    assert_eq!(byte[0], b'$');
+    let mut reply = Vec::new();
+    loop {
+        stream.read_exact(&mut byte).unwrap();
+        if byte[0] == b'#' { break; }
+        reply.push(byte[0]);
+    }
+    let mut checksum = [0u8; 2];
+    stream.read_exact(&mut checksum).unwrap();
+    stream.write_all(b"+").unwrap();
+    String::from_utf8(reply).unwrap()
+}
+
+/// Cliente mínimo: envía un paquete y devuelve la respuesta (con acks).
+fn request(stream: &mut TcpStream, data: &str) -> String {
+    send_packet(stream, data);
+    read_reply(stream)
+}
+
+#[test]
+fn breakpoints_watchpoints_registers_and_memory() {
+    // ld hl, $C000 ; .loop: ld [hl], a ; inc a ; jr .loop
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0107].copy_from_slice(&[0x21, 0x00, 0xC0, 0x77, 0x3C, 0x18, 0xFC]);
+    let mut bus = Bus::new(Box::new(RomOnly { rom }));
+    let mut cpu = Cpu::new();
+    cpu.regs.pc = 0x0100;
+    cpu.regs.a = 0x10;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // El Bus no es Send (Box<dyn Mbc>): el servidor se queda en este hilo y el cliente se va a otro.
+    let client = thread::spawn(move || {
+        let mut s = TcpStream::connect(addr).unwrap();
+        s.set_nodelay(true).unwrap();
+        let mut replies = Vec::new();
+        for packet in [
+            "qSupported:swbreak+",
+            "m100,4",
+            "Z0,103,1",
+            "c",
+            "p5",
+            "s",
+            "p5",
+            "z0,103,1",
+            "Z2,c000,1",
+            "c",
+            "mc000,1",
+            "Mc001,2:abcd",
+            "mc001,2",
+            "P0=ff12",
+            "g",
+            "D",
+        ] {
+            replies.push(request(&mut s, packet));
+        }
+        replies
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    Session::new(stream).run(&mut Debugger::new(), &mut cpu, &mut bus).unwrap();
+    let replies = client.join().unwrap();
+
+    assert!(replies[0].contains("swbreak+"));
+    assert_eq!(replies[1], "2100c077");
+    assert_eq!(replies[2], "OK");
+    assert_eq!(replies[3], "T05swbreak:;");
+    assert_eq!(replies[4], "0301"); // PC = 0x0103, little-endian
+    assert_eq!(replies[5], "S05");
+    assert_eq!(replies[6], "0401");
+    assert_eq!(replies[9], "T05watch:c000;");
+    assert_eq!(replies[10], "11"); // A ya se incrementó una vez antes de la escritura
+    assert_eq!(replies[11], "OK");
+    assert_eq!(replies[12], "abcd");
+    assert_eq!(replies[13], "OK");
+    // AF = 0x12FF: el nibble bajo de F siempre es 0, queda 0x12F0.
+    assert!(replies[14].starts_with("f012"), "{}", replies[14]);
+    assert_eq!(replies[15], "OK");
+}
+
+#[test]
+fn rejects_bad_checksums_and_oversized_reads() {
+    let mut bus = Bus::new(Box::new(RomOnly { rom: vec![0; 0x8000] }));
+    let mut cpu = Cpu::new();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let mut s = TcpStream::connect(addr).unwrap();
+        s.set_nodelay(true).unwrap();
+        // Muchos paquetes corruptos seguidos: cada uno recibe '-' y el stub sigue esperando.
+        let mut byte = [0u8; 1];
+        for _ in 0..10_000 {
+            s.write_all(b"$m100,1#00").unwrap();
+            s.read_exact(&mut byte).unwrap();
+            assert_eq!(byte[0], b'-');
+        }
+        let replies = vec![
+            request(&mut s, "m0,2000"), // 0x2000 bytes: justo PacketSize en hex
+            request(&mut s, "m0,2001"),
+            request(&mut s, "m0,ffffffff"),
+        ];
+        request(&mut s, "D");
+        replies
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    Session::new(stream).run(&mut Debugger::new(), &mut cpu, &mut bus).unwrap();
+    let replies = client.join().unwrap();
+
+    assert_eq!(replies[0].len(), 0x4000);
+    assert_eq!(replies[1], "E01");
+    assert_eq!(replies[2], "E01");
+}
+
+#[test]
+fn keeps_bytes_received_while_running_and_clamps_xfer() {
+    // ROM de NOPs: 'c' no para nunca por sí solo.
+    let mut bus = Bus::new(Box::new(RomOnly { rom: vec![0; 0x8000] }));
+    let mut cpu = Cpu::new();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let mut s = TcpStream::connect(addr).unwrap();
+        s.set_nodelay(true).unwrap();
+        send_packet(&mut s, "c");
+        // Un paquete completo llega antes que el Ctrl-C: no se debe perder.
+        s.write_all(b"$?#3f\x03").unwrap();
+        let mut replies = vec![read_reply(&mut s)];
+        let mut byte = [0u8; 1];
+        s.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], b'+');
+        replies.push(read_reply(&mut s));
+        replies.push(request(&mut s, "qXfer:features:read:target.xml:10,ffffffffffffffff"));
+        request(&mut s, "D");
+        replies
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    Session::new(stream).run(&mut Debugger::new(), &mut cpu, &mut bus).unwrap();
+    let replies = client.join().unwrap();
+
+    assert_eq!(replies[0], "S02");
+    assert_eq!(replies[1], "S05");
+    assert!(replies[2].starts_with('l') && replies[2].ends_with("</target>"), "{}", replies[2]);
+}
@@ -3,21 +3,52 @@
 // Importamos crates externos para ventana y gráficos.
 // - winit: Manejo de ventanas multiplataforma.
 // - pixels: Renderizado de buffers de píxeles eficiente (hardware accelerated).
-use winit::event::{Event, VirtualKeyCode, WindowEvent};
+use winit::event::{Event, VirtualKeyCode};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 use pixels::{Pixels, SurfaceTexture};
 use winit_input_helper::WinitInputHelper;
 
-use cpu::Cpu;
+use std::fs;
+use std::path::PathBuf;
+
+use cpu::{Cpu, CpuEvent};
 use memory::Bus;
+use memory::movie::{Mode, Movie};
+use memory::rewind::Rewind;
 use joypad::Button;
 use gpu::{SCREEN_WIDTH, SCREEN_HEIGHT};
 
+/// Opciones del frontend. `Default` deja todo desactivado.
+#[derive(Default)]
+pub struct Options {
+    /// Archivo del save state: F5 guarda, F9 carga.
+    pub state_path: Option<PathBuf>,
+    /// Historia para el rewind: mantener R reproduce la partida hacia atrás.
+    pub rewind: Option<Rewind>,
+    /// Película de input que se graba o reproduce. Con película, F5/F9 son checkpoints
+    /// de rerecording y el teclado solo cuenta mientras se graba.
+    pub movie: Option<Movie>,
+    /// Dónde se guarda la película grabada al salir.
+    pub movie_path: Option<PathBuf>,
+}
+
+/// Teclado -> botones de la Game Boy.
+const KEYS: [(VirtualKeyCode, Button); 8] = [
+    (VirtualKeyCode::Z, Button::A),
+    (VirtualKeyCode::X, Button::B),
+    (VirtualKeyCode::Return, Button::Start),
+    (VirtualKeyCode::Back, Button::Select), // Backspace
+    (VirtualKeyCode::Up, Button::Up),
+    (VirtualKeyCode::Down, Button::Down),
+    (VirtualKeyCode::Left, Button::Left),
+    (VirtualKeyCode::Right, Button::Right),
+];
+
 /// Función principal que toma el control del emulador.
 /// Recibe la CPU y el Bus con propiedad (ownership), consumiéndolos.
 /// Esto garantiza que nadie más pueda modificarlos fuera del bucle.
-pub fn run(mut cpu: Cpu, mut bus: Bus) {
+pub fn run(mut cpu: Cpu, mut bus: Bus, mut options: Options) {
     // 1. Configurar la ventana (Window)
     // EventLoop maneja los mensajes del SO (clics, teclas, redibujado).
     let event_loop = EventLoop::new();
@@ -42,6 +73,9 @@ pub fn run(mut cpu: Cpu, mut bus: Bus) {
     // Helper para simplificar el manejo de input (teclado).
     let mut input = WinitInputHelper::new();
 
+    // El desync de la película se avisa una sola vez.
+    let mut desync_reported = false;
+
     // 3. El Bucle Principal (Game Loop)
     // event_loop.run toma el control del hilo principal (necesario en macOS).
     // El closure 'move |...|' captura variables del entorno (cpu, bus, pixels) moviéndolas dentro.
@@ -54,12 +88,84 @@ pub fn run(mut cpu: Cpu, mut bus: Bus) {
         if input.update(&event) {
             // Tecla Escape o botón Cerrar -> Salir
             if input.key_pressed(VirtualKeyCode::Escape) || input.close_requested() {
+                // El proceso termina sin ejecutar Drop: vaciamos el trace a mano.
+                if let Some(tracer) = cpu.tracer.as_mut() {
+                    let _ = tracer.flush();
+                }
+                // Igual con el profiler: folded-stack al archivo y resumen por consola.
+                if let Some(profiler) = cpu.profiler.as_mut() {
+                    let _ = profiler.save();
+                    let _ = profiler.write_report(&mut std::io::stdout(), 20);
+                    let _ = profiler.write_frames(&mut std::io::stdout(), 1, 10);
+                }
+                if let Some(coverage) = bus.coverage.as_ref() {
+                    if let Err(e) = coverage.save() {
+                        eprintln!("No se pudo guardar la cobertura: {}", e);
+                    }
+                    print!("{}", coverage);
+                }
+                // La película grabada solo existe en memoria hasta aquí.
+                if let (Some(movie), Some(path)) = (options.movie.as_ref(), options.movie_path.as_ref()) {
+                    if movie.mode == Mode::Recording {
+                        match fs::write(path, movie.save()) {
+                            Ok(()) => println!("Película guardada en {} ({} frames, {} rerecords)", path.display(), movie.len(), movie.rerecords),
+                            Err(e) => eprintln!("No se pudo guardar la película: {}", e),
+                        }
+                    }
+                }
                 *control_flow = ControlFlow::Exit;
                 return;
             }
 
-            // Manejo del Joypad (Mapeo de teclas PC -> Game Boy)
-            handle_input(&input, &mut bus);
+            // Manejo del Joypad (Mapeo de teclas PC -> Game Boy).
+            // Con película, el input pasa por ella para caer justo en un límite de frame;
+            // al reproducir, el teclado se ignora.
+            match options.movie.as_mut() {
+                Some(movie) if movie.mode == Mode::Recording => record_input(&input, movie),
+                Some(movie) if movie.mode == Mode::Playback => {}
+                _ => handle_input(&input, &mut bus),
+            }
+
+            // Con película, F5/F9 son checkpoints: cargar uno vuelve a grabar desde ahí.
+            if let (Some(movie), Some(path)) = (options.movie.as_mut(), options.state_path.as_ref()) {
+                if input.key_pressed(VirtualKeyCode::F5) {
+                    let msg = match fs::write(path, movie.checkpoint(&cpu, &bus)) {
+                        Ok(()) => format!("Checkpoint del frame {} guardado", movie.frame()),
+                        Err(e) => format!("No se pudo guardar el checkpoint: {}", e),
+                    };
+                    window.set_title(&format!("Rust GameBoy Emulator - {}", msg));
+                }
+                if input.key_pressed(VirtualKeyCode::F9) {
+                    let result = fs::read(path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|data| movie.rerecord(&mut cpu, &mut bus, &data).map_err(|e| e.to_string()));
+                    let msg = match result {
+                        Ok(()) => format!("Grabando desde el frame {} ({} rerecords)", movie.frame(), movie.rerecords),
+                        Err(e) => format!("No se pudo cargar el checkpoint: {}", e),
+                    };
+                    desync_reported = false;
+                    window.set_title(&format!("Rust GameBoy Emulator - {}", msg));
+                }
+            } else if let Some(path) = &options.state_path {
+                // Save states: F5 guarda, F9 carga. El resultado se muestra en el título.
+                if input.key_pressed(VirtualKeyCode::F5) {
+                    let msg = match fs::write(path, memory::state::save(&cpu, &bus)) {
+                        Ok(()) => format!("Estado guardado en {}", path.display()),
+                        Err(e) => format!("No se pudo guardar el estado: {}", e),
+                    };
+                    window.set_title(&format!("Rust GameBoy Emulator - {}", msg));
+                }
+                if input.key_pressed(VirtualKeyCode::F9) {
+                    let result = fs::read(path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|data| memory::state::load(&mut cpu, &mut bus, &data).map_err(|e| e.to_string()));
+                    let msg = match result {
+                        Ok(_) => format!("Estado cargado de {}", path.display()),
+                        Err(e) => format!("No se pudo cargar el estado: {}", e),
+                    };
+                    window.set_title(&format!("Rust GameBoy Emulator - {}", msg));
+                }
+            }
 
             // Redimensionar buffer si la ventana cambia de tamaño
             if let Some(size) = input.window_resized() {
@@ -69,6 +175,20 @@ pub fn run(mut cpu: Cpu, mut bus: Bus) {
 
         // 4. Renderizado: Winit emite RedrawRequested cuando toca dibujar.
         if let Event::RedrawRequested(_) = event {
+            // Rewind: con R mantenida, en vez de emular un frame se restaura el snapshot anterior.
+            if let Some(rewind) = options.rewind.as_mut() {
+                if input.key_held(VirtualKeyCode::R) {
+                    if rewind.rewind(&mut cpu, &mut bus) {
+                        pixels.frame_mut().copy_from_slice(&bus.gpu.frame_buffer);
+                    }
+                    if pixels.render().is_err() {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    window.request_redraw();
+                    return;
+                }
+            }
+
             // A. Ejecutar un frame completo de la CPU
             // La Game Boy corre a ~59.7 Hz (aprox 60).
             // Un frame son exactamente 70224 ciclos de reloj (T-Cycles) o 17556 M-Cycles.
@@ -82,35 +202,55 @@ pub fn run(mut cpu: Cpu, mut bus: Bus) {
             
             while cycles_spent < CYCLES_PER_FRAME {
                 // cpu.step devuelve M-Cycles (ej: 1 para NOP).
+                // Durante la instrucción, la CPU ya avanzó GPU y Timer en cada acceso al bus.
                 let m_cycles = cpu.step(&mut bus);
                 
                 // Convertimos a T-Cycles (Reloj del sistema) para precisión.
-                let t_cycles = m_cycles * 4;
-                cycles_spent += t_cycles;
-                
-                // Actualizar GPU (PPU)
-                // Devuelve true si acaba de entrar en V-Blank (frame listo).
-                let frame_ready = bus.gpu.step(t_cycles);
-                
-                // Actualizar Timer (DIV, TIMA)
-                bus.step_timer(m_cycles); // El timer suele contar M-Cycles internamente
+                cycles_spent += m_cycles * 4;
 
-                // Interrupción LCD STAT
-                if bus.gpu.request_stat_interrupt {
-                    bus.interrupt_flag |= 0x02; // Bit 1
+                // La película cuenta sus propios ciclos: los límites de frame no dependen
+                // de cuándo redibuja la ventana.
+                if let Some(movie) = options.movie.as_mut() {
+                    movie.step(m_cycles, &cpu, &mut bus);
+                    if let (Some(frame), false) = (movie.desync, desync_reported) {
+                        desync_reported = true;
+                        let msg = format!("Desync de la película en el frame {}", frame);
+                        eprintln!("{}", msg);
+                        window.set_title(&format!("Rust GameBoy Emulator - {}", msg));
+                    }
+                }
+
+                // Opcode ilegal: la CPU quedó colgada como en el hardware real
+                // (la pantalla sigue viva). Lo avisamos en consola y en el título.
+                if let Some(CpuEvent::IllegalOpcode { pc, opcode }) = cpu.take_event() {
+                    let msg = format!("CPU colgada: opcode ilegal {:#04X} en {:#06X}", opcode, pc);
+                    eprintln!("{}", msg);
+                    // Si hay trace, las últimas instrucciones suelen explicar cómo llegamos aquí.
+                    if let Some(tracer) = cpu.tracer.as_mut() {
+                        let _ = tracer.flush();
+                        eprintln!("Últimas instrucciones:");
+                        let _ = tracer.dump_recent(&mut std::io::stderr());
+                    }
+                    window.set_title(&format!("Rust GameBoy Emulator - {}", msg));
                 }
                 
-                // Si la GPU terminó de dibujar la pantalla
-                if frame_ready {
+                // Si la GPU entró en V-Blank durante la instrucción (frame listo).
+                if bus.frame_ready {
+                    bus.frame_ready = false;
+                    if let Some(profiler) = cpu.profiler.as_mut() {
+                        profiler.end_frame();
+                    }
                     // Copiar el buffer linear de la GPU al Texture de la ventana
                     let frame = pixels.frame_mut();
                     frame.copy_from_slice(&bus.gpu.frame_buffer);
-                    
-                    // Solicitar interrupción VBlank a la CPU (Bit 0)
-                    bus.interrupt_flag |= 0x01; 
                 }
             }
 
+            // Historia para el rewind (cada 'interval' frames toma un snapshot).
+            if let Some(rewind) = options.rewind.as_mut() {
+                rewind.end_frame(&cpu, &bus);
+            }
+
             // B. Dibujar en pantalla (Swap buffers)
             if pixels.render().is_err() {
                 *control_flow = ControlFlow::Exit;
@@ -126,23 +266,17 @@ pub fn run(mut cpu: Cpu, mut bus: Bus) {
 /// Helper para mapear teclado moderno a botones de GB.
 /// Recibe referencia mutable al Bus porque necesita modificar 'joypad'.
 fn handle_input(input: &WinitInputHelper, bus: &mut Bus) {
-    // Teclas presionadas
-    if input.key_pressed(VirtualKeyCode::Z) { bus.joypad.key_down(Button::A); }
-    if input.key_pressed(VirtualKeyCode::X) { bus.joypad.key_down(Button::B); }
-    if input.key_pressed(VirtualKeyCode::Return) { bus.joypad.key_down(Button::Start); }
-    if input.key_pressed(VirtualKeyCode::Back) { bus.joypad.key_down(Button::Select); } // Backspace
-    if input.key_pressed(VirtualKeyCode::Up) { bus.joypad.key_down(Button::Up); }
-    if input.key_pressed(VirtualKeyCode::Down) { bus.joypad.key_down(Button::Down); }
-    if input.key_pressed(VirtualKeyCode::Left) { bus.joypad.key_down(Button::Left); }
-    if input.key_pressed(VirtualKeyCode::Right) { bus.joypad.key_down(Button::Right); }
-
-    // Teclas soltadas
-    if input.key_released(VirtualKeyCode::Z) { bus.joypad.key_up(Button::A); }
-    if input.key_released(VirtualKeyCode::X) { bus.joypad.key_up(Button::B); }
-    if input.key_released(VirtualKeyCode::Return) { bus.joypad.key_up(Button::Start); }
-    if input.key_released(VirtualKeyCode::Back) { bus.joypad.key_up(Button::Select); }
-    if input.key_released(VirtualKeyCode::Up) { bus.joypad.key_up(Button::Up); }
-    if input.key_released(VirtualKeyCode::Down) { bus.joypad.key_up(Button::Down); }
-    if input.key_released(VirtualKeyCode::Left) { bus.joypad.key_up(Button::Left); }
-    if input.key_released(VirtualKeyCode::Right) { bus.joypad.key_up(Button::Right); }
-}
\ No newline at end of file
+    for (key, button) in KEYS {
+        // Teclas presionadas / soltadas
+        if input.key_pressed(key) { bus.joypad.key_down(button); }
+        if input.key_released(key) { bus.joypad.key_up(button); }
+    }
+}
+
+/// Igual que `handle_input`, pero el input se graba en la película.
+fn record_input(input: &WinitInputHelper, movie: &mut Movie) {
+    for (key, button) in KEYS {
+        if input.key_pressed(key) { movie.set_button(button, true); }
+        if input.key_released(key) { movie.set_button(button, false); }
+    }
+}
@@ -0,0 +1,308 @@
+// gpu/src/fifo.rs
+
+//! Renderer de pixel FIFO: el modo 3 tal como lo hace el PPU, un píxel por dot.
+//!
+//! Dos piezas trabajan a la vez:
+//! - El *fetcher* busca tiles de fondo (o de ventana) en 3 pasos de 2 dots cada uno:
+//!   número de tile, byte bajo, byte alto. Con el tile listo, lo empuja a la FIFO de
+//!   fondo en cuanto está vacía (8 píxeles de golpe).
+//! - La FIFO saca un píxel por dot hacia el LCD, mezclándolo con la FIFO de sprites.
+//!
+//! Todo lo que alarga el modo 3 sale de ahí: los `SCX % 8` píxeles que se descartan al
+//! principio, el reinicio del fetcher cuando empieza la ventana (6 dots) y las pausas
+//! para buscar sprites (6 a 11 dots). Como los registros se leen en el dot en que se
+//! usan, un cambio de SCX, BGP o LCDC a mitad de línea se ve a mitad de línea.
+
+use savestate::{Reader, Snapshot, StateError, Writer};
+
+use crate::{Gpu, BLANK, SCREEN_WIDTH};
+
+/// Dots de arranque antes del primer paso del fetcher. El hardware tira la primera
+/// búsqueda de tile: con los 6 dots del primer tile real, el modo 3 dura como mínimo
+/// 160 + 12 = 172 dots.
+const STARTUP_DOTS: u8 = 7;
+
+/// Dots que tarda el fetcher en tener un tile listo.
+const FETCH_DOTS: u8 = 6;
+
+/// Sin tile que ya haya pagado la espera extra de sprite.
+const NO_TILE: u16 = 0xFFFF;
+
+/// Estado del pipeline durante el modo 3. Se reinicia al empezar cada línea.
+#[derive(Clone)]
+pub(crate) struct Fifo {
+    /// Píxeles ya enviados al LCD en esta línea (0..=160).
+    lx: u8,
+    /// Píxeles que faltan por descartar al principio de la línea (SCX % 8).
+    discard: u8,
+    /// Dots de espera: arranque de la línea o penalización por sprite.
+    stall: u8,
+
+    // Fetcher: dots desde que empezó el tile actual (0..=6), columna de tile
+    // y lo leído hasta ahora.
+    step: u8,
+    fetch_x: u8,
+    tile: u8,
+    lo: u8,
+    hi: u8,
+    /// El fetcher está leyendo la ventana en vez del fondo.
+    window: bool,
+
+    /// FIFO de fondo: índices de color 0-3. El frente es `bg[8 - bg_len]`.
+    bg: [u8; 8],
+    bg_len: u8,
+    /// FIFO de sprites, siempre 8 píxeles alineados con los de fondo.
+    /// Bits 0-1: color (0 = transparente), bit 4: paleta OBP1, bit 7: detrás del fondo.
+    obj: [u8; 8],
+
+    /// Sprites de la línea (índices de OAM) que aún no se buscaron.
+    sprites: Vec<u8>,
+    /// Último tile de fondo/ventana que ya pagó la espera extra por sprite.
+    penalty_tile: u16,
+}
+
+impl Default for Fifo {
+    fn default() -> Self {
+        Fifo {
+            lx: 0, discard: 0, stall: 0,
+            step: 0, fetch_x: 0, tile: 0, lo: 0, hi: 0, window: false,
+            bg: [0; 8], bg_len: 0, obj: [0; 8],
+            sprites: Vec::new(),
+            penalty_tile: NO_TILE,
+        }
+    }
+}
+
+impl Gpu {
+    /// Comienzo del modo 3: pipeline vacío.
+    pub(crate) fn start_pixel_transfer(&mut self) {
+        self.fifo = Fifo {
+            discard: self.scx & 7,
+            stall: STARTUP_DOTS,
+            sprites: self.oam_scan(),
+            ..Fifo::default()
+        };
+    }
+
+    /// Un dot del modo 3. Devuelve true cuando ya salieron los 160 píxeles de la línea.
+    pub(crate) fn pixel_transfer_dot(&mut self) -> bool {
+        if self.fifo.stall > 0 {
+            self.fifo.stall -= 1;
+            return false;
+        }
+
+        self.fetcher_dot();
+
+        // Sin píxeles de fondo no sale nada (ni se pueden mezclar sprites).
+        if self.fifo.bg_len == 0 {
+            return false;
+        }
+        if self.fifo.discard == 0 && (self.window_trigger() || self.sprite_fetch()) {
+            return false;
+        }
+        self.shift_pixel()
+    }
+
+    /// Un dot del fetcher de fondo/ventana.
+    fn fetcher_dot(&mut self) {
+        if self.fifo.step < FETCH_DOTS {
+            self.fifo.step += 1;
+            // Cada lectura ocupa el segundo dot de su paso.
+            match self.fifo.step {
+                2 => self.fifo.tile = self.vram[self.fetch_map_addr() as usize],
+                4 => self.fifo.lo = self.vram[self.fetch_data_addr() as usize],
+                6 => self.fifo.hi = self.vram[self.fetch_data_addr() as usize + 1],
+                _ => {}
+            }
+        }
+        // En DMG el tile solo entra con la FIFO vacía; si no, se reintenta cada dot.
+        if self.fifo.step == FETCH_DOTS && self.fifo.bg_len == 0 {
+            for (i, pixel) in self.fifo.bg.iter_mut().enumerate() {
+                let bit = 7 - i;
+                *pixel = ((self.fifo.hi >> bit) & 1) << 1 | ((self.fifo.lo >> bit) & 1);
+            }
+            self.fifo.bg_len = 8;
+            self.fifo.step = 0;
+            self.fifo.fetch_x = self.fifo.fetch_x.wrapping_add(1);
+        }
+    }
+
+    /// Dirección en VRAM del número de tile que toca buscar.
+    fn fetch_map_addr(&self) -> u16 {
+        if self.fifo.window {
+            let map_base: u16 = if (self.lcdc & 0x40) != 0 { 0x1C00 } else { 0x1800 };
+            let row = self.window_line / 8;
+            map_base + row as u16 * 32 + (self.fifo.fetch_x & 31) as u16
+        } else {
+            let map_base: u16 = if (self.lcdc & 0x08) != 0 { 0x1C00 } else { 0x1800 };
+            let row = self.ly.wrapping_add(self.scy) / 8;
+            // SCX grueso se lee en cada fetch: por eso un cambio a mitad de línea se nota.
+            let col = (self.scx / 8).wrapping_add(self.fifo.fetch_x) & 31;
+            map_base + row as u16 * 32 + col as u16
+        }
+    }
+
+    /// Dirección en VRAM del byte bajo de la fila del tile buscado.
+    fn fetch_data_addr(&self) -> u16 {
+        let line = if self.fifo.window { self.window_line } else { self.ly.wrapping_add(self.scy) };
+        self.get_tile_data_addr(self.fifo.tile, (self.lcdc & 0x10) != 0) + (line % 8) as u16 * 2
+    }
+
+    /// ¿Empieza la ventana en este píxel? Si es así, el fetcher vuelve a empezar
+    /// desde la columna 0 de la ventana y se tira lo que quedaba en la FIFO.
+    fn window_trigger(&mut self) -> bool {
+        if self.fifo.window {
+            return false;
+        }
+        let Some((start, skipped)) = self.window_start() else { return false };
+        if self.fifo.lx != start {
+            return false;
+        }
+        self.fifo.window = true;
+        // WX 0-6: los píxeles de la ventana a la izquierda del borde se descartan.
+        self.fifo.discard = skipped;
+        self.fifo.bg_len = 0;
+        self.fifo.step = 0;
+        self.fifo.fetch_x = 0;
+        true
+    }
+
+    /// ¿Hay que buscar un sprite que empieza en este píxel? La FIFO se para mientras
+    /// tanto: 6 dots, más la espera a que el fetcher de fondo termine su tile
+    /// (solo el primer sprite de cada tile la paga).
+    fn sprite_fetch(&mut self) -> bool {
+        if (self.lcdc & 0x02) == 0 {
+            return false;
+        }
+        // Entre los que ya empezaron (los cortados por el borde izquierdo empiezan todos
+        // en el píxel 0), primero el de X menor y a igual X el de índice menor:
+        // así el primero en ocupar la FIFO es el de más prioridad en DMG.
+        let lx = self.fifo.lx;
+        let oam = &self.oam;
+        let ready = self.fifo.sprites.iter().enumerate().filter(|&(_, &i)| oam[i as usize * 4 + 1] <= lx + 8);
+        let Some((pos, _)) = ready.min_by_key(|&(_, &i)| oam[i as usize * 4 + 1]) else {
+            return false;
+        };
+        let index = self.fifo.sprites.remove(pos) as usize * 4;
+        let x = self.oam[index + 1];
+
+        // Tile de fondo o de ventana donde cae el píxel izquierdo del sprite.
+        let pixel = match self.fifo.window {
+            true => x.wrapping_sub(1).wrapping_sub(self.wx),
+            false => x.wrapping_sub(8).wrapping_add(self.scx),
+        };
+        let tile = (self.fifo.window as u16) << 8 | (pixel / 8) as u16;
+        let mut penalty = 6;
+        if tile != self.fifo.penalty_tile {
+            self.fifo.penalty_tile = tile;
+            penalty += (7 - pixel % 8).saturating_sub(2);
+        }
+
+        self.load_sprite(index);
+        // Este dot ya cuenta como el primero de la pausa.
+        self.fifo.stall = penalty - 1;
+        true
+    }
+
+    /// Mezcla la fila del sprite en la FIFO de sprites. Donde ya hay un píxel opaco de
+    /// otro sprite, gana el que estaba (se buscó antes: X menor o índice menor).
+    fn load_sprite(&mut self, index: usize) {
+        let sprite_height = if (self.lcdc & 0x04) != 0 { 16 } else { 8 };
+        let y_pos = self.oam[index] as i16 - 16;
+        let x_pos = self.oam[index + 1] as i16 - 8;
+        let tile_idx = self.oam[index + 2];
+        let flags = self.oam[index + 3];
+
+        // OAM y LCDC se releen ahora, no en el OAM scan: si el juego cambió la Y o el
+        // tamaño durante el modo 3, la fila puede quedar fuera del sprite. La máscara la
+        // deja dentro del tile (como el hardware, que solo mira los bits bajos).
+        let mut line = (self.ly as i16).wrapping_sub(y_pos) as u16 & (sprite_height - 1);
+        if (flags & 0x40) != 0 { line = sprite_height - 1 - line; }
+        let actual_tile_idx = if sprite_height == 16 { tile_idx & 0xFE } else { tile_idx };
+        let tile_addr = (actual_tile_idx as u16 * 16) + (line * 2);
+        let byte1 = self.vram[tile_addr as usize];
+        let byte2 = self.vram[(tile_addr + 1) as usize];
+
+        for pixel_x in 0..8 {
+            // Los píxeles a la izquierda del actual (sprite cortado por el borde) se pierden.
+            let slot = x_pos + pixel_x - self.fifo.lx as i16;
+            if !(0..8).contains(&slot) { continue; }
+
+            let bit_idx = if (flags & 0x20) != 0 { pixel_x } else { 7 - pixel_x };
+            let color_id = ((byte2 >> bit_idx) & 1) << 1 | ((byte1 >> bit_idx) & 1);
+            let entry = &mut self.fifo.obj[slot as usize];
+            if color_id != 0 && (*entry & 0x03) == 0 {
+                *entry = color_id | (flags & 0x90);
+            }
+        }
+    }
+
+    /// Saca un píxel de las FIFOs hacia el LCD.
+    fn shift_pixel(&mut self) -> bool {
+        let bg = self.fifo.bg[8 - self.fifo.bg_len as usize];
+        self.fifo.bg_len -= 1;
+        if self.fifo.discard > 0 {
+            self.fifo.discard -= 1;
+            return false;
+        }
+        let obj = self.fifo.obj[0];
+        self.fifo.obj.copy_within(1.., 0);
+        self.fifo.obj[7] = 0;
+
+        // LCDC bit 0 apagado: fondo y ventana en blanco, con índice 0 para la prioridad.
+        let bg_enabled = (self.lcdc & 0x01) != 0;
+        let bg = if bg_enabled { bg } else { 0 };
+        // Un sprite con el bit 7 queda detrás de los colores 1-3 del fondo.
+        let obj_visible = (obj & 0x03) != 0 && (self.lcdc & 0x02) != 0 && ((obj & 0x80) == 0 || bg == 0);
+
+        // Las paletas se leen ahora: un cambio de BGP a mitad de línea se ve desde aquí.
+        let color = if obj_visible {
+            let palette = if (obj & 0x10) != 0 { self.obp1 } else { self.obp0 };
+            self.get_color(obj & 0x03, palette)
+        } else if bg_enabled {
+            self.get_color(bg, self.bgp)
+        } else {
+            BLANK
+        };
+        self.set_pixel(self.fifo.lx as usize, self.ly as usize, color);
+        self.fifo.lx += 1;
+        if self.fifo.lx as usize == SCREEN_WIDTH {
+            self.end_window_line(self.fifo.window);
+            return true;
+        }
+        false
+    }
+}
+
+/// El pipeline también entra en el save state: un estado tomado a mitad del modo 3
+/// tiene que seguir la línea exactamente igual.
+impl Snapshot for Fifo {
+    fn save_state(&self, w: &mut Writer) {
+        for v in [self.lx, self.discard, self.stall, self.step, self.fetch_x, self.tile, self.lo, self.hi, self.bg_len] {
+            w.u8(v);
+        }
+        w.bool(self.window);
+        w.bytes(&self.bg);
+        w.bytes(&self.obj);
+        w.blob(&self.sprites);
+        w.u16(self.penalty_tile);
+    }
+
+    fn load_state(&mut self, r: &mut Reader) -> Result<(), StateError> {
+        for v in [
+            &mut self.lx, &mut self.discard, &mut self.stall, &mut self.step, &mut self.fetch_x,
+            &mut self.tile, &mut self.lo, &mut self.hi, &mut self.bg_len,
+        ] {
+            *v = r.u8()?;
+        }
+        self.window = r.bool()?;
+        r.bytes(&mut self.bg)?;
+        r.bytes(&mut self.obj)?;
+        self.sprites = r.blob()?.to_vec();
+        self.penalty_tile = r.u16()?;
+        if self.lx as usize > SCREEN_WIDTH || self.bg_len > 8 || self.sprites.iter().any(|&i| i >= 40) {
+            return Err(StateError::Invalid("pipeline del PPU fuera de rango".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,19 @@
 // gpu/src/lib.rs
 
+use savestate::{Reader, Snapshot, StateError, Writer};
+
+mod fifo;
+
+use fifo::Fifo;
+
 /// Resolución nativa del Game Boy.
 /// 'usize' es el tipo preferido para indexación de arrays en Rust.
 pub const SCREEN_WIDTH: usize = 160;
 pub const SCREEN_HEIGHT: usize = 144;
 
+/// Blanco del LCD: lo que se ve con el fondo apagado (LCDC bit 0), sin pasar por BGP.
+const BLANK: [u8; 4] = [0x9B, 0xBC, 0x0F, 0xFF];
+
 /// Los estados del ciclo de renderizado (Modos de la PPU).
 /// #[derive(...)]: Macros que implementan Traits automáticamente.
 /// - Copy/Clone: Permiten copiar el enum por valor (como un int primitivo).
@@ -17,6 +26,17 @@ pub enum Mode {
     PixelTransfer = 3, // Enviando píxeles al LCD driver.
 }
 
+/// Cómo se dibuja cada línea.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Renderer {
+    /// Pixel FIFO, un píxel por dot como el hardware: los cambios de registros a mitad
+    /// de línea se ven y el modo 3 dura lo que tiene que durar (172 a ~289 dots).
+    #[default]
+    Fifo,
+    /// Modo rápido: la línea entera de golpe al final de un modo 3 fijo de 172 dots.
+    Scanline,
+}
+
 pub struct Gpu {
     // VRAM (Video RAM): 8KB para Tiles y Mapas.
     pub vram: [u8; 0x2000],
@@ -41,9 +61,36 @@ pub struct Gpu {
     
     // Solicitud de interrupción interna hacia el Bus.
     pub request_stat_interrupt: bool,
+    // Línea de interrupción STAT: OR de todas las fuentes activas. Solo un flanco de
+    // subida pide interrupción ("STAT blocking").
+    stat_line: bool,
+
+    // Renderer activo. Privado: cambiarlo en mitad del modo 3 exige reiniciar el pipeline
+    // (ver `set_renderer`).
+    renderer: Renderer,
 
-    // Contador de ciclos para la máquina de estados del PPU.
+    // Dot dentro de la línea actual (0-455) para la máquina de estados del PPU.
     cycles: u32,
+    // Pipeline del modo 3 (solo lo usa el renderer FIFO).
+    fifo: Fifo,
+
+    // --- ESTADO INTERNO DE LA VENTANA ---
+    // La ventana no dibuja la fila LY - WY: tiene su propio contador de líneas, que solo
+    // avanza en las líneas donde se llegó a dibujar (apagarla unas líneas la "pausa").
+    window_line: u8,
+    // LY == WY se comprobó al empezar alguna línea de este frame. Se queda así hasta el
+    // siguiente frame aunque WY cambie después.
+    wy_latched: bool,
+    // La línea anterior terminó con la ventana activa en WX=166: esta línea es ventana
+    // desde el píxel 0 (bug del hardware).
+    window_wrap: bool,
+    // Índice de color (0-3) del fondo/ventana en cada píxel de la línea, para la
+    // prioridad OBJ-BG del renderer de línea. Se rehace en cada línea: no va al save state.
+    bg_line: [u8; SCREEN_WIDTH],
+}
+
+impl Default for Gpu {
+    fn default() -> Self { Self::new() }
 }
 
 impl Gpu {
@@ -59,120 +106,285 @@ impl Gpu {
             bgp: 0xFC, obp0: 0xFF, obp1: 0xFF, // Paletas por defecto
             wy: 0, wx: 0,
             request_stat_interrupt: false,
+            stat_line: false,
+            renderer: Renderer::default(),
             cycles: 0,
+            fifo: Fifo::default(),
+            window_line: 0,
+            wy_latched: false,
+            window_wrap: false,
+            bg_line: [0; SCREEN_WIDTH],
         }
     }
 
     // Getters y Setters rápidos inline
+    /// Renderer activo.
+    pub fn renderer(&self) -> Renderer { self.renderer }
+
+    /// Cambia de renderer. Se puede hacer en cualquier momento: si llega en mitad del
+    /// modo 3, el FIFO empieza la línea con el pipeline vacío (el que tuviera era de
+    /// otra línea y ya había sacado sus 160 píxeles).
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        if renderer != self.renderer && renderer == Renderer::Fifo && self.get_mode() == Mode::PixelTransfer {
+            self.start_pixel_transfer();
+        }
+        self.renderer = renderer;
+    }
+
     pub fn read_vram(&self, addr: u16) -> u8 { self.vram[addr as usize] }
     pub fn write_vram(&mut self, addr: u16, val: u8) { self.vram[addr as usize] = val; }
 
     /// Avanza el estado de la GPU.
     /// Retorna 'true' si se completó un frame (VBlank start) para refrescar la UI.
+    ///
+    /// Cada línea dura 456 dots (T-Cycles): modo 2 (80), modo 3 (172 o más) y el resto
+    /// de HBlank. Las líneas 144-153 son VBlank.
     pub fn step(&mut self, cycles: u32) -> bool {
         self.request_stat_interrupt = false;
 
         // Si el LCD está apagado (Bit 7 de LCDC), reseteamos estado y salimos.
+        // Al encenderlo, la línea 0 arranca en modo 0 durante los 80 dots que serían
+        // el modo 2 y luego pasa al modo 3 (ver `step_scanline` y `step_dot`). En una
+        // línea 0 normal el modo 0 llega después del modo 3, así que no hay confusión.
         if (self.lcdc & 0x80) == 0 {
             self.ly = 0;
             self.stat &= 0xFC; // Forzamos Modo 0 (HBlank) en STAT
             self.cycles = 0;
+            self.stat_line = false;
             return false;
         }
 
+        let frame_ready = match self.renderer {
+            Renderer::Scanline => self.step_scanline(cycles),
+            Renderer::Fifo => {
+                // Dot a dot: el modo 3 avanza píxel a píxel.
+                let mut frame_ready = false;
+                for _ in 0..cycles {
+                    frame_ready |= self.step_dot();
+                }
+                frame_ready
+            }
+        };
+        self.update_stat();
+        frame_ready
+    }
+
+    /// LY, la coincidencia LY=LYC y la línea STAT, una vez por paso (cada M-Cycle desde el Bus).
+    fn update_stat(&mut self) {
+        // Línea 153: LY solo vale 153 durante el primer M-Cycle, después ya lee 0.
+        if self.ly == 153 && self.cycles >= 4 {
+            self.ly = 0;
+        }
+
+        // Bit 2: Coincidence Flag.
+        match self.lyc_compare() {
+            Some(ly) if ly == self.lyc => self.stat |= 0x04,
+            _ => self.stat &= !0x04,
+        }
+
+        // Los cambios de STAT (escrituras del juego incluidas) se notan aquí: si la línea
+        // ya estaba alta por otra fuente, no hay interrupción nueva.
+        let line = self.stat_line_level();
+        if line && !self.stat_line {
+            self.request_stat_interrupt = true;
+        }
+        self.stat_line = line;
+    }
+
+    /// Valor de LY que el comparador con LYC está mirando ahora mismo. Al empezar cada
+    /// línea pasa un M-Cycle sin comparar (el flag lee 0). En la línea 153 se compara
+    /// primero con 153 y, tras otro M-Cycle en blanco, con 0 (que sigue en la línea 0).
+    fn lyc_compare(&self) -> Option<u8> {
+        let line_153 = self.get_mode() == Mode::VBlank && (self.ly == 153 || self.ly == 0);
+        match self.cycles {
+            _ if line_153 => match self.cycles {
+                0..4 => None,
+                4..8 => Some(153),
+                8..12 => None,
+                _ => Some(0),
+            },
+            // La línea 0 sigue comparando con el 0 de la línea 153.
+            _ if self.ly == 0 => Some(0),
+            0..4 => None,
+            _ => Some(self.ly),
+        }
+    }
+
+    /// OR de las fuentes de STAT habilitadas (bits 3-6).
+    fn stat_line_level(&self) -> bool {
+        let mode = self.get_mode();
+        // Al entrar en VBlank (línea 144) también se activa por un momento la fuente del modo 2.
+        let oam = mode == Mode::OamSearch || (mode == Mode::VBlank && self.ly == 144 && self.cycles < 4);
+        // El HBlank se alarga durante el primer M-Cycle de la línea siguiente (1-143): tapa
+        // la coincidencia LYC que llega justo detrás.
+        let hblank = mode == Mode::HBlank || (mode == Mode::OamSearch && self.ly != 0 && self.cycles < 4);
+        ((self.stat & 0x08) != 0 && hblank)
+            || ((self.stat & 0x10) != 0 && mode == Mode::VBlank)
+            || ((self.stat & 0x20) != 0 && oam)
+            || ((self.stat & 0x40) != 0 && (self.stat & 0x04) != 0)
+    }
+
+    /// Modo rápido: duraciones fijas y la línea se dibuja entera al acabar el modo 3.
+    fn step_scanline(&mut self, cycles: u32) -> bool {
         self.cycles += cycles;
-        let mut frame_ready = false;
 
         // Máquina de Estados del PPU (Mode 2 -> 3 -> 0 ... -> 1)
         match self.get_mode() {
             Mode::OamSearch => {
                 // Modo 2: Dura 80 ciclos (búsqueda de sprites)
                 if self.cycles >= 80 {
-                    self.cycles -= 80;
                     self.set_mode(Mode::PixelTransfer);
                 }
             }
             Mode::PixelTransfer => {
-                // Modo 3: Dura ~172 ciclos (dibujado)
-                if self.cycles >= 172 {
-                    self.cycles -= 172;
-                    self.set_mode(Mode::HBlank);
-                    
-                    // Verificación de Interrupción HBlank (STAT Bit 3)
-                    if (self.stat & 0x08) != 0 {
-                        self.request_stat_interrupt = true;
-                    }
-                    
+                // Modo 3: 172 ciclos fijos (dibujado)
+                if self.cycles >= 80 + 172 {
+                    self.enter_hblank();
                     // Renderizamos la scanline completa al buffer
                     self.render_scanline();
                 }
             }
-            Mode::HBlank => {
-                // Modo 0: Dura ~204 ciclos (descanso horizontal)
-                if self.cycles >= 204 {
-                    self.cycles -= 204;
-                    self.ly += 1; // Avanzamos a la siguiente línea
-                    
-                    self.check_lyc(); // Chequear coincidencia LY=LYC
-
-                    if self.ly == 144 {
-                        // Terminó la pantalla visible -> VBlank
-                        self.set_mode(Mode::VBlank);
-                        
-                        // Interrupción VBlank (STAT Bit 4)
-                        if (self.stat & 0x10) != 0 {
-                            self.request_stat_interrupt = true;
-                        }
-                        frame_ready = true; // Avisamos que hay nuevo frame
-                    } else {
-                        // Nueva línea visible -> OamSearch
-                        self.set_mode(Mode::OamSearch);
-                        
-                        // Interrupción OAM (STAT Bit 5)
-                        if (self.stat & 0x20) != 0 {
-                            self.request_stat_interrupt = true;
-                        }
-                    }
-                }
+            // Primera línea tras encender el LCD: empieza en modo 0 sin búsqueda de OAM.
+            Mode::HBlank if self.ly == 0 && self.cycles >= 80 && self.cycles - cycles < 80 => {
+                self.set_mode(Mode::PixelTransfer);
             }
-            Mode::VBlank => {
-                // Modo 1: Dura 4560 ciclos (10 líneas * 456 ciclos)
+            // Modo 0 (descanso horizontal) y Modo 1 (10 líneas de VBlank):
+            // ambos terminan con la línea.
+            Mode::HBlank | Mode::VBlank => {
                 if self.cycles >= 456 {
                     self.cycles -= 456;
-                    self.ly += 1;
-                    self.check_lyc(); 
-
-                    if self.ly > 153 {
-                        // Fin de VBlank, volvemos al principio (Línea 0)
-                        self.ly = 0;
-                        self.set_mode(Mode::OamSearch);
-                        if (self.stat & 0x20) != 0 {
-                            self.request_stat_interrupt = true;
-                        }
-                    }
+                    return self.next_line();
                 }
             }
         }
-        frame_ready
+        false
     }
 
-    /// Compara LY con LYC y actualiza el registro STAT y solicita interrupción si corresponde.
-    fn check_lyc(&mut self) {
-        if self.ly == self.lyc {
-            self.stat |= 0x04; // Bit 2: Coincidence Flag
-            if (self.stat & 0x40) != 0 { // Check Bit 6: LYC Stats Interrupt Enable
-                self.request_stat_interrupt = true;
+    /// Renderer FIFO: un dot.
+    fn step_dot(&mut self) -> bool {
+        self.cycles += 1;
+        match self.get_mode() {
+            Mode::OamSearch => {
+                if self.cycles >= 80 {
+                    self.set_mode(Mode::PixelTransfer);
+                    self.start_pixel_transfer();
+                }
             }
-        } else {
-            self.stat &= !0x04;
+            Mode::PixelTransfer => {
+                // El modo 3 termina cuando sale el píxel 160, no en un dot fijo.
+                if self.pixel_transfer_dot() {
+                    self.enter_hblank();
+                }
+            }
+            Mode::HBlank if self.ly == 0 && self.cycles == 80 => {
+                self.set_mode(Mode::PixelTransfer);
+                self.start_pixel_transfer();
+            }
+            Mode::HBlank | Mode::VBlank => {
+                if self.cycles >= 456 {
+                    self.cycles = 0;
+                    return self.next_line();
+                }
+            }
+        }
+        false
+    }
+
+    /// Fin del modo 3. (La interrupción HBlank, STAT Bit 3, la decide `update_stat`.)
+    fn enter_hblank(&mut self) {
+        self.set_mode(Mode::HBlank);
+    }
+
+    /// Fin de línea: LY avanza y empieza la siguiente (o el VBlank).
+    /// Retorna 'true' al entrar en VBlank.
+    fn next_line(&mut self) -> bool {
+        // En VBlank con LY=0 estamos al final de la línea 153 (LY ya se leía 0).
+        let line = if self.get_mode() == Mode::VBlank && self.ly == 0 { 153 } else { self.ly };
+        self.ly = line + 1; // Avanzamos a la siguiente línea
+
+        match self.ly {
+            // Nueva línea visible -> OamSearch
+            0..=143 => self.enter_oam_search(),
+            144 => {
+                // Terminó la pantalla visible -> VBlank
+                self.set_mode(Mode::VBlank);
+                return true; // Avisamos que hay nuevo frame
+            }
+            145..=153 => {}
+            _ => {
+                // Fin de VBlank, volvemos al principio (Línea 0)
+                self.ly = 0;
+                self.window_line = 0;
+                self.wy_latched = false;
+                self.window_wrap = false;
+                self.enter_oam_search();
+            }
+        }
+        false
+    }
+
+    fn enter_oam_search(&mut self) {
+        // WY solo se compara al empezar la línea.
+        if self.ly == self.wy {
+            self.wy_latched = true;
+        }
+        self.set_mode(Mode::OamSearch);
+    }
+
+    /// Fin de una línea visible: si se dibujó la ventana, su contador avanza.
+    fn end_window_line(&mut self, drawn: bool) {
+        if drawn {
+            self.window_line = self.window_line.wrapping_add(1);
         }
+        self.window_wrap = drawn && self.wx == 166;
+    }
+
+    /// Píxel de pantalla donde empieza la ventana en esta línea (None si no hay ventana)
+    /// y cuántos píxeles de la ventana quedan fuera por la izquierda (WX 0-6).
+    fn window_start(&self) -> Option<(u8, u8)> {
+        if (self.lcdc & 0x20) == 0 || !self.wy_latched {
+            return None;
+        }
+        match self.wx {
+            _ if self.window_wrap => Some((0, 0)),
+            // WX 0-6 empieza antes del borde. (El hardware además tiembla con SCX % 8 en WX=0.)
+            0..=6 => Some((0, 7 - self.wx)),
+            // WX tiene un offset de +7 por hardware. 166 = solo el último píxel.
+            7..=166 => Some((self.wx - 7, 0)),
+            _ => None,
+        }
+    }
+
+    /// OAM scan (lo que hace el modo 2): los primeros 10 sprites de la OAM que
+    /// intersectan la línea actual, en orden de OAM. Solo mira Y: un sprite fuera de
+    /// pantalla en X (X=0 o X>=168) también ocupa uno de los 10 huecos. Es el límite que
+    /// los juegos aprovechan para el parpadeo cuando hay demasiados sprites en una fila.
+    fn oam_scan(&self) -> Vec<u8> {
+        let sprite_height = if (self.lcdc & 0x04) != 0 { 16 } else { 8 };
+        (0..40u8)
+            .filter(|&i| {
+                let y_pos = self.oam[i as usize * 4] as i16 - 16;
+                (self.ly as i16) >= y_pos && (self.ly as i16) < y_pos + sprite_height
+            })
+            .take(10)
+            .collect()
     }
 
     /// Orquesta el renderizado de la línea actual.
     fn render_scanline(&mut self) {
-        // En Go esto serían 'if' simples comprobando flags.
-        if (self.lcdc & 0x01) != 0 { self.render_background(); } // Bit 0: BG Enable
-        if (self.lcdc & 0x20) != 0 { self.render_window(); }     // Bit 5: Windows Enable
+        // Bit 0: en DMG apaga a la vez fondo y ventana; la línea queda en blanco
+        // (color 0 a efectos de prioridad) y los sprites se dibujan encima.
+        if (self.lcdc & 0x01) != 0 {
+            self.render_background();
+            self.render_window(); // Bit 5: Windows Enable (lo mira window_start)
+        } else {
+            self.bg_line = [0; SCREEN_WIDTH];
+            for x in 0..SCREEN_WIDTH {
+                self.set_pixel(x, self.ly as usize, BLANK);
+            }
+            // Sin dibujarse, la ventana se sigue buscando: su contador avanza igual.
+            self.end_window_line(self.window_start().is_some());
+        }
         if (self.lcdc & 0x02) != 0 { self.render_sprites(); }    // Bit 1: OBJ Enable
     }
 
@@ -218,29 +430,29 @@ impl Gpu {
             // Traducimos ID a color real usando paleta
             let color = self.get_color(color_id, self.bgp);
             self.set_pixel(x, y as usize, color);
+            self.bg_line[x] = color_id;
         }
     }
 
     fn render_window(&mut self) {
-        let wy = self.wy;
-        // Si la línea actual está antes de que empiece la ventana, salimos.
-        if self.ly < wy { return; }
-
-        let wx = self.wx;
-        // WX tiene un offset de +7 por hardware.
-        let window_x_pos = wx.wrapping_sub(7);
+        // Sin WY alcanzado, con la ventana apagada o con WX fuera de pantalla, salimos.
+        let Some((window_x_pos, skipped)) = self.window_start() else {
+            self.end_window_line(false);
+            return;
+        };
 
         let map_base: u16 = if (self.lcdc & 0x40) != 0 { 0x1C00 } else { 0x1800 };
         let use_unsigned = (self.lcdc & 0x10) != 0;
         
-        let window_line = self.ly.wrapping_sub(wy);
+        let window_line = self.window_line;
         let tile_row = (window_line % 8) as u16;
         let tile_line_idx = (window_line / 8) as u16;
 
         for x in 0..SCREEN_WIDTH {
             if (x as u8) < window_x_pos { continue; }
-            
-            let window_rel_x = (x as u8).wrapping_sub(window_x_pos);
+
+            // Con WX 0-6 los primeros 'skipped' píxeles de la ventana quedan fuera.
+            let window_rel_x = (x as u8 - window_x_pos) + skipped;
             let tile_col = (window_rel_x / 8) as u16;
             
             let tile_map_addr = map_base + (tile_line_idx * 32) + tile_col;
@@ -253,42 +465,46 @@ impl Gpu {
             let bit_idx = 7 - (window_rel_x % 8);
             let lo = (byte1 >> bit_idx) & 1;
             let hi = (byte2 >> bit_idx) & 1;
-            let color = self.get_color((hi << 1) | lo, self.bgp);
+            let color_id = (hi << 1) | lo;
+            let color = self.get_color(color_id, self.bgp);
 
             self.set_pixel(x, self.ly as usize, color);
+            self.bg_line[x] = color_id;
         }
+        self.end_window_line(true);
     }
 
     fn render_sprites(&mut self) {
         // Bit 2: Tamaño de Sprite (8x8 vs 8x16)
         let sprite_height = if (self.lcdc & 0x04) != 0 { 16 } else { 8 };
-        
-        // Iteramos los 40 sprites posibles.
-        // Nota: En un emulador preciso, hay límite de 10 sprites por línea. Aquí simplificamos.
-        for i in 0..40 {
-            let idx = i * 4;
+
+        // Prioridad en DMG: gana el sprite con X menor y, a igual X, el de índice de OAM
+        // menor. 'sort_by_key' es estable, así que el orden de OAM desempata solo.
+        let mut sprites = self.oam_scan();
+        sprites.sort_by_key(|&i| self.oam[i as usize * 4 + 1]);
+
+        // Píxeles de sprite de la línea (mismo formato que la FIFO de sprites):
+        // recorriendo de más a menos prioridad, el primer píxel opaco se queda.
+        let mut line = [0u8; SCREEN_WIDTH];
+        for i in sprites {
+            let idx = i as usize * 4;
             let y_pos = self.oam[idx] as i16 - 16;
             let x_pos = self.oam[idx+1] as i16 - 8;
             let tile_idx = self.oam[idx+2];
             let flags = self.oam[idx+3];
 
-            // Verificamos si el sprite intercepta la línea actual ('scanline').
-            if (self.ly as i16) < y_pos || (self.ly as i16) >= (y_pos + sprite_height) { continue; }
-
             // Flags de volteo
             let y_flip = (flags & 0x40) != 0;
             let x_flip = (flags & 0x20) != 0;
-            // Palette selection (Non-CGB)
-            let palette = if (flags & 0x10) != 0 { self.obp1 } else { self.obp0 };
 
             // Cálculo de línea interna del sprite
-            let mut line = (self.ly as i16 - y_pos) as u16;
-            if y_flip { line = sprite_height as u16 - 1 - line; }
-            
+            let mut line_y = (self.ly as i16 - y_pos) as u16;
+            if y_flip { line_y = sprite_height as u16 - 1 - line_y; }
+
             // En modo 8x16, el bit menor del tile index se ignora.
             let actual_tile_idx = if sprite_height == 16 { tile_idx & 0xFE } else { tile_idx };
-            
-            let tile_addr = (actual_tile_idx as u16 * 16) + (line * 2);
+
+            let tile_addr = (actual_tile_idx as u16 * 16) + (line_y * 2);
             let byte1 = self.vram[tile_addr as usize];
             let byte2 = self.vram[(tile_addr + 1) as usize];
 
@@ -303,15 +519,26 @@ impl Gpu {
                 let hi = (byte2 >> bit_idx) & 1;
                 let color_id = (hi << 1) | lo;
 
-                if color_id == 0 { continue; } // Color 0 en OBJ es transparente
-
-                let color = self.get_color(color_id, palette);
-                
-                // Prioridad (Bit 7): Si es 1, el sprite se oculta detrás de colores de fondo != 0.
-                // Aquí omitimos esa lógica para simplificar, dibujando siempre encima.
-                self.set_pixel(screen_x as usize, self.ly as usize, color);
+                // Color 0 en OBJ es transparente: deja ver al sprite de menos prioridad.
+                let entry = &mut line[screen_x as usize];
+                if color_id != 0 && (*entry & 0x03) == 0 {
+                    *entry = color_id | (flags & 0x90);
+                }
             }
         }
+
+        for (x, entry) in line.into_iter().enumerate() {
+            if (entry & 0x03) == 0 { continue; }
+            // Palette selection (Non-CGB)
+            let palette = if (entry & 0x10) != 0 { self.obp1 } else { self.obp0 };
+            let color = self.get_color(entry & 0x03, palette);
+
+            // Prioridad (Bit 7): Si es 1, el sprite se oculta detrás de colores de fondo != 0.
+            // Como el ganador ya se eligió, un sprite oculto así también tapa a los de
+            // menos prioridad que hubiera debajo (igual que en el hardware).
+            if (entry & 0x80) != 0 && self.bg_line[x] != 0 { continue; }
+            self.set_pixel(x, self.ly as usize, color);
+        }
     }
 
     /// Helper para obtener dirección de datos de tiles.
@@ -358,4 +585,69 @@ impl Gpu {
     fn set_mode(&mut self, mode: Mode) { 
         self.stat = (self.stat & !0x03) | (mode as u8); 
     }
-}
\ No newline at end of file
+}
+
+/// Save state: memoria de vídeo, registros, el contador interno y también el framebuffer
+/// (así la pantalla no queda en negro hasta el siguiente V-Blank). El renderer elegido
+/// no se guarda: es una opción del usuario, no estado de la máquina.
+impl Snapshot for Gpu {
+    fn save_state(&self, w: &mut Writer) {
+        w.bytes(&self.vram);
+        w.bytes(&self.oam);
+        w.bytes(&self.frame_buffer);
+        for v in [self.lcdc, self.stat, self.scy, self.scx, self.ly, self.lyc, self.bgp, self.obp0, self.obp1, self.wy, self.wx] {
+            w.u8(v);
+        }
+        w.bool(self.request_stat_interrupt);
+        w.u32(self.cycles);
+        self.fifo.save_state(w);
+        w.u8(self.window_line);
+        w.bool(self.wy_latched);
+        w.bool(self.window_wrap);
+        w.bool(self.stat_line);
+    }
+
+    fn load_state(&mut self, r: &mut Reader) -> Result<(), StateError> {
+        r.bytes(&mut self.vram)?;
+        r.bytes(&mut self.oam)?;
+        r.bytes(&mut self.frame_buffer)?;
+        for v in [
+            &mut self.lcdc, &mut self.stat, &mut self.scy, &mut self.scx, &mut self.ly, &mut self.lyc,
+            &mut self.bgp, &mut self.obp0, &mut self.obp1, &mut self.wy, &mut self.wx,
+        ] {
+            *v = r.u8()?;
+        }
+        self.request_stat_interrupt = r.bool()?;
+        self.cycles = r.u32()?;
+        if r.version >= 2 {
+            self.fifo.load_state(r)?;
+        } else {
+            // Versión 1: el contador era de dots dentro del modo, no de la línea,
+            // y no había pipeline (el modo 3 empieza de nuevo).
+            self.cycles += match self.get_mode() {
+                Mode::PixelTransfer => 80,
+                Mode::HBlank => 80 + 172,
+                Mode::OamSearch | Mode::VBlank => 0,
+            };
+            self.fifo = Fifo::default();
+            if self.get_mode() == Mode::PixelTransfer {
+                self.start_pixel_transfer();
+            }
+        }
+        if r.version >= 3 {
+            self.window_line = r.u8()?;
+            self.wy_latched = r.bool()?;
+            self.window_wrap = r.bool()?;
+        } else {
+            // Antes la ventana dibujaba la fila LY - WY: es lo que vale el contador
+            // si nadie la apagó a mitad de frame.
+            self.wy_latched = self.ly >= self.wy && self.ly < 144;
+            self.window_line = if self.wy_latched { self.ly - self.wy } else { 0 };
+            self.window_wrap = false;
+        }
+        // Versión 3 o anterior: la línea STAT no existía; se toma su nivel actual
+        // (sin flanco, así cargar no dispara una interrupción).
+        self.stat_line = if r.version >= 4 { r.bool()? } else { self.stat_line_level() };
+        Ok(())
+    }
+}
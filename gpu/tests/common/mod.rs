@@ -0,0 +1,63 @@
+// gpu/tests/common/mod.rs
+
+//! Utilidades compartidas por los tests de integración de la GPU.
+
+// Cada archivo de tests usa solo algunas.
+#![allow(dead_code)]
+
+use gpu::{Gpu, Renderer, SCREEN_WIDTH};
+
+/// Colores de la paleta identidad (BGP = 0xE4), del 0 al 3.
+pub const COLORS: [[u8; 4]; 4] = [
+    [0x9B, 0xBC, 0x0F, 0xFF],
+    [0x8B, 0xAC, 0x0F, 0xFF],
+    [0x30, 0x62, 0x30, 0xFF],
+    [0x0F, 0x38, 0x0F, 0xFF],
+];
+
+/// LCD encendido, fondo y sprites activos, tiles en 0x8000, paletas identidad.
+/// Tile 1 = color 3 entero, tile 3 = color 1 entero. El fondo (tile 0) queda en color 0.
+pub fn gpu(renderer: Renderer) -> Gpu {
+    let mut gpu = Gpu::new();
+    gpu.set_renderer(renderer);
+    gpu.lcdc = 0x93;
+    gpu.bgp = 0xE4;
+    gpu.obp0 = 0xE4;
+    gpu.vram[0x10..0x20].fill(0xFF);
+    for row in 0..8 {
+        gpu.vram[0x30 + row * 2] = 0xFF;
+    }
+    gpu
+}
+
+/// Avanza M-Cycle a M-Cycle (4 dots, como el Bus) hasta el modo 2 de la línea 'ly'.
+pub fn run_to_line(gpu: &mut Gpu, ly: u8) {
+    while !(gpu.ly == ly && gpu.stat & 0x03 == 2) {
+        gpu.step(4);
+    }
+}
+
+/// Dibuja un frame completo (el primero empieza a mitad).
+pub fn render(gpu: &mut Gpu) {
+    let mut frames = 0;
+    while frames < 2 {
+        frames += gpu.step(4) as u32;
+    }
+}
+
+/// Sprite 'n' con la esquina superior izquierda en pantalla (x, y).
+pub fn sprite(gpu: &mut Gpu, n: usize, x: i16, y: i16, tile: u8) {
+    gpu.oam[n * 4..n * 4 + 4].copy_from_slice(&[(y + 16) as u8, (x + 8) as u8, tile, 0]);
+}
+
+/// RGBA de un píxel del frame buffer.
+pub fn pixel(gpu: &Gpu, x: usize, y: usize) -> [u8; 4] {
+    let offset = (y * SCREEN_WIDTH + x) * 4;
+    gpu.frame_buffer[offset..offset + 4].try_into().unwrap()
+}
+
+/// Color (0-3) de un píxel.
+pub fn color(gpu: &Gpu, x: usize, y: usize) -> usize {
+    let rgba = pixel(gpu, x, y);
+    COLORS.iter().position(|&c| c == rgba).unwrap()
+}
@@ -0,0 +1,153 @@
+// gpu/tests/fifo.rs
+
+//! Renderer de pixel FIFO: duración del modo 3 y efectos a mitad de línea.
+
+use gpu::{Gpu, Renderer};
+
+mod common;
+use common::{gpu, pixel, render, run_to_line, sprite};
+
+/// Avanza hasta el primer dot del modo 3 de la línea 'ly' (el OAM scan ya está hecho).
+fn run_to_mode3(gpu: &mut Gpu, ly: u8) {
+    run_to_line(gpu, ly);
+    while gpu.stat & 0x03 != 3 {
+        gpu.step(1);
+    }
+}
+
+/// Dots que dura el modo 3 de la línea 'ly'.
+fn mode3_length(gpu: &mut Gpu, ly: u8) -> u32 {
+    run_to_mode3(gpu, ly);
+    let mut dots = 0;
+    while gpu.stat & 0x03 == 3 {
+        gpu.step(1);
+        dots += 1;
+    }
+    dots
+}
+
+#[test]
+fn mode3_lasts_172_dots_plus_fine_scroll() {
+    assert_eq!(mode3_length(&mut gpu(Renderer::Fifo), 1), 172);
+
+    let mut scrolled = gpu(Renderer::Fifo);
+    scrolled.scx = 3;
+    assert_eq!(mode3_length(&mut scrolled, 1), 175);
+    // Solo cuenta la parte fina: SCX=8 es un tile entero y no descarta nada.
+    scrolled.scx = 8;
+    assert_eq!(mode3_length(&mut scrolled, 2), 172);
+}
+
+#[test]
+fn sprites_and_window_lengthen_mode3() {
+    // Sprite en el borde izquierdo de un tile: 6 dots + 5 de espera al fetcher.
+    let mut g = gpu(Renderer::Fifo);
+    sprite(&mut g, 0, 0, 0, 1);
+    assert_eq!(mode3_length(&mut g, 1), 172 + 11);
+
+    // Un segundo sprite en el mismo tile solo paga los 6 dots.
+    sprite(&mut g, 1, 2, 0, 1);
+    assert_eq!(mode3_length(&mut g, 2), 172 + 11 + 6);
+
+    // Cerca del final del tile, el fetcher ya terminó: sin espera extra.
+    let mut g = gpu(Renderer::Fifo);
+    sprite(&mut g, 0, 5, 0, 1);
+    assert_eq!(mode3_length(&mut g, 1), 172 + 6);
+
+    // Con OBJ desactivado no se buscan.
+    g.lcdc &= !0x02;
+    assert_eq!(mode3_length(&mut g, 2), 172);
+
+    // La ventana reinicia el fetcher: 6 dots.
+    let mut g = gpu(Renderer::Fifo);
+    g.lcdc |= 0x20;
+    g.wy = 1; // WY se compara al empezar la línea 1
+    g.wx = 7 + 80;
+    assert_eq!(mode3_length(&mut g, 1), 172 + 6);
+}
+
+#[test]
+fn mid_line_palette_write_splits_the_line() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.vram[0x1800..0x1C00].fill(1);
+        run_to_line(&mut g, 1);
+        // Mitad del modo 3: 80 dots de modo 2 + 12 de arranque + 80 píxeles.
+        for _ in 0..80 + 12 + 80 {
+            g.step(1);
+        }
+        g.bgp = 0x00;
+        run_to_line(&mut g, 2);
+
+        let (left, right) = (pixel(&g, 10, 1), pixel(&g, 150, 1));
+        match renderer {
+            // El píxel 10 salió con la paleta vieja (color 3), el 150 con la nueva.
+            Renderer::Fifo => assert_ne!(left, right),
+            // El modo rápido dibuja la línea entera al final: toda con la nueva.
+            Renderer::Scanline => assert_eq!(left, right),
+        }
+    }
+}
+
+#[test]
+fn fifo_matches_scanline_renderer_on_static_scene() {
+    let scene = |renderer| {
+        let mut g = gpu(renderer);
+        // Tile 2: rayas verticales con los 4 colores.
+        for row in 0..8 {
+            g.vram[0x20 + row * 2] = 0b0101_0101;
+            g.vram[0x20 + row * 2 + 1] = 0b0011_0011;
+        }
+        for (i, tile) in g.vram[0x1800..0x1C00].iter_mut().enumerate() {
+            *tile = (i % 3) as u8;
+        }
+        g.vram[0x1C00..0x2000].fill(2);
+        g.lcdc |= 0x20 | 0x40;
+        g.scx = 13;
+        g.scy = 5;
+        g.wy = 100;
+        g.wx = 7 + 40;
+        sprite(&mut g, 0, -3, 10, 2);
+        sprite(&mut g, 1, 60, 50, 1);
+        sprite(&mut g, 2, 155, 120, 2);
+        render(&mut g);
+        g.frame_buffer
+    };
+    assert!(scene(Renderer::Fifo) == scene(Renderer::Scanline));
+}
+
+#[test]
+fn oam_and_lcdc_changes_during_mode3_do_not_panic() {
+    // Sprite 8x16 con flip Y: en la fila 12 se pasa a 8x8 después del OAM scan.
+    let mut g = gpu(Renderer::Fifo);
+    g.lcdc |= 0x04;
+    sprite(&mut g, 0, 80, 0, 1);
+    g.oam[3] = 0x40;
+    run_to_mode3(&mut g, 12);
+    g.lcdc &= !0x04;
+    run_to_line(&mut g, 13);
+
+    // La Y del sprite cambia a mitad del modo 3: la línea ya no lo cruza.
+    let mut g = gpu(Renderer::Fifo);
+    sprite(&mut g, 0, 80, 10, 1);
+    run_to_mode3(&mut g, 12);
+    g.oam[0] = 0xF0;
+    run_to_line(&mut g, 13);
+    // Se dibuja igual (el scan ya lo eligió), con una fila dentro del tile.
+    assert_eq!(pixel(&g, 84, 12), pixel(&g, 84, 11));
+}
+
+#[test]
+fn switching_to_fifo_during_mode3_restarts_the_pipeline() {
+    // La línea 10 deja el pipeline con sus 160 píxeles; la 11 empieza con el de línea.
+    let mut g = gpu(Renderer::Fifo);
+    run_to_line(&mut g, 11);
+    g.set_renderer(Renderer::Scanline);
+    run_to_mode3(&mut g, 11);
+    g.step(4);
+    g.set_renderer(Renderer::Fifo);
+    run_to_line(&mut g, 12);
+    assert_eq!(g.renderer(), Renderer::Fifo);
+    // La línea 11 se dibujó entera con el FIFO.
+    assert_eq!(pixel(&g, 159, 11), pixel(&g, 159, 10));
+}
@@ -0,0 +1,120 @@
+// gpu/tests/sprites.rs
+
+//! Sprites: límite de 10 por línea, prioridad de DMG y prioridad frente al fondo,
+//! con los dos renderers.
+
+use gpu::Renderer;
+
+mod common;
+use common::{gpu, pixel, render, sprite, COLORS};
+
+const LIGHT: [u8; 4] = COLORS[1];
+const DARK: [u8; 4] = COLORS[3];
+
+#[test]
+fn only_first_ten_sprites_of_a_line_are_drawn() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        for n in 0..11 {
+            sprite(&mut g, n, n as i16 * 10, 20, 1);
+        }
+        render(&mut g);
+        assert_eq!(pixel(&g, 90, 20), DARK);
+        assert_ne!(pixel(&g, 100, 20), DARK);
+
+        // Los sprites fuera de pantalla en X también gastan hueco.
+        let mut g = gpu(renderer);
+        for n in 0..10 {
+            sprite(&mut g, n, -8, 20, 1);
+        }
+        sprite(&mut g, 10, 50, 20, 1);
+        // En otra línea el sprite 10 sí entra.
+        sprite(&mut g, 11, 50, 40, 1);
+        render(&mut g);
+        assert_ne!(pixel(&g, 50, 20), DARK);
+        assert_eq!(pixel(&g, 50, 40), DARK);
+    }
+}
+
+#[test]
+fn smaller_x_wins_then_lower_oam_index() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        // X menor gana aunque tenga índice mayor.
+        sprite(&mut g, 0, 10, 20, 3);
+        sprite(&mut g, 1, 6, 20, 1);
+        // A igual X, gana el índice menor.
+        sprite(&mut g, 2, 40, 20, 3);
+        sprite(&mut g, 3, 40, 20, 1);
+        // Cortados por el borde izquierdo: sigue ganando la X menor.
+        sprite(&mut g, 4, -2, 40, 3);
+        sprite(&mut g, 5, -5, 40, 1);
+        render(&mut g);
+
+        assert_eq!(pixel(&g, 12, 20), DARK);
+        assert_eq!(pixel(&g, 15, 20), LIGHT);
+        assert_eq!(pixel(&g, 42, 20), LIGHT);
+        assert_eq!(pixel(&g, 1, 40), DARK);
+        assert_eq!(pixel(&g, 4, 40), LIGHT);
+    }
+}
+
+#[test]
+fn transparent_pixels_show_lower_priority_sprite() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        // Tile 4: solo la mitad izquierda opaca (color 1).
+        for row in 0..8 {
+            g.vram[0x40 + row * 2] = 0xF0;
+        }
+        sprite(&mut g, 0, 20, 20, 4);
+        sprite(&mut g, 1, 20, 20, 1);
+        render(&mut g);
+        assert_eq!(pixel(&g, 21, 20), LIGHT);
+        assert_eq!(pixel(&g, 26, 20), DARK);
+    }
+}
+
+#[test]
+fn behind_bg_sprites_only_show_over_color_zero() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        // Columnas de tiles 0-3 en color 3, el resto en color 0.
+        for row in 0..32 {
+            g.vram[0x1800 + row * 32..0x1800 + row * 32 + 4].fill(1);
+        }
+        // Sprite detrás del fondo cruzando el borde en x=32.
+        sprite(&mut g, 0, 28, 20, 3);
+        g.oam[3] = 0x80;
+        // Uno detrás del fondo con más prioridad tapa al de delante que tiene debajo.
+        sprite(&mut g, 1, 8, 40, 3);
+        g.oam[7] = 0x80;
+        sprite(&mut g, 2, 10, 40, 3);
+        render(&mut g);
+
+        assert_eq!(pixel(&g, 29, 20), DARK);
+        assert_eq!(pixel(&g, 33, 20), LIGHT);
+        assert_eq!(pixel(&g, 12, 40), DARK);
+    }
+}
+
+#[test]
+fn bg_enable_off_blanks_background_and_window() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.vram[0x1800..0x2000].fill(1);
+        g.lcdc |= 0x20;
+        g.wx = 7 + 80;
+        sprite(&mut g, 0, 20, 20, 1);
+        render(&mut g);
+        assert_eq!(pixel(&g, 0, 0), DARK);
+        assert_eq!(pixel(&g, 100, 0), DARK);
+
+        // Sin el bit 0 no queda nada del frame anterior: blanco, con los sprites encima.
+        g.lcdc &= !0x01;
+        render(&mut g);
+        assert_eq!(pixel(&g, 0, 0), COLORS[0]);
+        assert_eq!(pixel(&g, 100, 0), COLORS[0]);
+        assert_eq!(pixel(&g, 20, 20), DARK);
+    }
+}
@@ -0,0 +1,161 @@
+// gpu/tests/stat.rs
+
+//! Línea de interrupción STAT: OR de las fuentes, flanco de subida ("STAT blocking")
+//! y los tiempos de LY/LYC, con los dos renderers.
+
+use gpu::{Gpu, Renderer};
+
+mod common;
+use common::{gpu, run_to_line};
+
+/// Interrupciones STAT pedidas durante 'lines' líneas completas.
+fn count_interrupts(gpu: &mut Gpu, lines: u32) -> u32 {
+    let mut count = 0;
+    for _ in 0..lines * 114 {
+        gpu.step(4);
+        count += gpu.request_stat_interrupt as u32;
+    }
+    count
+}
+
+#[test]
+fn hblank_and_oam_sources_share_one_line() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        run_to_line(&mut g, 10);
+        // HBlank pasa directo a modo 2: la línea no baja y solo hay flanco al entrar en HBlank.
+        g.stat |= 0x08 | 0x20;
+        g.step(4); // el flanco por el modo 2 que ya estaba activo
+        assert_eq!(count_interrupts(&mut g, 20), 20);
+
+        // Solo el modo 2: uno por línea también.
+        g.stat = (g.stat & 0x07) | 0x20;
+        g.step(4);
+        assert_eq!(count_interrupts(&mut g, 20), 20);
+    }
+}
+
+#[test]
+fn lyc_during_hblank_is_blocked() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.lyc = 50;
+        run_to_line(&mut g, 40);
+        g.stat |= 0x08 | 0x40;
+        // El HBlank de la línea 49 sigue alto cuando empieza la coincidencia de la 50,
+        // y la coincidencia tapa a su vez el HBlank de la 50.
+        run_to_line(&mut g, 49);
+        assert_eq!(count_interrupts(&mut g, 2), 1);
+        assert_eq!(count_interrupts(&mut g, 1), 1);
+
+        // Sin HBlank, la coincidencia sí dispara su propio flanco.
+        let mut g = gpu(renderer);
+        g.lyc = 50;
+        run_to_line(&mut g, 49);
+        g.stat |= 0x40;
+        assert_eq!(count_interrupts(&mut g, 2), 1);
+    }
+}
+
+#[test]
+fn enabling_a_source_while_its_condition_holds_fires() {
+    let mut g = gpu(Renderer::Fifo);
+    run_to_line(&mut g, 5);
+    g.stat |= 0x20;
+    g.step(4);
+    assert!(g.request_stat_interrupt);
+    // Escribir STAT otra vez con la línea alta no repite la interrupción.
+    g.stat |= 0x20;
+    g.step(4);
+    assert!(!g.request_stat_interrupt);
+}
+
+#[test]
+fn lyc_flag_skips_first_m_cycle_of_each_line() {
+    let mut g = gpu(Renderer::Fifo);
+    g.lyc = 20;
+    run_to_line(&mut g, 19);
+    while g.ly != 20 {
+        g.step(4);
+    }
+    assert_eq!(g.stat & 0x04, 0);
+    g.step(4);
+    assert_ne!(g.stat & 0x04, 0);
+    // Y se apaga al empezar la línea siguiente.
+    while g.ly != 21 {
+        g.step(4);
+    }
+    assert_eq!(g.stat & 0x04, 0);
+}
+
+#[test]
+fn line_153_reads_ly_0_early() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.lyc = 153;
+        run_to_line(&mut g, 143);
+        while g.ly != 153 {
+            g.step(4);
+        }
+        // Primer M-Cycle: LY=153 pero todavía sin comparar.
+        assert_eq!((g.ly, g.stat & 0x04), (153, 0));
+        g.step(4);
+        // Segundo: LY ya lee 0, pero se compara con 153.
+        assert_eq!((g.ly, g.stat & 0x04), (0, 0x04));
+        g.step(4);
+        assert_eq!(g.stat & 0x04, 0);
+
+        // El resto de la línea 153 y toda la línea 0 coinciden con LYC=0.
+        g.lyc = 0;
+        g.step(4);
+        assert_eq!(g.stat & 0x03, 1);
+        let mut steps = 0;
+        while g.ly == 0 {
+            assert_ne!(g.stat & 0x04, 0);
+            g.step(4);
+            steps += 1;
+        }
+        // 111 M-Cycles restantes de la línea 153 + los 114 de la línea 0.
+        assert_eq!(steps, 111 + 114);
+        assert_eq!(g.ly, 1);
+    }
+}
+
+#[test]
+fn vblank_entry_also_triggers_oam_source() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        run_to_line(&mut g, 143);
+        g.stat |= 0x20;
+        g.step(4);
+        // Del modo 2 de la línea 143 al de la 0: el pulso al entrar en VBlank y el modo 2 de la 0.
+        let mut count = 0;
+        while !(g.ly == 0 && g.stat & 0x03 == 2) {
+            g.step(4);
+            count += g.request_stat_interrupt as u32;
+        }
+        assert_eq!(count, 2);
+    }
+}
+
+#[test]
+fn first_line_after_lcd_on_skips_oam_search() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        run_to_line(&mut g, 50);
+        g.lcdc &= !0x80;
+        g.step(4);
+        g.lcdc |= 0x80;
+
+        // 80 dots en modo 0 (sin modo 2), luego modo 3 y HBlank en la misma línea 0.
+        let mut modes = Vec::new();
+        for _ in 0..114 {
+            g.step(4);
+            if g.ly == 0 && modes.last() != Some(&(g.stat & 0x03)) {
+                modes.push(g.stat & 0x03);
+            }
+        }
+        assert_eq!(modes, [0, 3, 0]);
+        assert_eq!((g.ly, g.stat & 0x03), (1, 2));
+    }
+}
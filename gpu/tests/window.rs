@@ -0,0 +1,108 @@
+// gpu/tests/window.rs
+
+//! Ventana: contador de líneas propio, WY latcheado y WX en los bordes.
+
+use gpu::{Gpu, Renderer};
+
+mod common;
+use common::{color, run_to_line};
+
+/// Fondo en color 0 (tile 0). La ventana usa el mapa 0x9C00 con el tile 1:
+/// la fila 'r' del tile es de color r % 4 y, en la fila 0, la columna 'c' es de color 3 - c % 4.
+fn gpu(renderer: Renderer) -> Gpu {
+    let mut gpu = common::gpu(renderer);
+    gpu.lcdc = 0x91 | 0x20 | 0x40;
+    gpu.vram[0x10] = 0b1010_1010;
+    gpu.vram[0x11] = 0b1100_1100;
+    for row in 1..8 {
+        gpu.vram[0x10 + row * 2] = if row & 1 != 0 { 0xFF } else { 0x00 };
+        gpu.vram[0x10 + row * 2 + 1] = if row & 2 != 0 { 0xFF } else { 0x00 };
+    }
+    gpu.vram[0x1C00..0x2000].fill(1);
+    gpu
+}
+
+#[test]
+fn window_line_counter_pauses_while_window_is_off() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.wy = 10;
+        g.wx = 7;
+        run_to_line(&mut g, 0);
+        // Líneas 10-19 con ventana, 20-29 sin ella.
+        run_to_line(&mut g, 20);
+        g.lcdc &= !0x20;
+        run_to_line(&mut g, 30);
+        g.lcdc |= 0x20;
+        run_to_line(&mut g, 40);
+
+        // Línea 13: fila 3 de la ventana.
+        assert_eq!(color(&g, 20, 13), 3);
+        // Línea 30: la ventana retoma en su fila 10 (tile 1, fila 2), no en la 20.
+        assert_eq!(color(&g, 20, 30), 2);
+        assert_eq!(color(&g, 20, 25), 0);
+    }
+}
+
+#[test]
+fn wy_is_latched_for_the_rest_of_the_frame() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.wy = 10;
+        g.wx = 7;
+        run_to_line(&mut g, 0);
+        run_to_line(&mut g, 20);
+        // Subir WY ya no esconde la ventana en este frame...
+        g.wy = 100;
+        run_to_line(&mut g, 40);
+        assert_eq!(color(&g, 20, 35), 1); // fila 25 -> fila 1 del tile
+
+        // ...y en el siguiente frame empieza en la línea 100.
+        run_to_line(&mut g, 0);
+        run_to_line(&mut g, 104);
+        assert_eq!(color(&g, 20, 50), 0);
+        assert_eq!(color(&g, 20, 103), 3);
+
+        // WY bajado por debajo de LY a mitad de frame: LY == WY nunca se vio.
+        run_to_line(&mut g, 0);
+        run_to_line(&mut g, 50);
+        g.wy = 10;
+        run_to_line(&mut g, 60);
+        assert_eq!(color(&g, 20, 55), 0);
+    }
+}
+
+#[test]
+fn wx_below_7_starts_the_window_off_screen() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.wy = 0;
+        g.wx = 5;
+        run_to_line(&mut g, 0);
+        run_to_line(&mut g, 0);
+        run_to_line(&mut g, 2);
+        // Fila 0 del tile: los 2 primeros píxeles de la ventana quedan fuera.
+        assert_eq!(color(&g, 0, 0), 1);
+        assert_eq!(color(&g, 1, 0), 0);
+        assert_eq!(color(&g, 2, 0), 3);
+        assert_eq!(color(&g, 0, 1), 1);
+    }
+}
+
+#[test]
+fn wx_166_fills_the_following_line() {
+    for renderer in [Renderer::Fifo, Renderer::Scanline] {
+        let mut g = gpu(renderer);
+        g.wy = 10;
+        g.wx = 166;
+        run_to_line(&mut g, 0);
+        run_to_line(&mut g, 12);
+        // Línea 10: solo el último píxel es ventana.
+        assert_eq!(color(&g, 0, 10), 0);
+        assert_eq!(color(&g, 159, 10), 3); // columna 0 de la fila 0
+        assert_eq!(color(&g, 158, 10), 0);
+        // Línea 11: ventana desde el píxel 0 (fila 1 del tile, color 1).
+        assert_eq!(color(&g, 0, 11), 1);
+        assert_eq!(color(&g, 100, 11), 1);
+    }
+}
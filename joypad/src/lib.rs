@@ -1,5 +1,7 @@
 // joypad/src/lib.rs
 
+use savestate::{Reader, Snapshot, StateError, Writer};
+
 /// Enumeración para identificar los botones.
 /// Derivamos Debug, Clone, Copy y PartialEq para usarlos fácilmente.
 /// - Copy: Permite pasar el botón por valor sin mover la propiedad (ownership).
@@ -27,6 +29,10 @@ pub struct Joypad {
     pub interrupt_request: bool,
 }
 
+impl Default for Joypad {
+    fn default() -> Self { Self::new() }
+}
+
 impl Joypad {
     pub fn new() -> Self {
         Self {
@@ -69,6 +75,20 @@ impl Joypad {
         }
     }
 
+    /// ¿Está presionado 'btn'? (Para herramientas: películas, depurador.)
+    pub fn is_pressed(&self, btn: Button) -> bool {
+        match btn {
+            Button::Right => self.right,
+            Button::Left => self.left,
+            Button::Up => self.up,
+            Button::Down => self.down,
+            Button::A => self.a,
+            Button::B => self.b,
+            Button::Select => self.select,
+            Button::Start => self.start,
+        }
+    }
+
     /// Lectura del registro P1 (0xFF00).
     /// La lógica es "Active Low" (0 = Seleccionado/Presionado).
     pub fn read(&self) -> u8 {
@@ -106,4 +126,28 @@ impl Joypad {
         // Los bits 0-3 son Read-Only (estado de botones).
         self.selection = val & 0x30;
     }
-}
\ No newline at end of file
+}
+
+/// Save state: botones presionados y fila seleccionada. Los botones se guardan para que
+/// un estado reproduzca la máquina exacta (el frontend los actualiza con la siguiente tecla).
+impl Snapshot for Joypad {
+    fn save_state(&self, w: &mut Writer) {
+        for pressed in [self.right, self.left, self.up, self.down, self.a, self.b, self.select, self.start] {
+            w.bool(pressed);
+        }
+        w.u8(self.selection);
+        w.bool(self.interrupt_request);
+    }
+
+    fn load_state(&mut self, r: &mut Reader) -> Result<(), StateError> {
+        for pressed in [
+            &mut self.right, &mut self.left, &mut self.up, &mut self.down,
+            &mut self.a, &mut self.b, &mut self.select, &mut self.start,
+        ] {
+            *pressed = r.bool()?;
+        }
+        self.selection = r.u8()?;
+        self.interrupt_request = r.bool()?;
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 // mbc/src/lib.rs
 
+use savestate::{Reader, StateError, Writer};
+
 /// Interfaz (Trait) común para todos los tipos de cartuchos.
 /// Permite al Bus interactuar con el cartucho sin saber si es Tetris (simple) o Pokémon (complejo).
 ///
@@ -11,6 +13,38 @@ pub trait Mbc {
     fn read(&self, addr: u16) -> u8;
     // &mut self indica que la escritura puede cambiar el estado interno del struct (ej: cambiar de banco).
     fn write(&mut self, addr: u16, val: u8);
+
+    /// Lectura para herramientas (depurador, trace, profiler): nunca dispara comportamiento
+    /// del mapper. En MBC1 'read' ya no tiene efectos, pero mappers como el MBC3 (latch del
+    /// RTC) o el MBC7 (acelerómetro) sí los tendrían y deberán sobrescribirlo.
+    fn peek(&self, addr: u16) -> u8 { self.read(addr) }
+
+    /// Nombre del mapper, para mostrarlo en el depurador.
+    fn name(&self) -> &'static str;
+
+    // Estado de los bancos. Métodos con implementación por defecto:
+    // un cartucho sin mapper tiene siempre el banco 0 y el 1, y no tiene RAM.
+
+    /// Banco de ROM visible en 0x0000-0x3FFF.
+    fn rom_bank0(&self) -> u16 { 0 }
+
+    /// Banco de ROM visible en 0x4000-0x7FFF.
+    fn rom_bank(&self) -> u16 { 1 }
+
+    /// Banco de RAM externa visible en 0xA000-0xBFFF.
+    fn ram_bank(&self) -> u8 { 0 }
+
+    /// ¿Está habilitada la RAM externa? Si no, 0xA000-0xBFFF lee 0xFF.
+    fn ram_enabled(&self) -> bool { false }
+
+    /// Contenido de la ROM (para identificarla en los save states).
+    fn rom(&self) -> &[u8];
+
+    // Save state: registros del mapper y RAM. La ROM no se guarda (se identifica por su CRC32).
+    // No es el trait `Snapshot` como supertrait porque un cartucho sin mapper no tiene
+    // nada que guardar: así basta con la implementación por defecto.
+    fn save_state(&self, _w: &mut Writer) {}
+    fn load_state(&mut self, _r: &mut Reader) -> Result<(), StateError> { Ok(()) }
 }
 
 // =========================================================================
@@ -36,6 +70,10 @@ impl Mbc for RomOnly {
         // Escribir en la ROM no hace nada.
         // El guion bajo en _addr suprime el warning de "variable no usada".
     }
+
+    fn name(&self) -> &'static str { "ROM ONLY" }
+
+    fn rom(&self) -> &[u8] { &self.rom }
 }
 
 // =========================================================================
@@ -95,16 +133,13 @@ impl Mbc for Mbc1 {
             // 0xA000 - 0xBFFF: RAM Externa (SRAM)
             // Memoria para guardar partidas (si el cartucho tiene pila).
             // ---------------------------------------------------------
-            0xA000..=0xBFFF => {
-                if self.ram_enabled {
-                    let offset = (self.ram_bank as usize * 0x2000) + (addr as usize - 0xA000);
-                    self.ram[offset % self.ram.len()]
-                } else {
-                    0xFF // Open Bus
-                }
+            // Si la RAM está bloqueada, cae al brazo '_' (Open Bus).
+            0xA000..=0xBFFF if self.ram_enabled => {
+                let offset = (self.ram_bank as usize * 0x2000) + (addr as usize - 0xA000);
+                self.ram[offset % self.ram.len()]
             }
             
-            _ => 0xFF,
+            _ => 0xFF, // Open Bus
         }
     }
 
@@ -154,17 +189,56 @@ impl Mbc for Mbc1 {
             // ---------------------------------------------------------
             // 0xA000 - 0xBFFF: Escritura en RAM Externa
             // ---------------------------------------------------------
-            0xA000..=0xBFFF => {
-                if self.ram_enabled {
-                    let offset = (self.ram_bank as usize * 0x2000) + (addr as usize - 0xA000);
-                    let len = self.ram.len();
-                    self.ram[offset % len] = val;
-                }
+            0xA000..=0xBFFF if self.ram_enabled => {
+                let offset = (self.ram_bank as usize * 0x2000) + (addr as usize - 0xA000);
+                let len = self.ram.len();
+                self.ram[offset % len] = val;
             }
 
             _ => {}
         }
     }
+
+    fn rom_bank(&self) -> u16 {
+        // Igual que en 'read': un banco mayor que la ROM "da la vuelta".
+        let banks = (self.rom.len() / 0x4000).max(1);
+        (self.rom_bank as usize % banks) as u16
+    }
+
+    fn name(&self) -> &'static str { "MBC1" }
+
+    // Este MBC1 simplificado no mapea los bits altos en 0x0000-0x3FFF (modo 1 de ROMs de 1MB+),
+    // así que ahí se ve siempre el banco 0. Se usa el valor por defecto de rom_bank0.
+
+    fn ram_bank(&self) -> u8 {
+        let banks = (self.ram.len() / 0x2000).max(1);
+        (self.ram_bank as usize % banks) as u8
+    }
+
+    fn ram_enabled(&self) -> bool { self.ram_enabled }
+
+    fn rom(&self) -> &[u8] { &self.rom }
+
+    fn save_state(&self, w: &mut Writer) {
+        w.u8(self.rom_bank);
+        w.u8(self.ram_bank);
+        w.bool(self.ram_enabled);
+        w.u8(self.banking_mode);
+        w.blob(&self.ram);
+    }
+
+    fn load_state(&mut self, r: &mut Reader) -> Result<(), StateError> {
+        self.rom_bank = r.u8()?;
+        self.ram_bank = r.u8()?;
+        self.ram_enabled = r.bool()?;
+        self.banking_mode = r.u8()?;
+        let ram = r.blob()?;
+        if ram.len() != self.ram.len() {
+            return Err(StateError::Invalid(format!("RAM de {} bytes, el cartucho tiene {}", ram.len(), self.ram.len())));
+        }
+        self.ram.copy_from_slice(ram);
+        Ok(())
+    }
 }
 
 // =========================================================================
@@ -181,7 +255,7 @@ pub fn new_cartridge(data: Vec<u8>) -> Box<dyn Mbc> {
         0x00 => Box::new(RomOnly { rom: data }),
         
         // MBC1 es el más común (Mario Land, Tetris, Zelda).
-        0x01 | 0x02 | 0x03 => Box::new(Mbc1::new(data)),
+        0x01..=0x03 => Box::new(Mbc1::new(data)),
 
         // MBC3 (Pokemon Red/Blue) - Usaremos MBC1 como fallback por ahora.
         // 0x11 | 0x12 | 0x13 => Box::new(Mbc3::new(data)),
@@ -0,0 +1,57 @@
+// mbc/tests/banking.rs
+
+//! Estado de los bancos visto desde el trait `Mbc` (lo que usan depurador y símbolos).
+
+use mbc::{Mbc, Mbc1, RomOnly, new_cartridge};
+
+/// ROM de 'banks' bancos de 16KB; cada banco empieza con su número.
+fn rom(banks: usize, cartridge_type: u8) -> Vec<u8> {
+    let mut rom = vec![0; banks * 0x4000];
+    for bank in 0..banks {
+        rom[bank * 0x4000] = bank as u8;
+    }
+    rom[0x0147] = cartridge_type;
+    rom
+}
+
+#[test]
+fn rom_only_has_fixed_banks() {
+    let cart = RomOnly { rom: rom(2, 0x00) };
+    assert_eq!(cart.name(), "ROM ONLY");
+    assert_eq!((cart.rom_bank0(), cart.rom_bank(), cart.ram_bank()), (0, 1, 0));
+    assert!(!cart.ram_enabled());
+}
+
+#[test]
+fn mbc1_reports_switched_banks() {
+    let mut cart = Mbc1::new(rom(8, 0x01));
+    assert_eq!(cart.name(), "MBC1");
+    assert_eq!(cart.rom_bank(), 1);
+
+    cart.write(0x2000, 0x05);
+    assert_eq!(cart.rom_bank(), 5);
+    assert_eq!(cart.read(0x4000), 5);
+    // Un banco mayor que la ROM da la vuelta, igual que la lectura.
+    cart.write(0x2000, 0x0B);
+    assert_eq!(cart.rom_bank(), 3);
+    assert_eq!(cart.read(0x4000), 3);
+    assert_eq!(cart.rom_bank0(), 0);
+
+    cart.write(0x0000, 0x0A);
+    cart.write(0x6000, 0x01);
+    cart.write(0x4000, 0x02);
+    assert!(cart.ram_enabled());
+    assert_eq!(cart.ram_bank(), 2);
+}
+
+#[test]
+fn peek_matches_read_without_changing_state() {
+    let mut cart = new_cartridge(rom(4, 0x01));
+    cart.write(0x2000, 0x02);
+    cart.write(0x0000, 0x0A);
+    cart.write(0xA123, 0x42);
+    for addr in [0x0000, 0x4000, 0xA123] {
+        assert_eq!(cart.peek(addr), cart.read(addr));
+    }
+    assert_eq!(cart.rom_bank(), 2);
+}
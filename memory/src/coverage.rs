@@ -0,0 +1,98 @@
+// memory/src/coverage.rs
+
+//! Mapa de cobertura de la ROM (estilo CDL, "Code/Data Log" de FCEUX/Mesen).
+//!
+//! Un byte de banderas por cada byte de la ROM, en el mismo orden que el archivo
+//! `.gb` (el banco N empieza en N * 0x4000). Así queda etiquetado por banco sin
+//! formato extra, y un desensamblador puede separar código de datos.
+//!
+//! Las banderas solo se suman (OR): al cargar un `.cdl` existente se sigue
+//! acumulando sobre lo ya visto en sesiones anteriores.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Byte ejecutado como parte de una instrucción (opcode u operando). Compatible con el bit 0 de FCEUX.
+pub const CODE: u8 = 0x01;
+/// Byte leído como dato (LD A,[nn], tablas, fuente de un DMA...). Compatible con el bit 1 de FCEUX.
+pub const DATA: u8 = 0x02;
+/// Primer byte de una instrucción (opcode). Junto con CODE distingue opcodes de operandos.
+pub const OPCODE: u8 = 0x04;
+
+pub struct Coverage {
+    /// Banderas por byte de ROM.
+    pub flags: Vec<u8>,
+    // Archivo del que se cargó y al que se guarda (None = solo en memoria).
+    path: Option<PathBuf>,
+}
+
+impl Coverage {
+    /// Mapa vacío para una ROM de 'rom_len' bytes.
+    pub fn new(rom_len: usize) -> Self {
+        Coverage { flags: vec![0; rom_len], path: None }
+    }
+
+    /// Abre un `.cdl` para seguir acumulando. Si no existe, empieza vacío.
+    /// Un archivo de otro tamaño es de otra ROM: error en vez de mezclarlo.
+    pub fn open(path: &Path, rom_len: usize) -> io::Result<Self> {
+        let flags = match fs::read(path) {
+            Ok(flags) if flags.len() == rom_len => flags,
+            Ok(flags) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} bytes, la ROM tiene {}", flags.len(), rom_len),
+                ));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![0; rom_len],
+            Err(e) => return Err(e),
+        };
+        Ok(Coverage { flags, path: Some(path.to_path_buf()) })
+    }
+
+    /// Escribe el mapa en el archivo de `open`. Como el trace, hay que llamarlo antes de salir.
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => fs::write(path, &self.flags),
+            None => Ok(()),
+        }
+    }
+
+    /// Offset en el archivo de ROM de 'addr' (0x0000-0x7FFF) con 'bank' mapeado ahí.
+    pub fn rom_offset(addr: u16, bank: u16) -> usize {
+        bank as usize * 0x4000 + (addr & 0x3FFF) as usize
+    }
+
+    /// Marca 'flag' en el byte 'offset' de la ROM (fuera de rango se ignora).
+    pub fn mark(&mut self, offset: usize, flag: u8) {
+        if let Some(byte) = self.flags.get_mut(offset) {
+            *byte |= flag;
+        }
+    }
+
+    /// (bytes de código, bytes de datos, bytes sin tocar) de un rango de la ROM.
+    pub fn count(&self, range: std::ops::Range<usize>) -> (usize, usize, usize) {
+        let flags = self.flags.get(range).unwrap_or(&[]);
+        let code = flags.iter().filter(|&&f| f & CODE != 0).count();
+        let data = flags.iter().filter(|&&f| f & DATA != 0).count();
+        let unused = flags.iter().filter(|&&f| f == 0).count();
+        (code, data, unused)
+    }
+
+    /// Mismo resumen para el banco 'bank' de 16KB.
+    pub fn bank_count(&self, bank: usize) -> (usize, usize, usize) {
+        self.count(bank * 0x4000..(bank + 1) * 0x4000)
+    }
+}
+
+impl fmt::Display for Coverage {
+    /// Resumen de una línea por banco: `banco 01: 1234 código, 56 datos, 15094 sin tocar`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for bank in 0..self.flags.len().div_ceil(0x4000) {
+            let (code, data, unused) = self.bank_count(bank);
+            writeln!(f, "banco {:02X}: {} código, {} datos, {} sin tocar", bank, code, data, unused)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+// memory/src/hooks.rs
+
+//! Hooks de acceso al bus: callbacks que se ejecutan cuando la CPU lee o escribe
+//! en un rango de direcciones. Sobre esto se montan herramientas (cheats, scripts,
+//! logros...) sin tocar `Bus::read`/`Bus::write`.
+//!
+//! Solo se observan los accesos de la CPU (incluidos los fetch de instrucciones),
+//! no los `peek` de las herramientas ni el DMA. Sin hooks registrados el coste es
+//! comprobar que un Vec está vacío.
+
+use std::ops::RangeInclusive;
+
+/// Qué accesos dispara un hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// Lo que recibe el callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    pub write: bool,
+    pub addr: u16,
+    /// Valor leído o escrito.
+    pub value: u8,
+    /// M-Cycle del acceso (`Bus::cycles`).
+    pub cycle: u64,
+}
+
+/// Identificador para quitar un hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u32);
+
+// Box<dyn FnMut>: un closure cualquiera que puede modificar lo que captura
+// (como pasar un func en Go). Para compartir estado con quien lo registró,
+// el closure captura un Rc<RefCell<...>> o un Rc<Cell<...>>.
+type Callback = Box<dyn FnMut(&Access)>;
+
+struct Hook {
+    id: HookId,
+    range: RangeInclusive<u16>,
+    kind: HookKind,
+    callback: Callback,
+}
+
+#[derive(Default)]
+pub struct Hooks {
+    hooks: Vec<Hook>,
+    next_id: u32,
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra 'callback' para los accesos de tipo 'kind' en 'range'.
+    pub fn add(&mut self, range: RangeInclusive<u16>, kind: HookKind, callback: impl FnMut(&Access) + 'static) -> HookId {
+        let id = HookId(self.next_id);
+        self.next_id += 1;
+        self.hooks.push(Hook { id, range, kind, callback: Box::new(callback) });
+        id
+    }
+
+    /// Quita un hook. Devuelve false si ya no estaba.
+    pub fn remove(&mut self, id: HookId) -> bool {
+        let len = self.hooks.len();
+        self.hooks.retain(|h| h.id != id);
+        self.hooks.len() != len
+    }
+
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Llama a los hooks que coinciden con 'access', en orden de registro.
+    pub(crate) fn fire(&mut self, access: &Access) {
+        for hook in &mut self.hooks {
+            let kind_matches = match hook.kind {
+                HookKind::Read => !access.write,
+                HookKind::Write => access.write,
+                HookKind::ReadWrite => true,
+            };
+            if kind_matches && hook.range.contains(&access.addr) {
+                (hook.callback)(access);
+            }
+        }
+    }
+}
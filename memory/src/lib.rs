@@ -3,6 +3,17 @@
 use mbc::Mbc;
 use gpu::Gpu;
 use joypad::Joypad;
+use cpu::{Fetch, MemoryInterface};
+
+pub mod coverage;
+pub mod hooks;
+pub mod movie;
+pub mod rewind;
+pub mod state;
+use coverage::Coverage;
+use hooks::{Access, HookId, HookKind, Hooks};
+use std::ops::RangeInclusive;
+use savestate::{Reader, Snapshot, StateError, Writer};
 
 /// El Bus de Memoria es el "sistema nervioso" del Game Boy.
 /// Conecta la CPU con todos los periféricos mapeando direcciones de memoria (0x0000 - 0xFFFF).
@@ -48,6 +59,28 @@ pub struct Bus {
     pub tma: u8,    
     // TAC: Control del Timer (frecuencia start/stop).
     pub tac: u8,    
+
+    // --- PUERTO SERIE (Link Cable) ---
+    // SB (0xFF01): Byte a enviar / recibido.
+    pub serial_data: u8,
+    // SC (0xFF02): Control (Bit 7: Iniciar transferencia, Bit 0: Reloj interno).
+    pub serial_control: u8,
+    // Bytes transmitidos por el cable. No hay otra Game Boy al otro lado,
+    // pero los test ROMs (Blargg) imprimen aquí su resultado en texto.
+    pub serial_output: Vec<u8>,
+
+    // La GPU completó un frame durante algún M-Cycle (inicio de V-Blank).
+    // El frontend lo consulta y lo baja tras copiar el framebuffer.
+    pub frame_ready: bool,
+
+    // Mapa de cobertura de la ROM (código/datos). None = desactivado, sin coste.
+    pub coverage: Option<Coverage>,
+
+    // Callbacks sobre los accesos de la CPU (ver `add_hook`).
+    pub hooks: Hooks,
+
+    // M-Cycles transcurridos desde el encendido (lo reciben los hooks).
+    pub cycles: u64,
 }
 
 impl Bus {
@@ -69,7 +102,43 @@ impl Bus {
             tima: 0,
             tma: 0,
             tac: 0,
+
+            serial_data: 0,
+            serial_control: 0,
+            serial_output: Vec::new(),
+            frame_ready: false,
+            coverage: None,
+            hooks: Hooks::new(),
+            cycles: 0,
+        }
+    }
+
+    /// Avanza los periféricos (GPU y Timer) 'm_cycles' ciclos de máquina.
+    /// Retorna 'true' si la GPU acaba de completar un frame (inicio de V-Blank).
+    ///
+    /// La CPU lo invoca con 1 en cada lectura, escritura o ciclo interno
+    /// (ver `MemoryInterface`), así cada acceso cae en su M-Cycle exacto
+    /// respecto a la GPU y el Timer.
+    pub fn step(&mut self, m_cycles: u32) -> bool {
+        self.cycles += m_cycles as u64;
+
+        // La GPU cuenta en T-Cycles (Reloj del sistema): 1 M-Cycle = 4 T-Cycles.
+        let frame_ready = self.gpu.step(m_cycles * 4);
+
+        // El Timer cuenta M-Cycles internamente.
+        self.step_timer(m_cycles);
+
+        // Interrupción LCD STAT (Bit 1)
+        if self.gpu.request_stat_interrupt {
+            self.interrupt_flag |= 0x02;
+        }
+
+        // Interrupción V-Blank (Bit 0)
+        if frame_ready {
+            self.interrupt_flag |= 0x01;
         }
+
+        frame_ready
     }
     
     /// Avanza el Timer del sistema. Se llama en cada ciclo de la CPU.
@@ -112,6 +181,15 @@ impl Bus {
         }
     }
 
+    /// Lectura para herramientas: como 'read', pero el cartucho usa 'Mbc::peek'
+    /// para no disparar nada en el mapper.
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x7FFF | 0xA000..=0xBFFF => self.cartridge.peek(addr),
+            _ => self.read(addr),
+        }
+    }
+
     /// Lectura de memoria (La CPU pide un byte en 'addr').
     /// El 'match' en Rust es como un switch superpoderoso. Puede hacer matching de rangos (..=).
     pub fn read(&self, addr: u16) -> u8 {
@@ -141,6 +219,10 @@ impl Bus {
             // Joypad
             0xFF00 => self.joypad.read(),
             
+            // Puerto Serie
+            0xFF01 => self.serial_data,
+            0xFF02 => self.serial_control | 0x7E, // Bits 1-6 no usados (leen 1)
+
             // Timer Registers
             0xFF04 => (self.div >> 8) as u8, // Solo se lee el byte alto de DIV
             0xFF05 => self.tima,
@@ -182,6 +264,9 @@ impl Bus {
             0xFE00..=0xFE9F => self.gpu.oam[(addr - 0xFE00) as usize] = val,
             
             0xFF00 => self.joypad.write(val),
+
+            0xFF01 => self.serial_data = val,
+            0xFF02 => self.write_serial_control(val),
             
             // Timer Registers
             0xFF04 => self.div = 0, // Escribir cualquier valor resetea DIV a 0
@@ -203,6 +288,21 @@ impl Bus {
         }
     }
 
+    /// Escritura en SC (0xFF02).
+    /// Sin cable conectado, una transferencia con reloj interno termina "al instante":
+    /// el byte sale por 'serial_output', SB recibe 0xFF (línea en alto) y se pide
+    /// la interrupción Serial. No simulamos los 8 bits de retardo.
+    fn write_serial_control(&mut self, val: u8) {
+        if (val & 0x81) == 0x81 {
+            self.serial_output.push(self.serial_data);
+            self.serial_data = 0xFF;
+            self.serial_control = val & 0x7F; // Transferencia terminada (Bit 7 a 0)
+            self.interrupt_flag |= 0x08; // Bit 3: Serial Interrupt
+        } else {
+            self.serial_control = val;
+        }
+    }
+
     /// DMA Transfer (Direct Memory Access)
     /// Copia 160 bytes desde addr (xx00) a OAM (FE00).
     /// Es crítico para refrescar sprites rápidamente.
@@ -212,10 +312,41 @@ impl Bus {
             let addr = base_addr + i;
             // Usamos self.read() para manejar correctamente si la fuente es ROM, WRAM, etc.
             let byte = self.read(addr);
+            self.mark_coverage(addr, coverage::DATA);
             self.gpu.oam[i as usize] = byte;
         }
     }
 
+    /// Registra un callback para las lecturas y/o escrituras de la CPU en 'range'.
+    /// Recibe dirección, valor y M-Cycle. Devuelve un id para `remove_hook`.
+    pub fn add_hook(&mut self, range: RangeInclusive<u16>, kind: HookKind, callback: impl FnMut(&Access) + 'static) -> HookId {
+        self.hooks.add(range, kind, callback)
+    }
+
+    pub fn remove_hook(&mut self, id: HookId) -> bool {
+        self.hooks.remove(id)
+    }
+
+    /// Avisa a los hooks de un acceso de la CPU.
+    fn fire_hooks(&mut self, write: bool, addr: u16, value: u8) {
+        // Sin hooks, un solo if: no se construye nada.
+        if !self.hooks.is_empty() {
+            self.hooks.fire(&Access { write, addr, value, cycle: self.cycles });
+        }
+    }
+
+    /// Anota un acceso a ROM en el mapa de cobertura, con el banco mapeado ahora.
+    fn mark_coverage(&mut self, addr: u16, flag: u8) {
+        if let Some(coverage) = self.coverage.as_mut() {
+            let bank = match addr {
+                0x0000..=0x3FFF => self.cartridge.rom_bank0(),
+                0x4000..=0x7FFF => self.cartridge.rom_bank(),
+                _ => return, // Solo la ROM: la SRAM y la RAM interna no se etiquetan.
+            };
+            coverage.mark(Coverage::rom_offset(addr, bank), flag);
+        }
+    }
+
     // Helpers para registros GPU
     fn read_gpu_register(&self, addr: u16) -> u8 {
         match addr {
@@ -271,4 +402,94 @@ impl Bus {
             _ => {}
         }
     }
-}
\ No newline at end of file
+}
+
+/// El Bus es la memoria que ve la CPU real.
+/// Cada acceso de la CPU dura un M-Cycle: primero avanza el resto del sistema
+/// y luego se realiza el acceso. Así una lectura de LY o TIMA a mitad de
+/// instrucción ve el valor de ese ciclo, no el del final de la instrucción.
+/// Delegamos en los métodos propios de `Bus` (los inherentes tienen prioridad sobre los del trait).
+impl MemoryInterface for Bus {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.tick();
+        self.mark_coverage(addr, coverage::DATA);
+        let value = Bus::read(self, addr);
+        self.fire_hooks(false, addr, value);
+        value
+    }
+
+    fn fetch(&mut self, addr: u16, kind: Fetch) -> u8 {
+        self.tick();
+        let flag = match kind {
+            Fetch::Opcode => coverage::CODE | coverage::OPCODE,
+            Fetch::Operand => coverage::CODE,
+        };
+        self.mark_coverage(addr, flag);
+        let value = Bus::read(self, addr);
+        self.fire_hooks(false, addr, value);
+        value
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.tick();
+        Bus::write(self, addr, val);
+        self.fire_hooks(true, addr, val);
+    }
+
+    fn peek(&self, addr: u16) -> u8 { Bus::peek(self, addr) }
+
+    fn tick(&mut self) {
+        if self.step(1) {
+            self.frame_ready = true;
+        }
+    }
+
+    fn pending_interrupts(&self) -> u8 { self.interrupt_flag & self.interrupt_enable & 0x1F }
+    fn clear_interrupt(&mut self, mask: u8) { self.interrupt_flag &= !mask; }
+
+    // Bits 0-3 de P1 en 0 = botón presionado en una fila seleccionada.
+    fn joypad_line_low(&self) -> bool { (self.joypad.read() & 0x0F) != 0x0F }
+
+    // STOP detiene el oscilador: DIV vuelve a 0. Somos una DMG, no hay KEY1
+    // (cambio de doble velocidad de CGB) que aplicar.
+    fn stop(&mut self) { self.div = 0; }
+
+    fn rom_bank(&self) -> u16 { self.cartridge.rom_bank() }
+    fn ram_bank(&self) -> u16 { self.cartridge.ram_bank() as u16 }
+}
+
+/// Save state del Bus y de todo lo que cuelga de él (GPU, Joypad, cartucho).
+/// Las herramientas (cobertura, hooks) y 'serial_output' no son estado de la máquina.
+impl Snapshot for Bus {
+    fn save_state(&self, w: &mut Writer) {
+        w.bytes(&self.wram);
+        w.bytes(&self.hram);
+        w.u8(self.interrupt_enable);
+        w.u8(self.interrupt_flag);
+        w.u16(self.div);
+        for v in [self.tima, self.tma, self.tac, self.serial_data, self.serial_control] {
+            w.u8(v);
+        }
+        w.u64(self.cycles);
+        self.gpu.save_state(w);
+        self.joypad.save_state(w);
+        self.cartridge.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut Reader) -> Result<(), StateError> {
+        r.bytes(&mut self.wram)?;
+        r.bytes(&mut self.hram)?;
+        self.interrupt_enable = r.u8()?;
+        self.interrupt_flag = r.u8()?;
+        self.div = r.u16()?;
+        for v in [&mut self.tima, &mut self.tma, &mut self.tac, &mut self.serial_data, &mut self.serial_control] {
+            *v = r.u8()?;
+        }
+        self.cycles = r.u64()?;
+        self.gpu.load_state(r)?;
+        self.joypad.load_state(r)?;
+        self.cartridge.load_state(r)?;
+        self.frame_ready = false;
+        Ok(())
+    }
+}
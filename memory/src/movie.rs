@@ -0,0 +1,309 @@
+// memory/src/movie.rs
+
+//! Películas de input: el estado del Joypad en cada frame, desde el encendido o desde
+//! un save state incrustado. Reproducirla da exactamente la misma partida.
+//!
+//! Para que sea exacta, los frames no los marca el frontend sino los ciclos:
+//! cada `FRAME_CYCLES` M-Cycles (los que devuelve `Cpu::step`, incluido STOP) empieza
+//! un frame, y solo en ese instante cambia el Joypad. Al grabar, las teclas del usuario
+//! se acumulan con `set_button` y se aplican en el siguiente límite de frame.
+//!
+//! Cada `sync_interval` frames se guarda un CRC32 de la máquina completa; al reproducir
+//! se compara y la primera diferencia queda en `desync`.
+//!
+//! Formato (little-endian, con las primitivas del crate `savestate`):
+//!
+//! ```text
+//! "GBMOVIE\0" u32 versión, u32 CRC32 de la ROM, u32 rerecords, u32 sync_interval,
+//! u8 inicio (0 = encendido, 1 = save state) [blob save state],
+//! blob inputs (un byte por frame: bit 0 Right, 1 Left, 2 Up, 3 Down, 4 A, 5 B, 6 Select, 7 Start),
+//! u32 n, n x (u64 frame, u32 CRC32)
+//! ```
+
+use cpu::Cpu;
+use joypad::Button;
+use savestate::{crc32, Reader, StateError, Writer};
+
+use crate::state;
+use crate::Bus;
+
+/// M-Cycles por frame (70224 T-Cycles / 4).
+pub const FRAME_CYCLES: u32 = 17556;
+
+const MAGIC: &[u8; 8] = b"GBMOVIE\0";
+const VERSION: u32 = 1;
+const CHECKPOINT_MAGIC: &[u8; 8] = b"GBMOVCP\0";
+
+/// Orden de los bits en cada byte de input.
+const BUTTONS: [Button; 8] = [
+    Button::Right, Button::Left, Button::Up, Button::Down,
+    Button::A, Button::B, Button::Select, Button::Start,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Recording,
+    Playback,
+    /// La reproducción llegó al último frame grabado.
+    Finished,
+}
+
+pub struct Movie {
+    pub rom_crc32: u32,
+    /// Veces que se volvió a grabar desde un checkpoint (estadística habitual en TAS).
+    pub rerecords: u32,
+    /// Frames entre hashes de sincronización.
+    pub sync_interval: u32,
+    /// Save state de inicio (None = desde el encendido).
+    pub start_state: Option<Vec<u8>>,
+    pub mode: Mode,
+    /// Primer frame en el que la reproducción no coincidió con la grabación.
+    pub desync: Option<u64>,
+    inputs: Vec<u8>,
+    hashes: Vec<(u64, u32)>,
+    // Frame actual y M-Cycles transcurridos dentro de él.
+    frame: u64,
+    cycles: u32,
+    // Botones que quiere el usuario (al grabar) y los aplicados al Joypad.
+    wanted: u8,
+    applied: u8,
+}
+
+impl Movie {
+    /// Empieza a grabar. Con 'from_state' la película incrusta el estado actual;
+    /// sin él, la máquina tiene que estar recién encendida.
+    pub fn record(cpu: &Cpu, bus: &mut Bus, from_state: bool, sync_interval: u32) -> Self {
+        let applied = joypad_buttons(bus);
+        let mut movie = Movie {
+            rom_crc32: crc32(bus.cartridge.rom()),
+            rerecords: 0,
+            sync_interval: sync_interval.max(1),
+            start_state: from_state.then(|| state::save(cpu, bus)),
+            mode: Mode::Recording,
+            desync: None,
+            inputs: Vec::new(),
+            hashes: Vec::new(),
+            frame: 0,
+            cycles: 0,
+            wanted: applied,
+            applied,
+        };
+        movie.begin_frame(cpu, bus);
+        movie
+    }
+
+    /// Prepara la máquina para reproducir: carga el estado incrustado si lo hay.
+    /// Si es desde el encendido, la máquina tiene que estar recién creada
+    /// (el hash del frame 0 lo comprueba).
+    pub fn play(&mut self, cpu: &mut Cpu, bus: &mut Bus) -> Result<(), StateError> {
+        let rom_crc32 = crc32(bus.cartridge.rom());
+        if self.rom_crc32 != rom_crc32 {
+            return Err(StateError::RomMismatch { expected: rom_crc32, found: self.rom_crc32 });
+        }
+        if let Some(data) = &self.start_state {
+            state::load(cpu, bus, data)?;
+        }
+        self.mode = Mode::Playback;
+        self.desync = None;
+        self.frame = 0;
+        self.cycles = 0;
+        self.applied = joypad_buttons(bus);
+        self.begin_frame(cpu, bus);
+        Ok(())
+    }
+
+    /// Frame actual (número de límites de frame pasados).
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Frames grabados.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Input del usuario mientras se graba (se aplica en el próximo frame).
+    /// Al reproducir se ignora.
+    pub fn set_button(&mut self, btn: Button, pressed: bool) {
+        let bit = 1 << BUTTONS.iter().position(|&b| b == btn).unwrap();
+        match pressed {
+            true => self.wanted |= bit,
+            false => self.wanted &= !bit,
+        }
+    }
+
+    /// Avisa de los M-Cycles de un `Cpu::step`. Hay que llamarlo después de cada uno.
+    pub fn step(&mut self, cycles: u32, cpu: &Cpu, bus: &mut Bus) {
+        self.cycles += cycles;
+        while self.cycles >= FRAME_CYCLES {
+            self.cycles -= FRAME_CYCLES;
+            self.frame += 1;
+            self.begin_frame(cpu, bus);
+        }
+    }
+
+    /// Límite de frame: primero el hash (estado antes del input), después el input.
+    fn begin_frame(&mut self, cpu: &Cpu, bus: &mut Bus) {
+        if self.frame.is_multiple_of(self.sync_interval as u64) {
+            let hash = crc32(&state::snapshot(cpu, bus));
+            match self.mode {
+                Mode::Recording => self.hashes.push((self.frame, hash)),
+                Mode::Playback => {
+                    let expected = self.hashes.iter().find(|&&(frame, _)| frame == self.frame);
+                    if let Some(&(_, expected)) = expected && expected != hash && self.desync.is_none() {
+                        self.desync = Some(self.frame);
+                    }
+                }
+                Mode::Finished => {}
+            }
+        }
+
+        let buttons = match self.mode {
+            Mode::Recording => {
+                self.inputs.push(self.wanted);
+                self.wanted
+            }
+            Mode::Playback => match self.inputs.get(self.frame as usize) {
+                Some(&buttons) => buttons,
+                None => {
+                    self.mode = Mode::Finished;
+                    return;
+                }
+            },
+            Mode::Finished => return,
+        };
+        self.apply(bus, buttons);
+    }
+
+    /// Lleva el Joypad a 'buttons' con key_down/key_up solo de lo que cambió.
+    fn apply(&mut self, bus: &mut Bus, buttons: u8) {
+        for (i, &btn) in BUTTONS.iter().enumerate() {
+            let bit = 1 << i;
+            match (self.applied & bit != 0, buttons & bit != 0) {
+                (false, true) => bus.joypad.key_down(btn),
+                (true, false) => bus.joypad.key_up(btn),
+                _ => {}
+            }
+        }
+        self.applied = buttons;
+    }
+
+    // ---------------------------------------------------------
+    // Rerecording
+    // ---------------------------------------------------------
+
+    /// Save state con la posición en la película (frame y ciclos dentro del frame).
+    pub fn checkpoint(&self, cpu: &Cpu, bus: &Bus) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.bytes(CHECKPOINT_MAGIC);
+        w.u64(self.frame);
+        w.u32(self.cycles);
+        w.blob(&state::save(cpu, bus));
+        w.buf
+    }
+
+    /// Vuelve a un checkpoint y sigue grabando desde ahí: se descarta el input posterior.
+    pub fn rerecord(&mut self, cpu: &mut Cpu, bus: &mut Bus, checkpoint: &[u8]) -> Result<(), StateError> {
+        if !checkpoint.starts_with(CHECKPOINT_MAGIC) {
+            return Err(StateError::Invalid("no es un checkpoint de película".to_string()));
+        }
+        let mut r = Reader::new(&checkpoint[CHECKPOINT_MAGIC.len()..], savestate::VERSION);
+        let frame = r.u64()?;
+        let cycles = r.u32()?;
+        if frame as usize >= self.inputs.len() {
+            return Err(StateError::Invalid(format!("frame {} fuera de la película ({} frames)", frame, self.inputs.len())));
+        }
+        state::load(cpu, bus, r.blob()?)?;
+
+        self.inputs.truncate(frame as usize + 1);
+        self.hashes.retain(|&(f, _)| f <= frame);
+        self.frame = frame;
+        self.cycles = cycles;
+        self.applied = joypad_buttons(bus);
+        self.wanted = self.applied;
+        self.mode = Mode::Recording;
+        self.desync = None;
+        self.rerecords += 1;
+        Ok(())
+    }
+
+    // ---------------------------------------------------------
+    // Archivo
+    // ---------------------------------------------------------
+
+    pub fn save(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.bytes(MAGIC);
+        w.u32(VERSION);
+        w.u32(self.rom_crc32);
+        w.u32(self.rerecords);
+        w.u32(self.sync_interval);
+        match &self.start_state {
+            Some(data) => {
+                w.u8(1);
+                w.blob(data);
+            }
+            None => w.u8(0),
+        }
+        w.blob(&self.inputs);
+        w.u32(self.hashes.len() as u32);
+        for &(frame, hash) in &self.hashes {
+            w.u64(frame);
+            w.u32(hash);
+        }
+        w.buf
+    }
+
+    /// Lee una película. Queda lista para `play`.
+    pub fn load(data: &[u8]) -> Result<Self, StateError> {
+        if !data.starts_with(MAGIC) {
+            return Err(StateError::Invalid("no es una película".to_string()));
+        }
+        let mut r = Reader::new(&data[MAGIC.len()..], 0);
+        r.version = r.u32()?;
+        if r.version != VERSION {
+            return Err(StateError::UnsupportedVersion(r.version));
+        }
+        let rom_crc32 = r.u32()?;
+        let rerecords = r.u32()?;
+        let sync_interval = r.u32()?.max(1);
+        let start_state = match r.u8()? {
+            0 => None,
+            1 => Some(r.blob()?.to_vec()),
+            other => return Err(StateError::Invalid(format!("inicio desconocido {}", other))),
+        };
+        let inputs = r.blob()?.to_vec();
+        let count = r.u32()?;
+        let mut hashes = Vec::new();
+        for _ in 0..count {
+            hashes.push((r.u64()?, r.u32()?));
+        }
+        Ok(Movie {
+            rom_crc32,
+            rerecords,
+            sync_interval,
+            start_state,
+            mode: Mode::Playback,
+            desync: None,
+            inputs,
+            hashes,
+            frame: 0,
+            cycles: 0,
+            wanted: 0,
+            applied: 0,
+        })
+    }
+}
+
+/// Botones presionados ahora mismo en el Joypad, como byte de input.
+fn joypad_buttons(bus: &Bus) -> u8 {
+    BUTTONS
+        .iter()
+        .enumerate()
+        .filter(|&(_, &btn)| bus.joypad.is_pressed(btn))
+        .fold(0, |bits, (i, _)| bits | 1 << i)
+}
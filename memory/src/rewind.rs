@@ -0,0 +1,184 @@
+// memory/src/rewind.rs
+
+//! Rewind: snapshots periódicos de la máquina para poder volver atrás en el tiempo.
+//!
+//! Cada N frames se toma un `state::snapshot`. Solo el más reciente se guarda entero;
+//! los anteriores se guardan como diferencia con el siguiente (XOR byte a byte), que es
+//! casi todo ceros porque entre dos snapshots cambian pocos bytes (WRAM, registros,
+//! parte de la VRAM...). Esa diferencia se comprime con RLE de ceros.
+//!
+//! Volver atrás: se carga el snapshot más reciente y se reconstruye el anterior
+//! haciendo XOR con su diferencia. Al llenarse, se descarta la diferencia más antigua
+//! sin tocar las demás (no hay cadena que reconstruir hacia delante).
+
+use std::collections::VecDeque;
+
+use cpu::Cpu;
+
+use crate::state;
+use crate::Bus;
+
+/// Frames por segundo de la Game Boy (59.73 Hz, redondeado).
+const FRAMES_PER_SECOND: u32 = 60;
+
+/// Hasta dónde se puede volver atrás.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Depth {
+    Seconds(u32),
+    Megabytes(usize),
+}
+
+pub struct Rewind {
+    /// Frames entre snapshots (1 = cada frame).
+    pub interval: u32,
+    pub depth: Depth,
+    frames: u32,
+    // Snapshot más reciente, sin comprimir.
+    latest: Option<Vec<u8>>,
+    // Diferencias comprimidas: deltas[i] reconstruye el snapshot anterior al de deltas[i + 1].
+    deltas: VecDeque<Vec<u8>>,
+    delta_bytes: usize,
+}
+
+impl Rewind {
+    pub fn new(interval: u32, depth: Depth) -> Self {
+        Rewind { interval: interval.max(1), depth, frames: 0, latest: None, deltas: VecDeque::new(), delta_bytes: 0 }
+    }
+
+    /// Snapshots disponibles para volver atrás.
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.latest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Bytes ocupados (snapshot actual + diferencias comprimidas).
+    pub fn memory_usage(&self) -> usize {
+        self.latest.as_ref().map_or(0, Vec::len) + self.delta_bytes
+    }
+
+    /// Avisa de que terminó un frame: cada 'interval' frames se toma un snapshot.
+    pub fn end_frame(&mut self, cpu: &Cpu, bus: &Bus) {
+        self.frames += 1;
+        if self.frames >= self.interval {
+            self.frames = 0;
+            self.push(state::snapshot(cpu, bus));
+        }
+    }
+
+    fn push(&mut self, snapshot: Vec<u8>) {
+        if let Some(previous) = self.latest.replace(snapshot) {
+            let delta = encode(&xor(&previous, self.latest.as_ref().unwrap()), previous.len());
+            self.delta_bytes += delta.len();
+            self.deltas.push_back(delta);
+        }
+        // Descartar lo más antiguo hasta volver al límite.
+        while self.over_limit() {
+            match self.deltas.pop_front() {
+                Some(delta) => self.delta_bytes -= delta.len(),
+                None => break,
+            }
+        }
+    }
+
+    fn over_limit(&self) -> bool {
+        match self.depth {
+            Depth::Seconds(seconds) => {
+                // saturating_mul: '--rewind-seconds' enorme es "sin límite", no un overflow.
+                let max = seconds.saturating_mul(FRAMES_PER_SECOND).div_ceil(self.interval) as usize;
+                self.len() > max.max(1)
+            }
+            Depth::Megabytes(mb) => self.memory_usage() > mb.saturating_mul(1024 * 1024),
+        }
+    }
+
+    /// Vuelve al snapshot más reciente y lo descarta. Llamado una vez por frame mientras
+    /// se mantiene la tecla, la partida se reproduce hacia atrás ('interval' frames por paso).
+    /// Devuelve false si ya no queda historia.
+    pub fn rewind(&mut self, cpu: &mut Cpu, bus: &mut Bus) -> bool {
+        let Some(latest) = self.latest.take() else { return false };
+        // Un snapshot de esta sesión siempre se puede restaurar.
+        state::restore(cpu, bus, &latest).expect("snapshot de rewind corrupto");
+        if let Some(delta) = self.deltas.pop_back() {
+            self.delta_bytes -= delta.len();
+            let (diff, len) = decode(&delta);
+            let mut previous = xor(&latest, &diff);
+            previous.truncate(len);
+            self.latest = Some(previous);
+        }
+        self.frames = 0;
+        true
+    }
+}
+
+/// XOR byte a byte; si las longitudes difieren, lo que sobra se copia tal cual.
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let len = a.len().max(b.len());
+    (0..len).map(|i| a.get(i).copied().unwrap_or(0) ^ b.get(i).copied().unwrap_or(0)).collect()
+}
+
+// =========================================================================
+//  RLE DE CEROS
+//  Formato: longitud original, y luego pares (ceros, literales) hasta el final:
+//  [varint len] ([varint ceros] [varint n] [n bytes])*
+// =========================================================================
+
+fn write_varint(out: &mut Vec<u8>, mut v: usize) {
+    // 7 bits por byte, el bit alto indica "sigue otro byte" (como encoding/binary.PutUvarint en Go).
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> usize {
+    let mut v = 0;
+    let mut shift = 0;
+    while let Some(&byte) = data.get(*pos) {
+        *pos += 1;
+        v |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    v
+}
+
+/// Comprime 'diff' y recuerda 'len', la longitud del snapshot que reconstruye.
+fn encode(diff: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, len);
+    let mut i = 0;
+    while i < diff.len() {
+        let zeros = diff[i..].iter().take_while(|&&b| b == 0).count();
+        i += zeros;
+        // Literales hasta el próximo tramo de al menos 4 ceros (los huecos cortos no compensan).
+        let start = i;
+        while i < diff.len() && !diff[i..].starts_with(&[0, 0, 0, 0]) {
+            i += 1;
+        }
+        write_varint(&mut out, zeros);
+        write_varint(&mut out, i - start);
+        out.extend_from_slice(&diff[start..i]);
+    }
+    out
+}
+
+/// Descomprime: devuelve la diferencia y la longitud original.
+fn decode(data: &[u8]) -> (Vec<u8>, usize) {
+    let mut pos = 0;
+    let len = read_varint(data, &mut pos);
+    let mut out = Vec::with_capacity(len);
+    while pos < data.len() {
+        let zeros = read_varint(data, &mut pos);
+        out.resize(out.len() + zeros, 0);
+        let literals = read_varint(data, &mut pos);
+        out.extend_from_slice(&data[pos..pos + literals]);
+        pos += literals;
+    }
+    (out, len)
+}
@@ -0,0 +1,90 @@
+// memory/src/state.rs
+
+//! Save states de la máquina completa (CPU + Bus).
+//!
+//! El formato y la cabecera están en el crate `savestate`; aquí se decide qué se
+//! guarda y en qué orden: cabecera, CPU y luego el Bus (que incluye GPU, Joypad y cartucho).
+
+use cpu::Cpu;
+use gpu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use savestate::{crc32, Header, Reader, Snapshot, StateError, Writer, VERSION};
+
+use crate::Bus;
+
+/// Miniatura a mitad de resolución: 80x72.
+const THUMBNAIL_WIDTH: usize = SCREEN_WIDTH / 2;
+const THUMBNAIL_HEIGHT: usize = SCREEN_HEIGHT / 2;
+
+/// Serializa la máquina completa.
+pub fn save(cpu: &Cpu, bus: &Bus) -> Vec<u8> {
+    let mut w = Writer::new();
+    Header {
+        version: VERSION,
+        emulator: env!("CARGO_PKG_VERSION").to_string(),
+        rom_crc32: crc32(bus.cartridge.rom()),
+        thumbnail_width: THUMBNAIL_WIDTH as u16,
+        thumbnail_height: THUMBNAIL_HEIGHT as u16,
+        thumbnail: thumbnail(&bus.gpu.frame_buffer),
+    }
+    .write(&mut w);
+    cpu.save_state(&mut w);
+    bus.save_state(&mut w);
+    w.buf
+}
+
+/// Solo la cabecera (para mostrar slots sin cargarlos).
+pub fn read_header(data: &[u8]) -> Result<Header, StateError> {
+    Header::read(data).map(|(header, _)| header)
+}
+
+/// Restaura la máquina. Si el estado es de otra ROM, de una versión no soportada
+/// o está corrupto, devuelve el error y la máquina queda exactamente como estaba.
+pub fn load(cpu: &mut Cpu, bus: &mut Bus, data: &[u8]) -> Result<Header, StateError> {
+    let (header, mut r) = Header::read(data)?;
+    let rom_crc32 = crc32(bus.cartridge.rom());
+    if header.rom_crc32 != rom_crc32 {
+        return Err(StateError::RomMismatch { expected: rom_crc32, found: header.rom_crc32 });
+    }
+
+    // Un error a mitad del cuerpo dejaría la máquina a medias: guardamos el estado
+    // actual antes y lo restauramos si algo falla (ese sí se puede leer siempre).
+    let backup = save(cpu, bus);
+    let result = cpu.load_state(&mut r).and_then(|_| bus.load_state(&mut r)).and_then(|_| match r.remaining() {
+        0 => Ok(()),
+        extra => Err(StateError::Invalid(format!("{} bytes de más al final", extra))),
+    });
+    if let Err(e) = result {
+        let (_, mut r) = Header::read(&backup).expect("el backup se acaba de escribir");
+        cpu.load_state(&mut r).and_then(|_| bus.load_state(&mut r)).expect("el backup se acaba de escribir");
+        return Err(e);
+    }
+    Ok(header)
+}
+
+/// Solo el cuerpo, sin cabecera (ni CRC32 de la ROM ni miniatura): para snapshots
+/// en memoria como los del rewind, que nunca salen de esta sesión.
+pub fn snapshot(cpu: &Cpu, bus: &Bus) -> Vec<u8> {
+    let mut w = Writer::new();
+    cpu.save_state(&mut w);
+    bus.save_state(&mut w);
+    w.buf
+}
+
+/// Restaura un `snapshot`. Es de esta misma sesión: versión actual y misma ROM.
+pub fn restore(cpu: &mut Cpu, bus: &mut Bus, data: &[u8]) -> Result<(), StateError> {
+    let mut r = Reader::new(data, VERSION);
+    cpu.load_state(&mut r)?;
+    bus.load_state(&mut r)
+}
+
+/// Reduce el framebuffer RGBA a la mitad, tomando un píxel de cada bloque de 2x2, en RGB.
+fn thumbnail(frame_buffer: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 3);
+    for y in 0..THUMBNAIL_HEIGHT {
+        for x in 0..THUMBNAIL_WIDTH {
+            let offset = ((y * 2) * SCREEN_WIDTH + x * 2) * 4;
+            out.extend_from_slice(&frame_buffer[offset..offset + 3]);
+        }
+    }
+    out
+}
@@ -0,0 +1,62 @@
+// memory/tests/common/mod.rs
+
+//! Utilidades compartidas por los tests de integración del Bus: máquinas con un
+//! programa en el punto de entrada y bucles para ejecutarlas.
+
+// Cada archivo de tests usa solo algunas.
+#![allow(dead_code)]
+
+use cpu::Cpu;
+use mbc::{Mbc, Mbc1, RomOnly};
+use memory::Bus;
+use memory::movie::FRAME_CYCLES;
+
+/// ROM de 'size' bytes (en ceros) con 'code' en 0x0100.
+pub fn rom_with_code(size: usize, code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0; size];
+    rom[0x0100..0x0100 + code.len()].copy_from_slice(code);
+    rom
+}
+
+/// CPU en el punto de entrada (0x0100) y Bus con el cartucho dado.
+pub fn boot(cartridge: Box<dyn Mbc>) -> (Cpu, Bus) {
+    let mut cpu = Cpu::new();
+    cpu.regs.pc = 0x0100;
+    (cpu, Bus::new(cartridge))
+}
+
+/// ROM ONLY de 32KB que tiene 'code' en 0x0100.
+pub fn rom_only(code: &[u8]) -> (Cpu, Bus) {
+    boot(Box::new(RomOnly { rom: rom_with_code(0x8000, code) }))
+}
+
+/// MBC1 de 4 bancos (64KB) que tiene 'code' en 0x0100.
+pub fn mbc1(code: &[u8]) -> (Cpu, Bus) {
+    boot(Box::new(Mbc1::new(rom_with_code(0x10000, code))))
+}
+
+/// Ejecuta instrucciones hasta que 'done' lo diga. Recibe los M-Cycles de cada una.
+pub fn run_until(cpu: &mut Cpu, bus: &mut Bus, mut done: impl FnMut(&mut Cpu, &mut Bus, u32) -> bool) {
+    loop {
+        let cycles = cpu.step(bus);
+        if done(cpu, bus, cycles) {
+            break;
+        }
+    }
+}
+
+/// Ejecuta 'steps' instrucciones.
+pub fn run(cpu: &mut Cpu, bus: &mut Bus, steps: usize) {
+    for _ in 0..steps {
+        cpu.step(bus);
+    }
+}
+
+/// Emula un frame (al menos 17556 M-Cycles).
+pub fn frame(cpu: &mut Cpu, bus: &mut Bus) {
+    let mut total = 0;
+    run_until(cpu, bus, |_, _, cycles| {
+        total += cycles;
+        total >= FRAME_CYCLES
+    });
+}
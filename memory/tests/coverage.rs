@@ -0,0 +1,56 @@
+// memory/tests/coverage.rs
+
+//! Mapa de cobertura: opcodes, operandos y datos por banco, y acumulación entre sesiones.
+
+use cpu::Cpu;
+use memory::Bus;
+use memory::coverage::{Coverage, CODE, DATA, OPCODE};
+
+mod common;
+
+/// MBC1 de 4 bancos: en 0x0100 cambia al banco 2 y lee un dato de 0x4000.
+fn setup() -> (Cpu, Bus) {
+    let (cpu, mut bus) = common::mbc1(&[
+        0x3E, 0x02,       // ld a, 2
+        0xEA, 0x00, 0x20, // ld [$2000], a
+        0xFA, 0x00, 0x40, // ld a, [$4000]
+    ]);
+    bus.coverage = Some(Coverage::new(0x10000));
+    (cpu, bus)
+}
+
+#[test]
+fn separates_opcodes_operands_and_banked_data() {
+    let (mut cpu, mut bus) = setup();
+    for _ in 0..3 {
+        cpu.step(&mut bus);
+    }
+    let flags = &bus.coverage.as_ref().unwrap().flags;
+    assert_eq!(flags[0x0100], CODE | OPCODE);
+    assert_eq!(flags[0x0101], CODE);
+    assert_eq!(&flags[0x0102..0x0108], &[CODE | OPCODE, CODE, CODE, CODE | OPCODE, CODE, CODE]);
+    // La lectura de 0x4000 cae en el banco 2 (offset 0x8000 del archivo), no en el 1.
+    assert_eq!(flags[0x8000], DATA);
+    assert_eq!(flags[0x4000], 0);
+    assert_eq!(bus.coverage.as_ref().unwrap().bank_count(2), (0, 1, 0x3FFF));
+}
+
+#[test]
+fn accumulates_across_sessions() {
+    let path = std::env::temp_dir().join(format!("coverage-{}.cdl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut first = Coverage::open(&path, 0x10000).unwrap();
+    first.mark(0x0150, CODE);
+    first.save().unwrap();
+
+    let mut second = Coverage::open(&path, 0x10000).unwrap();
+    assert_eq!(second.flags[0x0150], CODE);
+    second.mark(0x0150, DATA);
+    second.save().unwrap();
+    assert_eq!(Coverage::open(&path, 0x10000).unwrap().flags[0x0150], CODE | DATA);
+
+    // Otra ROM (otro tamaño): no se mezcla.
+    assert!(Coverage::open(&path, 0x8000).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
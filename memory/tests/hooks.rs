@@ -0,0 +1,54 @@
+// memory/tests/hooks.rs
+
+//! Hooks de acceso: rangos, tipo de acceso, ciclo y baja de un hook.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cpu::Cpu;
+use memory::Bus;
+use memory::hooks::{Access, HookKind};
+
+mod common;
+
+/// ld a, $42 ; ld [$C000], a ; ld a, [$C000] ; ld [$C100], a
+fn setup() -> (Cpu, Bus) {
+    common::rom_only(&[0x3E, 0x42, 0xEA, 0x00, 0xC0, 0xFA, 0x00, 0xC0, 0xEA, 0x00, 0xC1])
+}
+
+#[test]
+fn hooks_see_matching_accesses_with_cycle() {
+    let (mut cpu, mut bus) = setup();
+    // Rc<RefCell>: el closure y el test comparten el Vec (como un puntero compartido en Go).
+    let writes = Rc::new(RefCell::new(Vec::new()));
+    let all = Rc::new(RefCell::new(Vec::new()));
+    let log = writes.clone();
+    bus.add_hook(0xC000..=0xC0FF, HookKind::Write, move |a: &Access| log.borrow_mut().push(*a));
+    let log = all.clone();
+    bus.add_hook(0xC000..=0xC0FF, HookKind::ReadWrite, move |a: &Access| log.borrow_mut().push(a.write));
+
+    for _ in 0..4 {
+        cpu.step(&mut bus);
+    }
+    // Solo la escritura a $C000: la de $C100 queda fuera del rango.
+    // ld a,n (2 ciclos) + 4 ciclos de ld [nn],a: la escritura es el 6º M-Cycle.
+    assert_eq!(*writes.borrow(), vec![Access { write: true, addr: 0xC000, value: 0x42, cycle: 6 }]);
+    assert_eq!(*all.borrow(), vec![true, false]);
+}
+
+#[test]
+fn removed_hooks_stop_firing() {
+    let (mut cpu, mut bus) = setup();
+    let count = Rc::new(RefCell::new(0));
+    let counter = count.clone();
+    // Los fetch de instrucciones también son lecturas.
+    let id = bus.add_hook(0x0100..=0x7FFF, HookKind::Read, move |_: &Access| *counter.borrow_mut() += 1);
+    cpu.step(&mut bus);
+    assert_eq!(*count.borrow(), 2);
+
+    assert!(bus.remove_hook(id));
+    assert!(!bus.remove_hook(id));
+    assert!(bus.hooks.is_empty());
+    cpu.step(&mut bus);
+    assert_eq!(*count.borrow(), 2);
+}
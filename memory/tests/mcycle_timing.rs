@@ -0,0 +1,28 @@
+// memory/tests/mcycle_timing.rs
+
+//! El Bus avanza Timer y GPU en cada M-Cycle de la CPU (lectura, escritura o ciclo interno).
+
+mod common;
+use common::rom_only;
+
+#[test]
+fn instruction_advances_timer_by_its_own_cycles() {
+    let (mut cpu, mut bus) = rom_only(&[0xCD, 0x00, 0x02]); // CALL $0200 (6 M-Cycles)
+    bus.div = 0;
+
+    let m_cycles = cpu.step(&mut bus);
+
+    assert_eq!(m_cycles, 6);
+    assert_eq!(bus.div, 6 * 4); // DIV cuenta T-Cycles
+}
+
+#[test]
+fn read_sees_the_timer_of_its_own_m_cycle() {
+    // LD A,(FF04): fetch opcode, fetch 2 bytes de dirección y la lectura de DIV en el 4º ciclo.
+    let (mut cpu, mut bus) = rom_only(&[0xFA, 0x04, 0xFF]);
+    bus.div = 0x00F0; // DIV (byte alto) pasa de 0x00 a 0x01 tras 4 M-Cycles
+
+    cpu.step(&mut bus);
+
+    assert_eq!(cpu.regs.a, 0x01);
+}
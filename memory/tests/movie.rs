@@ -0,0 +1,140 @@
+// memory/tests/movie.rs
+
+//! Películas de input: reproducción exacta, detección de desync y rerecording.
+
+use cpu::Cpu;
+use joypad::Button;
+use memory::Bus;
+use memory::movie::{Mode, Movie};
+use memory::state;
+
+mod common;
+
+/// Bucle que lee la cruceta y la va escribiendo en WRAM: el input cambia la memoria.
+fn machine() -> (Cpu, Bus) {
+    common::mbc1(&[
+        0x21, 0x00, 0xC0, // ld hl, $C000
+        0x3E, 0x20,       // .loop: ld a, $20   (seleccionar la cruceta)
+        0xE0, 0x00,       // ldh [$00], a
+        0xF0, 0x00,       // ldh a, [$00]
+        0x22,             // ld [hl+], a
+        0xCB, 0xAC,       // res 5, h   (al llegar a $E000 vuelve a $C000)
+        0x18, 0xF5,       // jr .loop
+    ])
+}
+
+/// Emula un frame de la película (hasta el siguiente límite de frame).
+fn frame(movie: &mut Movie, cpu: &mut Cpu, bus: &mut Bus) {
+    let target = movie.frame() + 1;
+    common::run_until(cpu, bus, |cpu, bus, cycles| {
+        movie.step(cycles, cpu, bus);
+        movie.frame() >= target || movie.mode == Mode::Finished
+    });
+}
+
+/// Graba 'frames' frames pulsando Right en los frames pares y Down cada 3.
+fn record(frames: u64) -> (Movie, Cpu, Bus) {
+    let (mut cpu, mut bus) = machine();
+    let mut movie = Movie::record(&cpu, &mut bus, false, 4);
+    for i in 0..frames {
+        movie.set_button(Button::Right, i % 2 == 0);
+        movie.set_button(Button::Down, i % 3 == 0);
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    (movie, cpu, bus)
+}
+
+#[test]
+fn playback_reproduces_recording_exactly() {
+    let (movie, cpu, bus) = record(30);
+    let expected = state::snapshot(&cpu, &bus);
+
+    let mut movie = Movie::load(&movie.save()).unwrap();
+    let (mut cpu, mut bus) = machine();
+    movie.play(&mut cpu, &mut bus).unwrap();
+    for _ in 0..30 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    assert_eq!(movie.desync, None);
+    assert_eq!(state::snapshot(&cpu, &bus), expected);
+
+    // Del frame 0 al 30: el 30 es el último grabado, después se acaba la película.
+    assert_eq!(movie.len(), 31);
+    frame(&mut movie, &mut cpu, &mut bus);
+    assert_eq!(movie.mode, Mode::Finished);
+}
+
+#[test]
+fn playback_detects_desync_at_next_sync_point() {
+    let (movie, _, _) = record(20);
+    let mut movie = Movie::load(&movie.save()).unwrap();
+    let (mut cpu, mut bus) = machine();
+    movie.play(&mut cpu, &mut bus).unwrap();
+    for _ in 0..5 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    // B no lo usa la ROM: el cambio solo lo ve el hash (cada 4 frames, el siguiente es el 8).
+    cpu.regs.b = 0x42;
+    for _ in 0..10 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    assert_eq!(movie.desync, Some(8));
+}
+
+#[test]
+fn rerecord_truncates_and_stays_in_sync() {
+    let (mut movie, mut cpu, mut bus) = record(10);
+    let checkpoint = movie.checkpoint(&cpu, &bus);
+    // Input que se va a descartar.
+    movie.set_button(Button::A, true);
+    for _ in 0..10 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    assert_eq!(movie.len(), 21);
+
+    movie.rerecord(&mut cpu, &mut bus, &checkpoint).unwrap();
+    assert_eq!((movie.frame(), movie.len(), movie.rerecords), (10, 11, 1));
+    assert_eq!(movie.mode, Mode::Recording);
+    movie.set_button(Button::Left, true);
+    for _ in 0..10 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    let expected = state::snapshot(&cpu, &bus);
+
+    // Reproducida desde el principio coincide con la rama nueva.
+    let mut movie = Movie::load(&movie.save()).unwrap();
+    assert_eq!(movie.rerecords, 1);
+    let (mut cpu, mut bus) = machine();
+    movie.play(&mut cpu, &mut bus).unwrap();
+    for _ in 0..20 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    assert_eq!(movie.desync, None);
+    assert_eq!(state::snapshot(&cpu, &bus), expected);
+    assert!(bus.joypad.is_pressed(Button::Left) && !bus.joypad.is_pressed(Button::A));
+}
+
+#[test]
+fn movie_from_save_state_embeds_start() {
+    let (mut cpu, mut bus) = machine();
+    for _ in 0..3 {
+        common::frame(&mut cpu, &mut bus);
+    }
+    let mut movie = Movie::record(&cpu, &mut bus, true, 1);
+    movie.set_button(Button::Up, true);
+    for _ in 0..5 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    let expected = state::snapshot(&cpu, &bus);
+
+    // Se reproduce sobre una máquina recién encendida: el estado incrustado manda.
+    let mut movie = Movie::load(&movie.save()).unwrap();
+    assert!(movie.start_state.is_some());
+    let (mut cpu, mut bus) = machine();
+    movie.play(&mut cpu, &mut bus).unwrap();
+    for _ in 0..5 {
+        frame(&mut movie, &mut cpu, &mut bus);
+    }
+    assert_eq!(movie.desync, None);
+    assert_eq!(state::snapshot(&cpu, &bus), expected);
+}
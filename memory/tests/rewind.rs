@@ -0,0 +1,70 @@
+// memory/tests/rewind.rs
+
+//! Rewind: reconstrucción exacta hacia atrás y límites de profundidad.
+
+use cpu::Cpu;
+use memory::Bus;
+use memory::rewind::{Depth, Rewind};
+use memory::state;
+
+mod common;
+use common::frame;
+
+/// Bucle que escribe un contador creciente en WRAM: cada frame cambia la memoria.
+fn machine() -> (Cpu, Bus) {
+    common::mbc1(&[
+        0x21, 0x00, 0xC0, // ld hl, $C000
+        0x3C,             // .loop: inc a
+        0x22,             // ld [hl+], a
+        0xCB, 0xAC,       // res 5, h   (al llegar a $E000 vuelve a $C000)
+        0x18, 0xFA,       // jr .loop
+    ])
+}
+
+#[test]
+fn rewinds_through_every_snapshot_in_reverse() {
+    let (mut cpu, mut bus) = machine();
+    let mut rewind = Rewind::new(2, Depth::Seconds(60));
+    let mut expected = Vec::new();
+    for i in 1..=20 {
+        frame(&mut cpu, &mut bus);
+        rewind.end_frame(&cpu, &bus);
+        if i % 2 == 0 {
+            expected.push(state::snapshot(&cpu, &bus));
+        }
+    }
+    assert_eq!(rewind.len(), 10);
+    // Las diferencias comprimidas ocupan mucho menos que los snapshots enteros.
+    assert!(rewind.memory_usage() < expected[0].len() * 2);
+
+    frame(&mut cpu, &mut bus);
+    while let Some(snapshot) = expected.pop() {
+        assert!(rewind.rewind(&mut cpu, &mut bus));
+        assert_eq!(state::snapshot(&cpu, &bus), snapshot);
+    }
+    assert!(rewind.is_empty());
+    assert!(!rewind.rewind(&mut cpu, &mut bus));
+}
+
+#[test]
+fn depth_is_bounded_by_seconds_or_megabytes() {
+    let (mut cpu, mut bus) = machine();
+    let mut by_time = Rewind::new(1, Depth::Seconds(1));
+    let mut by_size = Rewind::new(1, Depth::Megabytes(1));
+    for _ in 0..80 {
+        frame(&mut cpu, &mut bus);
+        by_time.end_frame(&cpu, &bus);
+        by_size.end_frame(&cpu, &bus);
+    }
+    assert_eq!(by_time.len(), 60);
+    assert!(by_size.memory_usage() <= 1024 * 1024);
+    assert!(by_size.len() > 1);
+
+    // Límites absurdos desde la línea de comandos: equivalen a "sin límite".
+    let mut huge = Rewind::new(1, Depth::Seconds(u32::MAX));
+    for _ in 0..3 {
+        frame(&mut cpu, &mut bus);
+        huge.end_frame(&cpu, &bus);
+    }
+    assert_eq!(huge.len(), 3);
+}
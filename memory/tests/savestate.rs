@@ -0,0 +1,81 @@
+// memory/tests/savestate.rs
+
+//! Save states de la máquina completa: ida y vuelta exacta, ROM equivocada y estados corruptos.
+
+use cpu::Cpu;
+use mbc::Mbc1;
+use memory::Bus;
+use memory::state;
+use savestate::StateError;
+
+mod common;
+use common::{boot, rom_with_code, run};
+
+/// MBC1 con un bucle que escribe en WRAM y en la RAM del cartucho mientras la GPU y el timer avanzan.
+fn machine(seed: u8) -> (Cpu, Bus) {
+    let mut rom = rom_with_code(0x10000, &[
+        0x3E, 0x0A, 0xEA, 0x00, 0x00, // ld a, $0A ; ld [$0000], a   (habilita la RAM)
+        0x3E, 0x02, 0xEA, 0x00, 0x20, // ld a, 2 ; ld [$2000], a     (banco 2)
+        0x21, 0x00, 0xA0,             // ld hl, $A000
+        0x3C,                         // .loop: inc a
+        0x22,                         // ld [hl+], a
+        0xE0, 0x80,                   // ldh [$80], a
+        0x18, 0xFA,                   // jr .loop
+    ]);
+    rom[0x0150] = seed;
+    let (cpu, mut bus) = boot(Box::new(Mbc1::new(rom)));
+    bus.write(0xFF07, 0x05); // Timer a 262KHz
+    (cpu, bus)
+}
+
+#[test]
+fn restoring_a_state_replays_identically() {
+    let (mut cpu, mut bus) = machine(0);
+    run(&mut cpu, &mut bus, 5000);
+    let saved = state::save(&cpu, &bus);
+
+    run(&mut cpu, &mut bus, 20000);
+    let expected = state::save(&cpu, &bus);
+
+    state::load(&mut cpu, &mut bus, &saved).unwrap();
+    assert_eq!(state::save(&cpu, &bus), saved);
+    run(&mut cpu, &mut bus, 20000);
+    assert_eq!(state::save(&cpu, &bus), expected);
+    assert_eq!(bus.cartridge.rom_bank(), 2);
+}
+
+#[test]
+fn header_has_rom_crc_and_thumbnail() {
+    let (cpu, bus) = machine(0);
+    let header = state::read_header(&state::save(&cpu, &bus)).unwrap();
+    assert_eq!(header.rom_crc32, savestate::crc32(bus.cartridge.rom()));
+    assert_eq!((header.thumbnail_width, header.thumbnail_height), (80, 72));
+    assert_eq!(header.thumbnail.len(), 80 * 72 * 3);
+}
+
+#[test]
+fn states_from_another_rom_are_rejected() {
+    let (cpu, bus) = machine(0);
+    let saved = state::save(&cpu, &bus);
+    let (mut other_cpu, mut other_bus) = machine(1);
+    let result = state::load(&mut other_cpu, &mut other_bus, &saved);
+    assert!(matches!(result, Err(StateError::RomMismatch { .. })));
+}
+
+#[test]
+fn corrupt_states_leave_the_machine_untouched() {
+    let (mut cpu, mut bus) = machine(0);
+    run(&mut cpu, &mut bus, 1000);
+    let saved = state::save(&cpu, &bus);
+    run(&mut cpu, &mut bus, 1000);
+    let before = state::save(&cpu, &bus);
+
+    let truncated = &saved[..saved.len() - 100];
+    assert_eq!(state::load(&mut cpu, &mut bus, truncated).unwrap_err(), StateError::Truncated);
+    assert_eq!(state::save(&cpu, &bus), before);
+
+    let mut longer = saved.clone();
+    longer.push(0);
+    assert!(matches!(state::load(&mut cpu, &mut bus, &longer), Err(StateError::Invalid(_))));
+    assert_eq!(state::save(&cpu, &bus), before);
+}
@@ -0,0 +1,48 @@
+// memory/tests/stop_mode.rs
+
+//! STOP detiene CPU, LCD y Timer hasta que una línea del Joypad baja.
+
+use joypad::Button;
+
+mod common;
+use common::rom_only;
+
+#[test]
+fn stop_sleeps_until_a_button_is_pressed() {
+    let (mut cpu, mut bus) = rom_only(&[0x10, 0x00, 0x3C]); // STOP ; INC A
+    bus.write(0xFF00, 0x00); // Seleccionar ambas filas del Joypad
+    let a = cpu.regs.a;
+    let ly = bus.gpu.ly;
+
+    cpu.step(&mut bus);
+    assert!(cpu.stopped);
+    assert_eq!(bus.div, 0); // STOP resetea DIV
+
+    for _ in 0..1000 {
+        cpu.step(&mut bus);
+    }
+    assert_eq!(cpu.regs.a, a);
+    assert_eq!(bus.div, 0); // El Timer está detenido
+    assert_eq!(bus.gpu.ly, ly); // El LCD también
+
+    bus.joypad.key_down(Button::Start);
+    cpu.step(&mut bus); // Despierta y ejecuta INC A
+    assert!(!cpu.stopped);
+    assert_eq!(cpu.regs.a, a.wrapping_add(1));
+    assert_eq!(cpu.regs.pc, 0x0103);
+}
+
+#[test]
+fn stop_with_button_held_enters_halt_instead() {
+    let (mut cpu, mut bus) = rom_only(&[0x10, 0x00, 0x3C]);
+    bus.write(0xFF00, 0x00);
+    bus.joypad.key_down(Button::A);
+    let div = bus.div;
+
+    cpu.step(&mut bus);
+    assert!(!cpu.stopped);
+    assert!(cpu.halted);
+    assert_eq!(cpu.regs.pc, 0x0102); // STOP de 2 bytes
+    assert_ne!(bus.div, 0);
+    assert!(bus.div >= div);
+}
@@ -0,0 +1,222 @@
+// savestate/src/lib.rs
+
+//! Formato binario de los save states.
+//!
+//! ```text
+//! "GBSTATE\0"  magic
+//! u32          versión del formato
+//! str          versión del emulador que lo escribió
+//! u32          CRC32 de la ROM
+//! u16 u16      ancho y alto de la miniatura, seguidos de ancho*alto*3 bytes RGB
+//! ...          cuerpo: cada componente escribe sus campos en orden fijo
+//! ```
+//!
+//! Todo en little-endian. No hay nombres de campo ni tipos en el cuerpo: el orden lo
+//! fija el código y se versiona con `VERSION`. Cuando un componente cambia sus campos
+//! se sube `VERSION` y su `load_state` consulta `Reader::version` para leer también
+//! el formato anterior (migración). Versiones futuras o desconocidas se rechazan.
+//!
+//! Concepto Rust vs Go:
+//! `Snapshot` es como implementar `encoding.BinaryMarshaler` en cada struct, pero
+//! sin reflexión: cada crate escribe sus campos (también los privados) a mano.
+
+use std::fmt;
+
+/// Firma al inicio de todo save state.
+pub const MAGIC: &[u8; 8] = b"GBSTATE\0";
+
+/// Versión actual del formato.
+/// - 1: formato inicial.
+/// - 2: la GPU cuenta dots por línea y guarda el pipeline del pixel FIFO.
+/// - 3: contador de líneas de la ventana y WY latcheado.
+/// - 4: línea de interrupción STAT (flanco de subida).
+pub const VERSION: u32 = 4;
+
+/// Versión más antigua que todavía se sabe migrar.
+pub const MIN_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// No empieza por `MAGIC`: no es un save state.
+    NotAState,
+    /// Versión del formato que este emulador no sabe leer.
+    UnsupportedVersion(u32),
+    /// El estado es de otra ROM.
+    RomMismatch { expected: u32, found: u32 },
+    /// Se acabaron los datos antes de tiempo.
+    Truncated,
+    /// Un valor no tiene sentido (tamaño de RAM distinto, etc.).
+    Invalid(String),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NotAState => write!(f, "no es un save state"),
+            StateError::UnsupportedVersion(v) => {
+                write!(f, "versión de save state {} no soportada (se leen de la {} a la {})", v, MIN_VERSION, VERSION)
+            }
+            StateError::RomMismatch { expected, found } => {
+                write!(f, "el save state es de otra ROM (CRC32 {:08X}, la cargada es {:08X})", found, expected)
+            }
+            StateError::Truncated => write!(f, "save state truncado"),
+            StateError::Invalid(msg) => write!(f, "save state inválido: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// Componente que sabe guardarse y restaurarse.
+pub trait Snapshot {
+    fn save_state(&self, w: &mut Writer);
+    fn load_state(&mut self, r: &mut Reader) -> Result<(), StateError>;
+}
+
+// =========================================================================
+//  ESCRITURA
+// =========================================================================
+
+#[derive(Default)]
+pub struct Writer {
+    pub buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u8(&mut self, v: u8) { self.buf.push(v); }
+    pub fn bool(&mut self, v: bool) { self.buf.push(v as u8); }
+    pub fn u16(&mut self, v: u16) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn u32(&mut self, v: u32) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+    pub fn u64(&mut self, v: u64) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+
+    /// Bloque de tamaño fijo (VRAM, WRAM...): sin longitud delante.
+    pub fn bytes(&mut self, v: &[u8]) { self.buf.extend_from_slice(v); }
+
+    /// Bloque de tamaño variable (RAM del cartucho): con su longitud delante.
+    pub fn blob(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.bytes(v);
+    }
+
+    pub fn str(&mut self, v: &str) { self.blob(v.as_bytes()); }
+}
+
+// =========================================================================
+//  LECTURA
+// =========================================================================
+
+#[derive(Debug)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Versión del formato que se está leyendo (para migrar estados antiguos).
+    pub version: u32,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8], version: u32) -> Self {
+        Reader { data, pos: 0, version }
+    }
+
+    /// Bytes aún sin leer.
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], StateError> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.data.len()).ok_or(StateError::Truncated)?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    // try_into: el slice tiene justo N bytes, así que la conversión a [u8; N] no falla.
+    pub fn u8(&mut self) -> Result<u8, StateError> { Ok(self.take(1)?[0]) }
+    pub fn bool(&mut self) -> Result<bool, StateError> { Ok(self.u8()? != 0) }
+    pub fn u16(&mut self) -> Result<u16, StateError> { Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap())) }
+    pub fn u32(&mut self) -> Result<u32, StateError> { Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap())) }
+    pub fn u64(&mut self) -> Result<u64, StateError> { Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap())) }
+
+    /// Rellena 'out' con un bloque de tamaño fijo.
+    pub fn bytes(&mut self, out: &mut [u8]) -> Result<(), StateError> {
+        out.copy_from_slice(self.take(out.len())?);
+        Ok(())
+    }
+
+    /// Bloque con longitud delante.
+    pub fn blob(&mut self) -> Result<&'a [u8], StateError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub fn str(&mut self) -> Result<String, StateError> {
+        let bytes = self.blob()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| StateError::Invalid("texto no UTF-8".to_string()))
+    }
+}
+
+// =========================================================================
+//  CABECERA
+// =========================================================================
+
+/// Cabecera de un save state: se puede leer sin cargar el estado (para listar slots).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    pub emulator: String,
+    pub rom_crc32: u32,
+    pub thumbnail_width: u16,
+    pub thumbnail_height: u16,
+    /// Píxeles RGB, fila a fila.
+    pub thumbnail: Vec<u8>,
+}
+
+impl Header {
+    pub fn write(&self, w: &mut Writer) {
+        w.bytes(MAGIC);
+        w.u32(self.version);
+        w.str(&self.emulator);
+        w.u32(self.rom_crc32);
+        w.u16(self.thumbnail_width);
+        w.u16(self.thumbnail_height);
+        w.bytes(&self.thumbnail);
+    }
+
+    /// Lee la cabecera y devuelve un `Reader` del cuerpo con la versión ya fijada.
+    pub fn read(data: &[u8]) -> Result<(Header, Reader<'_>), StateError> {
+        if !data.starts_with(MAGIC) {
+            return Err(StateError::NotAState);
+        }
+        let mut r = Reader::new(&data[MAGIC.len()..], 0);
+        let version = r.u32()?;
+        if !(MIN_VERSION..=VERSION).contains(&version) {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        r.version = version;
+        let emulator = r.str()?;
+        let rom_crc32 = r.u32()?;
+        let thumbnail_width = r.u16()?;
+        let thumbnail_height = r.u16()?;
+        let mut thumbnail = vec![0; thumbnail_width as usize * thumbnail_height as usize * 3];
+        r.bytes(&mut thumbnail)?;
+        let header = Header { version, emulator, rom_crc32, thumbnail_width, thumbnail_height, thumbnail };
+        Ok((header, r))
+    }
+}
+
+/// CRC32 (el de zip/PNG, polinomio 0xEDB88320). Identifica la ROM igual que No-Intro.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            // Si el bit bajo es 1, se aplica el polinomio (máscara sin ramas).
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
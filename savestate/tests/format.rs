@@ -0,0 +1,57 @@
+// savestate/tests/format.rs
+
+//! Formato binario: primitivas, cabecera y versiones.
+
+use savestate::{crc32, Header, Reader, StateError, Writer, MAGIC, VERSION};
+
+#[test]
+fn crc32_matches_the_standard_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
+
+#[test]
+fn primitives_round_trip_and_detect_truncation() {
+    let mut w = Writer::new();
+    w.u8(0x12);
+    w.bool(true);
+    w.u16(0x3456);
+    w.u32(0x789A_BCDE);
+    w.u64(u64::MAX - 1);
+    w.blob(&[1, 2, 3]);
+    w.str("hola");
+
+    let mut r = Reader::new(&w.buf, VERSION);
+    assert_eq!(r.u8(), Ok(0x12));
+    assert_eq!(r.bool(), Ok(true));
+    assert_eq!(r.u16(), Ok(0x3456));
+    assert_eq!(r.u32(), Ok(0x789A_BCDE));
+    assert_eq!(r.u64(), Ok(u64::MAX - 1));
+    assert_eq!(r.blob(), Ok(&[1u8, 2, 3][..]));
+    assert_eq!(r.str().as_deref(), Ok("hola"));
+    assert_eq!(r.remaining(), 0);
+    assert_eq!(r.u8(), Err(StateError::Truncated));
+}
+
+#[test]
+fn header_rejects_foreign_data_and_unknown_versions() {
+    let header = Header {
+        version: VERSION,
+        emulator: "0.1.0".to_string(),
+        rom_crc32: 0xDEAD_BEEF,
+        thumbnail_width: 2,
+        thumbnail_height: 1,
+        thumbnail: vec![1, 2, 3, 4, 5, 6],
+    };
+    let mut w = Writer::new();
+    header.write(&mut w);
+    w.u8(0x99); // Primer byte del cuerpo
+    let (read, mut body) = Header::read(&w.buf).unwrap();
+    assert_eq!(read, header);
+    assert_eq!(body.u8(), Ok(0x99));
+
+    assert_eq!(Header::read(b"no es un estado").unwrap_err(), StateError::NotAState);
+
+    let mut future = w.buf.clone();
+    future[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert_eq!(Header::read(&future).unwrap_err(), StateError::UnsupportedVersion(VERSION + 1));
+}
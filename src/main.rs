@@ -1,12 +1,24 @@
 use std::env; // Biblioteca estándar para interactuar con el entorno (similar al paquete "os" en Go)
 use std::fs;  // Biblioteca estándar para sistema de archivos ("io/ioutil" o "os" en Go)
 use std::process; // Para controlar el proceso del sistema (exit codes)
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::Ordering;
 
 // --- MODULOS ---
 // En Rust, estos 'use' traen items de otros crates (bibliotecas) al scope actual.
 // Es similar a los imports en Go, pero Rust es más explícito con la visibilidad.
 use cpu::Cpu;
+use cpu::profile::Profiler;
+use cpu::symbols::SymbolTable;
+use cpu::trace::Tracer;
 use memory::Bus;
+use memory::coverage::Coverage;
+use memory::movie::Movie;
+use memory::rewind::{Depth, Rewind};
+use debugger::Debugger;
+use gpu::Renderer;
 use mbc::new_cartridge;
 
 fn main() {
@@ -19,7 +31,7 @@ fn main() {
     // Verificación de longitud. 
     // Nota: args[0] es el nombre del ejecutable, igual que en C o Go.
     if args.len() < 2 {
-        eprintln!("Uso: {} <archivo_rom.gb>", args[0]);
+        eprintln!("Uso: {} <archivo_rom.gb> [--trace <archivo.log>] [--trace-ring <N>] [--trace-pc <inicio>-<fin>] [--trace-bank <n>] [--debug | --gdb <puerto>] [--sym <archivo.sym>] [--profile <archivo.folded>] [--coverage <archivo.cdl>] [--rewind-seconds <s> | --rewind-mb <mb>] [--rewind-interval <frames>] [--fast-ppu] [--record <archivo.gbm> [--from-state <archivo.state>] | --play <archivo.gbm>]", args[0]);
         process::exit(1);
     }
     
@@ -27,6 +39,16 @@ fn main() {
     // En Rust, intentamos no copiar strings si no es necesario.
     let filename = &args[1];
 
+    // Flags tras el nombre de la ROM. Cualquier error se informa aquí, una sola vez
+    // ('?' dentro de 'parse_args' lo propaga hasta este punto, como 'if err != nil { return err }' en Go).
+    let options = match parse_args(&args[2..]) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Error en los argumentos: {}", e);
+            process::exit(1);
+        }
+    };
+
     println!("Cargando ROM: {}", filename);
 
     // 2. Leer el archivo binario del disco
@@ -44,6 +66,7 @@ fn main() {
 
     // 'rom_data' es ahora dueño (owner) del vector de bytes.
     println!("Tamaño de ROM: {} bytes", rom_data.len());
+    let rom_len = rom_data.len();
 
     // 3. Ensamblaje de componentes (Hardware Wiring)
     
@@ -56,15 +79,324 @@ fn main() {
     // B. Insertar cartucho en el Bus de memoria
     // Movemos 'cartucho' dentro del Bus. 'main' pierde la posesión de 'cartucho'.
     // Si intentáramos usar 'cartucho' después de esta línea, el compilador daría error.
-    let bus = Bus::new(cartucho);
+    let mut bus = Bus::new(cartucho);
 
     // C. Conectar la CPU al sistema
-    let cpu = Cpu::new();
+    let mut cpu = Cpu::new();
+
+    // Símbolos de RGBDS: '--sym <archivo>', o el '.sym' junto a la ROM si existe.
+    let sym_path = options.sym_path.or_else(|| {
+        let path = Path::new(filename).with_extension("sym");
+        path.exists().then_some(path)
+    });
+    // Rc: la misma tabla la comparten el trace y el depurador (como un puntero compartido en Go).
+    let symbols = sym_path.and_then(|path| match SymbolTable::load(&path) {
+        Ok(table) => {
+            println!("{} símbolos cargados de {}", table.len(), path.display());
+            Some(Rc::new(table))
+        }
+        Err(e) => {
+            eprintln!("No se pudo leer {}: {}", path.display(), e);
+            None
+        }
+    });
+
+    cpu.profiler = options.profiler;
+    if let Some(profiler) = cpu.profiler.as_mut() {
+        profiler.symbols = symbols.clone();
+    }
+
+    // Cobertura: acumula código/datos sobre lo de sesiones anteriores.
+    if let Some(path) = options.coverage_path {
+        match Coverage::open(&path, rom_len) {
+            Ok(coverage) => bus.coverage = Some(coverage),
+            Err(e) => {
+                eprintln!("No se pudo abrir {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if options.fast_ppu {
+        bus.gpu.set_renderer(Renderer::Scanline);
+    }
+
+    cpu.tracer = options.tracer;
+    if let Some(tracer) = cpu.tracer.as_mut() {
+        tracer.symbols = symbols.clone();
+    }
+
+    // Con --debug o --gdb, el depurador toma el control en lugar de la ventana.
+    if let Some(mode) = options.debug {
+        let mut dbg = Debugger::new();
+        dbg.symbols = symbols;
+        let result = match mode {
+            DebugMode::Repl => {
+                // Ctrl-C para la ejecución ('c', 'n') y vuelve al prompt en lugar de matar el proceso.
+                let interrupt = dbg.interrupt.clone();
+                if let Err(e) = ctrlc::set_handler(move || interrupt.store(true, Ordering::Relaxed)) {
+                    eprintln!("Aviso: Ctrl-C no podrá parar la ejecución: {}", e);
+                }
+                debugger::repl::run(&mut dbg, &mut cpu, &mut bus, io::stdin().lock(), &mut io::stdout())
+            }
+            DebugMode::Gdb(port) => debugger::gdb::serve(&format!("127.0.0.1:{}", port), &mut dbg, &mut cpu, &mut bus),
+        };
+        if let Err(e) = result {
+            eprintln!("Error en el depurador: {}", e);
+        }
+        if let Some(tracer) = cpu.tracer.as_mut() {
+            let _ = tracer.flush();
+        }
+        if let Some(profiler) = cpu.profiler.as_mut() {
+            let _ = profiler.save();
+            let _ = profiler.write_report(&mut io::stdout(), 20);
+        }
+        if let Some(coverage) = bus.coverage.as_ref() {
+            if let Err(e) = coverage.save() {
+                eprintln!("No se pudo guardar la cobertura: {}", e);
+            }
+            print!("{}", coverage);
+        }
+        return;
+    }
 
     println!("Sistema ensamblado. Iniciando emulación...");
 
     // 4. Transferir control al sistema de Display (Bucle infinito)
     // El sistema de display manejará el bucle de eventos (input/render).
     // Le transferimos la propiedad (ownership) de 'cpu' y 'bus'.
-    display::run(cpu, bus);
-}
\ No newline at end of file
+    // Save state junto a la ROM: 'juego.gb' -> 'juego.state'.
+    let mut display_options = display::Options {
+        state_path: Some(Path::new(filename).with_extension("state")),
+        rewind: options.rewind,
+        ..Default::default()
+    };
+    if let Some(args) = options.movie {
+        match start_movie(args, &mut cpu, &mut bus) {
+            Ok((movie, path)) => {
+                // Volver atrás sin pasar por la película rompería la grabación.
+                display_options.rewind = None;
+                display_options.movie = Some(movie);
+                display_options.movie_path = path;
+            }
+            Err(e) => {
+                eprintln!("Error en la película: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    display::run(cpu, bus, display_options);
+}
+
+/// Opciones de la línea de comandos (todo lo que va tras el nombre de la ROM).
+struct Args {
+    /// `--debug` / `--gdb <puerto>`: el depurador en lugar de la ventana.
+    debug: Option<DebugMode>,
+    /// `--sym <archivo>`. Sin él se busca el `.sym` junto a la ROM.
+    sym_path: Option<PathBuf>,
+    /// `--profile <archivo>`: pilas en formato folded (flamegraph), guardadas al salir.
+    profiler: Option<Profiler>,
+    /// `--coverage <archivo.cdl>`.
+    coverage_path: Option<PathBuf>,
+    /// `--rewind-seconds`, `--rewind-mb`, `--rewind-interval`.
+    rewind: Option<Rewind>,
+    /// `--fast-ppu`: la GPU dibuja cada línea de golpe (más rápido, sin efectos a mitad de línea).
+    fast_ppu: bool,
+    /// `--record` / `--play`.
+    movie: Option<MovieArgs>,
+    /// `--trace*`.
+    tracer: Option<Tracer>,
+}
+
+/// Interpreta los flags. Cada grupo se separa del resto y lo que queda al final son los de trace.
+fn parse_args(flags: &[String]) -> Result<Args, String> {
+    let (debug, flags) = parse_debug_args(flags)?;
+    let (sym_path, flags) = take_value(flags, "--sym")?;
+    let (profile_path, flags) = take_value(flags, "--profile")?;
+    let profiler = match profile_path {
+        Some(path) => {
+            let file = fs::File::create(&path).map_err(|e| format!("{}: {}", path, e))?;
+            Some(Profiler::with_output(Box::new(BufWriter::new(file))))
+        }
+        None => None,
+    };
+    let (coverage_path, flags) = take_value(flags, "--coverage")?;
+    let (rewind, flags) = parse_rewind_args(flags)?;
+    let fast_ppu = flags.iter().any(|flag| flag == "--fast-ppu");
+    let flags: Vec<String> = flags.into_iter().filter(|flag| flag != "--fast-ppu").collect();
+    let (movie, flags) = parse_movie_args(flags)?;
+    // Las películas solo se graban/reproducen en la ventana: el depurador las ignoraría.
+    if debug.is_some() && movie.is_some() {
+        return Err("--record/--play no se pueden usar con --debug ni --gdb".to_string());
+    }
+    let tracer = parse_trace_args(&flags)?;
+
+    Ok(Args {
+        debug,
+        sym_path: sym_path.map(PathBuf::from),
+        profiler,
+        coverage_path: coverage_path.map(PathBuf::from),
+        rewind,
+        fast_ppu,
+        movie,
+        tracer,
+    })
+}
+
+/// Frames entre hashes de sincronización de las películas (~1 segundo).
+const MOVIE_SYNC_INTERVAL: u32 = 60;
+
+/// Qué hacer con la película.
+enum MovieArgs {
+    /// `--record <archivo> [--from-state <estado>]`
+    Record { path: PathBuf, from_state: Option<PathBuf> },
+    /// `--play <archivo>`
+    Play(PathBuf),
+}
+
+/// Separa `--record`, `--from-state` y `--play` del resto de flags.
+fn parse_movie_args(flags: Vec<String>) -> Result<(Option<MovieArgs>, Vec<String>), String> {
+    let (record, flags) = take_value(flags, "--record")?;
+    let (from_state, flags) = take_value(flags, "--from-state")?;
+    let (play, flags) = take_value(flags, "--play")?;
+    let args = match (record, from_state, play) {
+        (Some(_), _, Some(_)) => return Err("--record y --play son excluyentes".to_string()),
+        (None, Some(_), _) => return Err("--from-state solo tiene sentido con --record".to_string()),
+        (Some(path), from_state, None) => Some(MovieArgs::Record { path: path.into(), from_state: from_state.map(PathBuf::from) }),
+        (None, None, Some(path)) => Some(MovieArgs::Play(path.into())),
+        (None, None, None) => None,
+    };
+    Ok((args, flags))
+}
+
+/// Prepara la película sobre la máquina recién encendida. Devuelve también dónde guardarla al salir.
+fn start_movie(args: MovieArgs, cpu: &mut Cpu, bus: &mut Bus) -> Result<(Movie, Option<PathBuf>), String> {
+    match args {
+        MovieArgs::Record { path, from_state } => {
+            if let Some(state) = &from_state {
+                let data = fs::read(state).map_err(|e| format!("{}: {}", state.display(), e))?;
+                memory::state::load(cpu, bus, &data).map_err(|e| format!("{}: {}", state.display(), e))?;
+            }
+            println!("Grabando película en {}", path.display());
+            Ok((Movie::record(cpu, bus, from_state.is_some(), MOVIE_SYNC_INTERVAL), Some(path)))
+        }
+        MovieArgs::Play(path) => {
+            let data = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let mut movie = Movie::load(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+            movie.play(cpu, bus).map_err(|e| format!("{}: {}", path.display(), e))?;
+            println!("Reproduciendo {} ({} frames, {} rerecords)", path.display(), movie.len(), movie.rerecords);
+            // Si se vuelve a grabar desde un checkpoint, se sobrescribe el mismo archivo.
+            Ok((movie, Some(path)))
+        }
+    }
+}
+
+/// Cómo se depura en lugar de abrir la ventana.
+enum DebugMode {
+    /// `--debug`: REPL en la terminal.
+    Repl,
+    /// `--gdb <puerto>`: servidor GDB en 127.0.0.1.
+    Gdb(u16),
+}
+
+/// Separa `--debug` / `--gdb <puerto>` del resto de flags.
+fn parse_debug_args(flags: &[String]) -> Result<(Option<DebugMode>, Vec<String>), String> {
+    let mut mode = None;
+    let mut rest = Vec::new();
+    // 'iter()' manual: '--gdb' consume también el siguiente argumento.
+    let mut iter = flags.iter();
+    while let Some(flag) = iter.next() {
+        match flag.as_str() {
+            "--debug" => mode = Some(DebugMode::Repl),
+            "--gdb" => {
+                let port = iter.next().ok_or("falta el puerto de --gdb")?;
+                let port = port.parse().map_err(|_| format!("--gdb: puerto inválido {}", port))?;
+                mode = Some(DebugMode::Gdb(port));
+            }
+            _ => rest.push(flag.clone()),
+        }
+    }
+    Ok((mode, rest))
+}
+
+/// Extrae `<nombre> <valor>` de la lista de flags. Devuelve el valor (si estaba) y el resto.
+fn take_value(flags: Vec<String>, name: &str) -> Result<(Option<String>, Vec<String>), String> {
+    let mut value = None;
+    let mut rest = Vec::new();
+    let mut iter = flags.into_iter();
+    while let Some(flag) = iter.next() {
+        if flag == name {
+            value = Some(iter.next().ok_or(format!("falta el valor de {}", name))?);
+        } else {
+            rest.push(flag);
+        }
+    }
+    Ok((value, rest))
+}
+
+/// Rewind: activado por defecto (20 segundos, snapshot cada 2 frames).
+/// `--rewind-seconds 0` lo desactiva.
+fn parse_rewind_args(flags: Vec<String>) -> Result<(Option<Rewind>, Vec<String>), String> {
+    let (seconds, flags) = take_value(flags, "--rewind-seconds")?;
+    let (megabytes, flags) = take_value(flags, "--rewind-mb")?;
+    let (interval, flags) = take_value(flags, "--rewind-interval")?;
+
+    let number = |name: &str, value: String| value.parse::<u32>().map_err(|_| format!("{} inválido: {}", name, value));
+    let depth = match (seconds, megabytes) {
+        (Some(_), Some(_)) => return Err("--rewind-seconds y --rewind-mb son excluyentes".to_string()),
+        (Some(s), None) => Depth::Seconds(number("--rewind-seconds", s)?),
+        (None, Some(mb)) => Depth::Megabytes(number("--rewind-mb", mb)? as usize),
+        (None, None) => Depth::Seconds(20),
+    };
+    let interval = match interval {
+        Some(frames) => number("--rewind-interval", frames)?,
+        None => 2,
+    };
+    let rewind = match depth {
+        Depth::Seconds(0) | Depth::Megabytes(0) => None,
+        depth => Some(Rewind::new(interval, depth)),
+    };
+    Ok((rewind, flags))
+}
+
+/// Interpreta los flags de trace. Devuelve None si no se pidió ninguno.
+/// - `--trace <archivo>`: escribe todas las líneas en el archivo.
+/// - `--trace-ring <N>`: guarda las últimas N líneas y las vuelca si la CPU se cuelga.
+/// - `--trace-pc <inicio>-<fin>`: solo PC en ese rango (hex, ej: `0150-01FF`).
+/// - `--trace-bank <n>`: solo instrucciones de ese banco de ROM.
+fn parse_trace_args(flags: &[String]) -> Result<Option<Tracer>, String> {
+    let mut output = None;
+    let mut ring = 0;
+    let mut pc_range = None;
+    let mut bank = None;
+
+    // 'chunks(2)': recorremos los flags de dos en dos (nombre, valor).
+    for pair in flags.chunks(2) {
+        let value = pair.get(1).ok_or(format!("falta el valor de {}", pair[0]))?;
+        match pair[0].as_str() {
+            "--trace" => {
+                let file = fs::File::create(value).map_err(|e| format!("{}: {}", value, e))?;
+                output = Some(file);
+            }
+            "--trace-ring" => ring = value.parse().map_err(|_| format!("--trace-ring inválido: {}", value))?,
+            "--trace-pc" => {
+                let (start, end) = value.split_once('-').ok_or(format!("--trace-pc espera inicio-fin: {}", value))?;
+                let hex = |s: &str| u16::from_str_radix(s.trim_start_matches("0x"), 16).map_err(|_| format!("dirección inválida: {}", s));
+                pc_range = Some(hex(start)?..=hex(end)?);
+            }
+            "--trace-bank" => bank = Some(value.parse().map_err(|_| format!("--trace-bank inválido: {}", value))?),
+            other => return Err(format!("flag desconocido: {}", other)),
+        }
+    }
+
+    let mut tracer = match output {
+        Some(file) => Tracer::with_output(Box::new(BufWriter::new(file))),
+        None if ring > 0 => Tracer::ring(ring),
+        // Filtros sin destino: no hay nada que trazar.
+        None => return Ok(None),
+    };
+    tracer.set_ring_capacity(ring);
+    tracer.pc_range = pc_range;
+    tracer.bank = bank;
+    Ok(Some(tracer))
+}
@@ -0,0 +1,320 @@
+// tests/test_roms.rs
+
+//! Arnés de test ROMs (Blargg, Mooneye, dmg-acid2).
+//!
+//! Ejecuta cada ROM sin ventana (headless) y decide automáticamente si pasó:
+//! - Blargg (`blargg/`): texto "Passed"/"Failed" por el puerto serie o en pantalla.
+//! - Mooneye (`mooneye/`): al ejecutar `LD B,B` los registros B,C,D,E,H,L
+//!   contienen la serie de Fibonacci 3,5,8,13,21,34 si el test pasó.
+//! - dmg-acid2 (`dmg-acid2/`): hash del framebuffer comparado con `<rom>.hash`.
+//!
+//! Las ROMs no se incluyen en el repositorio. Se buscan en `tests/roms/`
+//! (o en la carpeta indicada por `GB_TEST_ROMS`). Si no hay ROMs, los tests
+//! solo escriben el reporte vacío.
+//!
+//! El reporte por ROM (tabla Markdown) se escribe en `target/test-roms-report.md`
+//! o en la ruta de `GB_TEST_REPORT`, para seguir la compatibilidad en el tiempo.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cpu::{Cpu, CpuEvent};
+use memory::Bus;
+use mbc::new_cartridge;
+
+/// Un frame son 70224 T-Cycles = 17556 M-Cycles.
+const M_CYCLES_PER_FRAME: u32 = 17556;
+
+/// ROMs que ya pasan (ruta relativa a la carpeta de ROMs).
+/// Si una de ellas está presente y falla, el test falla: es una regresión.
+/// El resto solo se anota en el reporte.
+/// Solo entran ROMs que se hayan ejecutado y pasado de verdad. Las rutas de Mooneye
+/// siguen la estructura de su release (`acceptance/ppu/...`).
+const EXPECTED_PASS: &[&str] = &[];
+
+/// Resultado de ejecutar una ROM.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Pass,
+    Fail(String),
+    Timeout,
+}
+
+/// Criterio de éxito según la suite.
+#[derive(Clone, Copy)]
+enum Suite {
+    Blargg,
+    Mooneye,
+    Acid2,
+}
+
+impl Suite {
+    fn name(self) -> &'static str {
+        match self {
+            Suite::Blargg => "Blargg",
+            Suite::Mooneye => "Mooneye",
+            Suite::Acid2 => "dmg-acid2",
+        }
+    }
+
+    /// Límite de frames antes de declarar Timeout.
+    /// cpu_instrs completo tarda ~55 segundos emulados.
+    fn frame_limit(self) -> u32 {
+        match self {
+            Suite::Blargg => 60 * 120,
+            Suite::Mooneye => 60 * 20,
+            Suite::Acid2 => 60,
+        }
+    }
+}
+
+/// Máquina headless: el mismo cableado que `main.rs`, sin ventana.
+struct Machine {
+    cpu: Cpu,
+    bus: Bus,
+    // Primer evento de la CPU (opcode ilegal): el test ya falló.
+    event: Option<CpuEvent>,
+}
+
+impl Machine {
+    fn new(rom: Vec<u8>) -> Self {
+        Self { cpu: Cpu::new(), bus: Bus::new(new_cartridge(rom)), event: None }
+    }
+
+    /// Ejecuta una instrucción y avanza los periféricos.
+    /// Retorna (M-Cycles, ejecutó `LD B,B`).
+    fn step(&mut self) -> (u32, bool) {
+        let pc = self.cpu.regs.pc;
+        let opcode = self.bus.read(pc);
+        // La CPU avanza GPU y Timer en cada M-Cycle a través del bus.
+        let m_cycles = self.cpu.step(&mut self.bus);
+        if let Some(event @ CpuEvent::IllegalOpcode { .. }) = self.cpu.take_event() {
+            self.event.get_or_insert(event);
+        }
+        // Si se despachó una interrupción, PC no avanzó una posición.
+        let ld_b_b = opcode == 0x40 && self.cpu.regs.pc == pc.wrapping_add(1);
+        (m_cycles, ld_b_b)
+    }
+
+    /// Ejecuta un frame completo. Retorna true si se ejecutó `LD B,B` (y se detuvo ahí).
+    fn run_frame(&mut self) -> bool {
+        let mut cycles = 0;
+        while cycles < M_CYCLES_PER_FRAME {
+            let (m_cycles, ld_b_b) = self.step();
+            if ld_b_b { return true; }
+            cycles += m_cycles;
+        }
+        false
+    }
+
+    /// Texto de la consola de Blargg: su fuente carga los tiles de modo que
+    /// el ID de tile coincide con el código ASCII. Leemos el mapa 0x9800.
+    fn screen_text(&self) -> String {
+        let mut text = String::new();
+        for row in 0..32 {
+            for col in 0..32 {
+                let tile = self.bus.gpu.vram[0x1800 + row * 32 + col];
+                text.push(if (0x20..0x7F).contains(&tile) { tile as char } else { ' ' });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Registros Fibonacci de Mooneye.
+    fn mooneye_result(&self) -> Outcome {
+        let r = &self.cpu.regs;
+        let regs = [r.b, r.c, r.d, r.e, r.h, r.l];
+        if regs == [3, 5, 8, 13, 21, 34] {
+            Outcome::Pass
+        } else {
+            Outcome::Fail(format!("B={:02X} C={:02X} D={:02X} E={:02X} H={:02X} L={:02X}",
+                r.b, r.c, r.d, r.e, r.h, r.l))
+        }
+    }
+
+    /// Hash FNV-1a de los tonos (0-3) del framebuffer.
+    /// Hasheamos el tono y no el RGBA para no depender de la paleta verde elegida.
+    fn frame_hash(&self) -> u64 {
+        let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+        for pixel in self.bus.gpu.frame_buffer.chunks(4) {
+            let shade: u8 = match pixel[0] {
+                0x9B => 0,
+                0x8B => 1,
+                0x30 => 2,
+                _ => 3,
+            };
+            hash ^= shade as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+        hash
+    }
+}
+
+/// Busca "Passed"/"Failed" en un texto de Blargg.
+fn blargg_verdict(text: &str) -> Option<Outcome> {
+    if text.contains("Passed") {
+        Some(Outcome::Pass)
+    } else if text.contains("Failed") {
+        // La línea anterior suele decir qué falló ("Failed #3", "Failed 2 tests").
+        let detail = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        Some(Outcome::Fail(detail))
+    } else {
+        None
+    }
+}
+
+fn run_rom(suite: Suite, path: &Path) -> Outcome {
+    let rom = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) => return Outcome::Fail(format!("no se pudo leer: {}", e)),
+    };
+    let mut gb = Machine::new(rom);
+
+    for _ in 0..suite.frame_limit() {
+        let ld_b_b = gb.run_frame();
+        if let Some(CpuEvent::IllegalOpcode { pc, opcode }) = gb.event {
+            return Outcome::Fail(format!("opcode ilegal {:#04X} en {:#06X}", opcode, pc));
+        }
+        match suite {
+            Suite::Blargg => {
+                let serial = String::from_utf8_lossy(&gb.bus.serial_output).into_owned();
+                if let Some(outcome) = blargg_verdict(&serial).or_else(|| blargg_verdict(&gb.screen_text())) {
+                    return outcome;
+                }
+            }
+            Suite::Mooneye => {
+                if ld_b_b { return gb.mooneye_result(); }
+            }
+            Suite::Acid2 => {
+                // dmg-acid2 también termina con LD B,B, pero la imagen se compara
+                // tras el frame siguiente, cuando ya está en el framebuffer.
+                if ld_b_b { gb.run_frame(); break; }
+            }
+        }
+    }
+
+    match suite {
+        Suite::Acid2 => {
+            let hash = format!("{:016X}", gb.frame_hash());
+            match fs::read_to_string(path.with_extension("hash")) {
+                Ok(expected) if expected.trim().eq_ignore_ascii_case(&hash) => Outcome::Pass,
+                Ok(expected) => Outcome::Fail(format!("hash {} (esperado {})", hash, expected.trim())),
+                Err(_) => Outcome::Fail(format!("hash {} (sin archivo .hash de referencia)", hash)),
+            }
+        }
+        _ => Outcome::Timeout,
+    }
+}
+
+/// Recolecta recursivamente los `.gb` de una carpeta, ordenados.
+fn collect_roms(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_roms(&path, out);
+        } else if path.extension().is_some_and(|ext| ext == "gb") {
+            out.push(path);
+        }
+    }
+    out.sort();
+}
+
+fn roms_dir() -> PathBuf {
+    match env::var("GB_TEST_ROMS") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms"),
+    }
+}
+
+fn report_path() -> PathBuf {
+    match env::var("GB_TEST_REPORT") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("target/test-roms-report.md"),
+    }
+}
+
+#[test]
+fn test_rom_suites() {
+    let root = roms_dir();
+    let mut rows = Vec::new();
+    let mut regressions = Vec::new();
+    let mut passed = 0;
+
+    for (suite, folder) in [(Suite::Blargg, "blargg"), (Suite::Mooneye, "mooneye"), (Suite::Acid2, "dmg-acid2")] {
+        let mut roms = Vec::new();
+        collect_roms(&root.join(folder), &mut roms);
+
+        for path in roms {
+            let name = path.strip_prefix(&root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            let outcome = run_rom(suite, &path);
+            let (status, detail) = match &outcome {
+                Outcome::Pass => { passed += 1; ("✅ Pass", String::new()) }
+                Outcome::Fail(detail) => ("❌ Fail", detail.clone()),
+                Outcome::Timeout => ("⏱️ Timeout", String::new()),
+            };
+            if outcome != Outcome::Pass && EXPECTED_PASS.contains(&name.as_str()) {
+                regressions.push(name.clone());
+            }
+            rows.push(format!("| {} | `{}` | {} | {} |", suite.name(), name, status, detail.replace('|', "/")));
+        }
+    }
+
+    let mut report = String::from("# Compatibilidad con test ROMs\n\n");
+    report += &format!("{} / {} ROMs pasan.\n\n", passed, rows.len());
+    report += "| Suite | ROM | Resultado | Detalle |\n|---|---|---|---|\n";
+    for row in &rows {
+        report += row;
+        report += "\n";
+    }
+
+    let path = report_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(&path, report).unwrap();
+    println!("Reporte escrito en {} ({} / {} pasan)", path.display(), passed, rows.len());
+
+    assert!(regressions.is_empty(), "ROMs que antes pasaban ahora fallan: {:?}", regressions);
+}
+
+// -------------------------------------------------------------------------
+//  Autoverificación del arnés con ROMs sintéticas (siempre se ejecutan).
+// -------------------------------------------------------------------------
+
+/// Crea una ROM de 32KB (ROM ONLY) con 'code' en el punto de entrada 0x0100.
+fn synthetic_rom(code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0100 + code.len()].copy_from_slice(code);
+    rom
+}
+
+#[test]
+fn harness_reads_blargg_serial_output() {
+    let mut code = Vec::new();
+    for &byte in b"Passed\n" {
+        code.extend_from_slice(&[0x3E, byte, 0xE0, 0x01]); // LD A,n ; LDH (SB),A
+        code.extend_from_slice(&[0x3E, 0x81, 0xE0, 0x02]); // LD A,$81 ; LDH (SC),A
+    }
+    code.extend_from_slice(&[0x18, 0xFE]); // JR -2 (bucle infinito)
+
+    let mut gb = Machine::new(synthetic_rom(&code));
+    gb.run_frame();
+    let serial = String::from_utf8_lossy(&gb.bus.serial_output).into_owned();
+    assert_eq!(serial, "Passed\n");
+    assert_eq!(blargg_verdict(&serial), Some(Outcome::Pass));
+}
+
+#[test]
+fn harness_detects_mooneye_fibonacci() {
+    let code = [
+        0x06, 3, 0x0E, 5, 0x16, 8, 0x1E, 13, 0x26, 21, 0x2E, 34, // LD B..L, Fibonacci
+        0x40,       // LD B,B
+        0x18, 0xFE, // JR -2
+    ];
+    let mut gb = Machine::new(synthetic_rom(&code));
+    assert!(gb.run_frame());
+    assert_eq!(gb.mooneye_result(), Outcome::Pass);
+}